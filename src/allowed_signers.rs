@@ -0,0 +1,99 @@
+//! Maintenance of `~/.ssh/allowed_signers`, the file git uses to verify SSH signatures.
+//!
+//! ssv owns one line per managed host, tagged with an `ssv:<HOST>` comment so that lines written
+//! by hand are never touched.
+
+use crate::error::AppError;
+use crate::secure_file;
+use crate::ssh_paths::SshPaths;
+use std::fs;
+use std::io::ErrorKind;
+
+/// Add `principal` for a host's key, keeping principals already recorded for that host.
+pub(crate) fn upsert(
+    paths: &SshPaths,
+    host: &str,
+    principal: &str,
+    public_key: &str,
+) -> Result<(), AppError> {
+    let key = key_fields(public_key)?;
+    let mut lines = read(paths)?;
+    let mut principals: Vec<String> = lines
+        .iter()
+        .filter(|line| is_managed(line, host))
+        .flat_map(|line| line.split_whitespace().next().unwrap_or_default().split(','))
+        .map(str::to_string)
+        .collect();
+    if !principals.iter().any(|existing| existing == principal) {
+        principals.push(principal.to_string());
+    }
+
+    lines.retain(|line| !is_managed(line, host));
+    lines.push(render(&principals.join(","), &key, host));
+    write(paths, &lines)
+}
+
+/// Point a host's entry at its rotated key, keeping the principals. Returns whether one existed.
+pub(crate) fn replace_key(
+    paths: &SshPaths,
+    host: &str,
+    public_key: &str,
+) -> Result<bool, AppError> {
+    let key = key_fields(public_key)?;
+    let mut lines = read(paths)?;
+    let mut replaced = false;
+    for line in lines.iter_mut().filter(|line| is_managed(line, host)) {
+        let principals = line.split_whitespace().next().unwrap_or_default().to_string();
+        *line = render(&principals, &key, host);
+        replaced = true;
+    }
+    if replaced {
+        write(paths, &lines)?;
+    }
+    Ok(replaced)
+}
+
+/// Drop the entry of a removed host. Returns whether one existed.
+pub(crate) fn remove(paths: &SshPaths, host: &str) -> Result<bool, AppError> {
+    let mut lines = read(paths)?;
+    let before = lines.len();
+    lines.retain(|line| !is_managed(line, host));
+    if lines.len() == before {
+        return Ok(false);
+    }
+    write(paths, &lines)?;
+    Ok(true)
+}
+
+fn is_managed(line: &str, host: &str) -> bool {
+    !line.trim_start().starts_with('#')
+        && line.split_whitespace().last() == Some(format!("ssv:{host}").as_str())
+}
+
+fn render(principals: &str, (algorithm, encoded): &(String, String), host: &str) -> String {
+    format!("{principals} namespaces=\"git\" {algorithm} {encoded} ssv:{host}")
+}
+
+fn key_fields(public_key: &str) -> Result<(String, String), AppError> {
+    let mut fields = public_key.split_whitespace();
+    match (fields.next(), fields.next()) {
+        (Some(algorithm), Some(encoded)) => Ok((algorithm.to_string(), encoded.to_string())),
+        _ => Err(AppError::validation_error("public key file is empty or malformed")),
+    }
+}
+
+fn read(paths: &SshPaths) -> Result<Vec<String>, AppError> {
+    match fs::read_to_string(paths.allowed_signers_path()) {
+        Ok(contents) => Ok(contents.lines().map(str::to_string).collect()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(AppError::from(err)),
+    }
+}
+
+fn write(paths: &SshPaths, lines: &[String]) -> Result<(), AppError> {
+    let mut contents = lines.join("\n");
+    if !contents.is_empty() {
+        contents.push('\n');
+    }
+    secure_file::write(&paths.allowed_signers_path(), contents.as_bytes(), 0o644)
+}
@@ -0,0 +1,349 @@
+//! Maintenance of `~/.ssh/authorized_keys`, the keys allowed to log in to this account.
+//!
+//! Entries added by ssv end in an `ssv:<LABEL>` marker after the key comment. Other lines,
+//! including comments and blank lines, are written back exactly as they were read.
+
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::key_info::{PublicKeyInfo, WireReader};
+use crate::secure_file;
+use crate::ssh_paths::SshPaths;
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+
+/// Options that take no value.
+const FLAG_OPTIONS: &[&str] = &[
+    "agent-forwarding",
+    "cert-authority",
+    "no-agent-forwarding",
+    "no-port-forwarding",
+    "no-pty",
+    "no-touch-required",
+    "no-user-rc",
+    "no-x11-forwarding",
+    "port-forwarding",
+    "pty",
+    "restrict",
+    "user-rc",
+    "verify-required",
+    "x11-forwarding",
+];
+
+/// Options written as `name="value"`.
+const VALUE_OPTIONS: &[&str] = &[
+    "command",
+    "environment",
+    "expiry-time",
+    "from",
+    "permitlisten",
+    "permitopen",
+    "principals",
+    "tunnel",
+];
+
+/// A labelled entry managed by ssv.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthorizedKey {
+    pub label: String,
+    /// Comma-separated sshd options in front of the key, e.g. `restrict,from="10.0.0.0/8"`.
+    pub options: Option<String>,
+    pub algorithm: String,
+    pub fingerprint: Option<String>,
+    /// Comment of the key itself, without the `ssv:` marker.
+    pub comment: Option<String>,
+}
+
+/// Parsed form of one non-comment `authorized_keys` line.
+struct Line<'a> {
+    options: Option<&'a str>,
+    key: PublicKeyInfo,
+    label: Option<&'a str>,
+}
+
+/// Managed entries in file order.
+pub(crate) fn list(paths: &SshPaths) -> Result<Vec<AuthorizedKey>, AppError> {
+    Ok(read(paths)?.iter().filter_map(|line| entry(line)).collect())
+}
+
+/// Add a labelled entry. `key` is a public key line (`ALGORITHM BASE64 [COMMENT]`).
+pub(crate) fn add(
+    paths: &SshPaths,
+    label: &str,
+    key: &str,
+    options: &[String],
+) -> Result<AuthorizedKey, AppError> {
+    validate_label(label)?;
+    let info = validate_key(key)?;
+    let options = options.join(",");
+    if !options.is_empty() {
+        validate_options(&options)?;
+    }
+
+    let mut lines = read(paths)?;
+    for (number, line) in lines.iter().enumerate() {
+        let Some(parsed) = parse(line) else { continue };
+        if parsed.label == Some(label) {
+            return Err(AppError::validation_error(format!(
+                "an authorized key labelled '{label}' already exists"
+            )));
+        }
+        if parsed.key.blob == info.blob {
+            return Err(AppError::validation_error(format!(
+                "this key is already authorized on line {} of authorized_keys",
+                number + 1
+            )));
+        }
+    }
+
+    let mut rendered = String::new();
+    if !options.is_empty() {
+        rendered.push_str(&options);
+        rendered.push(' ');
+    }
+    rendered.push_str(&key.split_whitespace().collect::<Vec<_>>().join(" "));
+    rendered.push_str(&format!(" ssv:{label}"));
+    lines.push(rendered);
+    write(paths, &lines)?;
+    let added = lines.last().and_then(|line| entry(line)).expect("rendered entry parses");
+    log(paths, Operation::Authorize, &added)?;
+    Ok(added)
+}
+
+/// Drop the entry with `label`. Returns whether one existed.
+pub(crate) fn remove(paths: &SshPaths, label: &str) -> Result<bool, AppError> {
+    let mut lines = read(paths)?;
+    let Some(removed) = lines.iter().filter_map(|line| entry(line)).find(|key| key.label == label)
+    else {
+        return Ok(false);
+    };
+    lines.retain(|line| parse(line).is_none_or(|parsed| parsed.label != Some(label)));
+    write(paths, &lines)?;
+    log(paths, Operation::Unauthorize, &removed)?;
+    Ok(true)
+}
+
+/// Log a change to the entry `key`, under its label.
+fn log(paths: &SshPaths, operation: Operation, key: &AuthorizedKey) -> Result<(), AppError> {
+    let mut entry = HistoryEntry::new(operation, &key.label, vec![paths.authorized_keys_path()]);
+    entry.fingerprint = key.fingerprint.clone();
+    history::record(paths, &entry)
+}
+
+fn entry(line: &str) -> Option<AuthorizedKey> {
+    let parsed = parse(line)?;
+    let comment = parsed.key.comment.as_deref().and_then(|comment| {
+        let comment = comment.rsplit_once(' ').map_or("", |(rest, _)| rest);
+        (!comment.is_empty()).then(|| comment.to_string())
+    });
+    Some(AuthorizedKey {
+        label: parsed.label?.to_string(),
+        options: parsed.options.map(str::to_string),
+        algorithm: parsed.key.algorithm.clone(),
+        fingerprint: parsed.key.fingerprint(),
+        comment,
+    })
+}
+
+fn parse(line: &str) -> Option<Line<'_>> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+    let first = trimmed.split_whitespace().next()?;
+    let (options, rest) = if is_algorithm(first) {
+        (None, trimmed)
+    } else {
+        let end = options_end(trimmed);
+        (Some(&trimmed[..end]), trimmed[end..].trim_start())
+    };
+    let key = PublicKeyInfo::parse(rest)?;
+    let label = rest.split_whitespace().skip(2).last().and_then(|word| word.strip_prefix("ssv:"));
+    Some(Line { options, key, label })
+}
+
+fn is_algorithm(word: &str) -> bool {
+    word.starts_with("ssh-")
+        || word.starts_with("ecdsa-sha2-")
+        || word.starts_with("sk-")
+        || word.ends_with("-cert-v01@openssh.com")
+}
+
+/// Byte offset of the first whitespace outside double quotes.
+fn options_end(line: &str) -> usize {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => return index,
+            _ => {}
+        }
+    }
+    line.len()
+}
+
+/// Split an options field on commas outside double quotes.
+fn split_options(options: &str) -> Result<Vec<&str>, AppError> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in options.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                parts.push(&options[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if quoted {
+        return Err(AppError::validation_error(format!("unterminated quote in '{options}'")));
+    }
+    parts.push(&options[start..]);
+    Ok(parts)
+}
+
+pub(crate) fn validate_options(options: &str) -> Result<(), AppError> {
+    if options.contains(['\n', '\r']) {
+        return Err(AppError::validation_error("authorized_keys options must be a single line"));
+    }
+    for option in split_options(options)? {
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (option, None),
+        };
+        let lower = name.to_ascii_lowercase();
+        match value {
+            None if FLAG_OPTIONS.contains(&lower.as_str()) => {}
+            Some(value) if VALUE_OPTIONS.contains(&lower.as_str()) => {
+                let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+                    return Err(AppError::validation_error(format!(
+                        "value of '{name}' must be double-quoted"
+                    )));
+                };
+                if lower == "expiry-time"
+                    && !(matches!(inner.len(), 8 | 12 | 14)
+                        && inner.bytes().all(|b| b.is_ascii_digit()))
+                {
+                    return Err(AppError::validation_error(format!(
+                        "expiry-time '{inner}' must be YYYYMMDD[HHMM[SS]]"
+                    )));
+                }
+            }
+            None if VALUE_OPTIONS.contains(&lower.as_str()) => {
+                return Err(AppError::validation_error(format!("option '{name}' needs a value")));
+            }
+            _ => {
+                return Err(AppError::validation_error(format!(
+                    "unknown authorized_keys option '{option}'"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse a public key line and check that the base64 blob decodes and names its algorithm.
+fn validate_key(key: &str) -> Result<PublicKeyInfo, AppError> {
+    let invalid = || AppError::validation_error("not a valid OpenSSH public key");
+    let info = PublicKeyInfo::parse(key).ok_or_else(invalid)?;
+    let name = WireReader::new(&info.blob).string().ok_or_else(invalid)?;
+    if !is_algorithm(&info.algorithm) || name != info.algorithm.as_bytes() {
+        return Err(invalid());
+    }
+    if key.trim().contains(['\n', '\r']) {
+        return Err(AppError::validation_error("expected a single public key line"));
+    }
+    if info.comment.as_deref().is_some_and(|comment| comment.contains("ssv:")) {
+        return Err(AppError::validation_error("key comment must not contain 'ssv:'"));
+    }
+    Ok(info)
+}
+
+fn validate_label(label: &str) -> Result<(), AppError> {
+    if label.is_empty()
+        || !label.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@'))
+    {
+        return Err(AppError::validation_error(format!(
+            "invalid label '{label}'; use letters, digits, '-', '_', '.' or '@'"
+        )));
+    }
+    Ok(())
+}
+
+fn read(paths: &SshPaths) -> Result<Vec<String>, AppError> {
+    match fs::read_to_string(paths.authorized_keys_path()) {
+        Ok(contents) => Ok(contents.lines().map(str::to_string).collect()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(AppError::from(err)),
+    }
+}
+
+fn write(paths: &SshPaths, lines: &[String]) -> Result<(), AppError> {
+    let mut contents = lines.join("\n");
+    if !contents.is_empty() {
+        contents.push('\n');
+    }
+    paths.ensure_dir_with_mode(&paths.ssh_root())?;
+    secure_file::write(&paths.authorized_keys_path(), contents.as_bytes(), 0o600)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAlUFMG3D4/ol4Gm9aH5E4iBjHU7jyNJlXygA4pfxqbB fix";
+
+    #[test]
+    fn parses_options_with_quoted_spaces() {
+        let line = format!("command=\"echo a, b\",no-pty {ED25519} ssv:ci");
+        let parsed = parse(&line).expect("parsable");
+        assert_eq!(parsed.options, Some("command=\"echo a, b\",no-pty"));
+        assert_eq!(parsed.label, Some("ci"));
+        assert_eq!(parsed.key.algorithm, "ssh-ed25519");
+
+        let managed = entry(&line).expect("managed");
+        assert_eq!(managed.comment.as_deref(), Some("fix"));
+        assert!(parse("# ssh-ed25519 AAAA").is_none());
+        assert!(entry(ED25519).is_none());
+    }
+
+    #[test]
+    fn validates_options() {
+        for valid in [
+            "restrict",
+            "from=\"10.0.0.0/8,!10.1.0.0/16\",no-pty",
+            "command=\"rsync --server \\\"x\\\"\"",
+            "expiry-time=\"20301231\"",
+            "NO-PTY",
+        ] {
+            assert!(validate_options(valid).is_ok(), "{valid}");
+        }
+        for invalid in
+            ["bogus", "from=10.0.0.0/8", "command", "expiry-time=\"2030\"", "from=\"unterminated"]
+        {
+            assert!(validate_options(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn validates_key_blobs() {
+        assert!(validate_key(ED25519).is_ok());
+        assert!(validate_key("ssh-ed25519 AAAATESTKEY").is_err());
+        assert!(
+            validate_key(
+                "ssh-rsa AAAAC3NzaC1lZDI1NTE5AAAAIAlUFMG3D4/ol4Gm9aH5E4iBjHU7jyNJlXygA4pfxqbB"
+            )
+            .is_err()
+        );
+        assert!(validate_key(&format!("{ED25519} ssv:spoof")).is_err());
+    }
+}
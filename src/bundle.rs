@@ -0,0 +1,77 @@
+//! Portable, encrypted archive of managed hosts used by `ssv export`/`ssv import`.
+
+use crate::error::AppError;
+use crate::metadata::HostMetadata;
+use crate::{sealed, secure_file};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"SSVBNDL1";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Bundle {
+    pub version: u32,
+    pub created_at: String,
+    pub hosts: Vec<BundledHost>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct BundledHost {
+    pub host: String,
+    pub config: String,
+    pub metadata: Option<HostMetadata>,
+    pub files: Vec<BundledFile>,
+    pub known_hosts: Vec<String>,
+}
+
+/// A key file stored relative to `~/.ssh`. Modes are not carried over; import applies 0600 to
+/// private material and 0644 to `.pub` files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct BundledFile {
+    pub name: String,
+    /// Base64-encoded file contents.
+    pub contents: String,
+}
+
+impl BundledFile {
+    pub(crate) fn new(name: String, contents: &[u8]) -> Self {
+        Self { name, contents: STANDARD.encode(contents) }
+    }
+
+    pub(crate) fn decode(&self) -> Result<Vec<u8>, AppError> {
+        STANDARD.decode(&self.contents).map_err(|_| {
+            AppError::validation_error(format!("bundle entry '{}' is corrupted", self.name))
+        })
+    }
+}
+
+impl Bundle {
+    pub(crate) fn new(created_at: String, hosts: Vec<BundledHost>) -> Self {
+        Self { version: FORMAT_VERSION, created_at, hosts }
+    }
+
+    pub(crate) fn write(&self, path: &Path, passphrase: &str) -> Result<(), AppError> {
+        let plaintext = serde_json::to_vec(self)
+            .map_err(|err| AppError::config_error(format!("failed to encode bundle: {err}")))?;
+        let sealed = sealed::seal(MAGIC, passphrase, &plaintext)?;
+        secure_file::write(path, &sealed, 0o600)
+    }
+
+    pub(crate) fn read(path: &Path, passphrase: &str) -> Result<Self, AppError> {
+        let sealed = fs::read(path)?;
+        let plaintext = sealed::open(MAGIC, passphrase, &sealed)?;
+        let bundle: Bundle = serde_json::from_slice(&plaintext)
+            .map_err(|err| AppError::validation_error(format!("invalid bundle contents: {err}")))?;
+        if bundle.version != FORMAT_VERSION {
+            return Err(AppError::validation_error(format!(
+                "unsupported bundle version {}",
+                bundle.version
+            )));
+        }
+        Ok(bundle)
+    }
+}
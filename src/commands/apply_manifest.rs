@@ -0,0 +1,313 @@
+use crate::commands::generate_host::GenerateHost;
+use crate::commands::list_hosts::ListHosts;
+use crate::commands::remove_host::RemoveHost;
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::key_store::KeyStore;
+use crate::keygen::KeyGenerator;
+use crate::metadata;
+use crate::ownership::{self, Ownership};
+use crate::ssh_config;
+use crate::ssh_paths::SshPaths;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// Declarative host inventory loaded from an `ssv.toml` manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// Remove managed hosts that are not listed (also enabled by `--prune`).
+    #[serde(default)]
+    pub prune: bool,
+    #[serde(default, rename = "host")]
+    pub hosts: Vec<ManifestHost>,
+}
+
+/// One `[[host]]` table of a manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestHost {
+    pub alias: String,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// Managed host to jump through, written as `ProxyJump` like `generate --via`.
+    pub via: Option<String>,
+    #[serde(rename = "type", default = "default_key_type")]
+    pub key_type: String,
+    /// Extra directives, written in key order.
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+}
+
+fn default_key_type() -> String {
+    "ed25519".to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    /// Generate a key pair and config for a host missing from conf.d.
+    Create,
+    /// Rewrite the config of an existing host; its keys are left untouched.
+    Update,
+    /// Remove a managed host that the manifest no longer lists.
+    Prune,
+}
+
+/// A single difference between the manifest and the managed state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedChange {
+    pub alias: String,
+    pub action: ChangeAction,
+    /// Config lines prefixed with `-` (removed) or `+` (added).
+    pub diff: Vec<String>,
+    /// Caveats such as a key type change that cannot be applied without rotation.
+    pub notes: Vec<String>,
+}
+
+/// Ordered list of changes needed to converge conf.d on a manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Plan {
+    pub changes: Vec<PlannedChange>,
+}
+
+impl Plan {
+    pub fn count(&self, action: ChangeAction) -> usize {
+        self.changes.iter().filter(|change| change.action == action).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Command object that compares (and optionally converges) conf.d against a manifest.
+pub(crate) struct ApplyManifest<'a> {
+    pub manifest_path: &'a Path,
+    pub prune: bool,
+}
+
+impl<'a> ApplyManifest<'a> {
+    pub(crate) fn plan(&self, paths: &SshPaths) -> Result<Plan, AppError> {
+        let manifest = self.load()?;
+        let prune = self.prune || manifest.prune;
+        let mut plan = Plan::default();
+        let state = ownership::load(paths)?;
+
+        for host in &manifest.hosts {
+            let options = Self::options(host);
+            let generate = Self::generate_command(host, &options);
+            generate.validate(paths)?;
+            let config_path = paths.host_config_path(&host.alias);
+
+            let Ok(current) = fs::read_to_string(&config_path) else {
+                let diff = generate
+                    .render_config(&generate.default_identity_file())
+                    .lines()
+                    .map(|line| format!("+{line}"))
+                    .collect();
+                plan.changes.push(PlannedChange {
+                    alias: host.alias.clone(),
+                    action: ChangeAction::Create,
+                    diff,
+                    notes: vec![format!("generate a new {} key pair", host.key_type)],
+                });
+                continue;
+            };
+
+            let body = ownership::strip_header(&current);
+            let (desired, mut notes) = Self::updated_config(&generate, body);
+            let owner = ownership::classify(&state, &host.alias, &current);
+            if owner == Ownership::Foreign {
+                notes.push("take over a config ssv did not write".to_string());
+            }
+            if desired != body || owner == Ownership::Foreign {
+                plan.changes.push(PlannedChange {
+                    alias: host.alias.clone(),
+                    action: ChangeAction::Update,
+                    diff: Self::diff(body, &desired),
+                    notes,
+                });
+            }
+        }
+
+        if prune {
+            let listed: BTreeSet<&str> = manifest.hosts.iter().map(|h| h.alias.as_str()).collect();
+            for host in ListHosts.execute(paths)? {
+                // Configs ssv did not write are not its to prune.
+                if !listed.contains(host.as_str())
+                    && ownership::status(paths, &host)? != Ownership::Foreign
+                {
+                    plan.changes.push(PlannedChange {
+                        alias: host,
+                        action: ChangeAction::Prune,
+                        diff: Vec::new(),
+                        notes: vec!["delete config, key pair and metadata".to_string()],
+                    });
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    pub(crate) fn apply(
+        &self,
+        paths: &SshPaths,
+        keygen: &dyn KeyGenerator,
+        store: &dyn KeyStore,
+    ) -> Result<Plan, AppError> {
+        let manifest = self.load()?;
+        let plan = self.plan(paths)?;
+        let pruned: BTreeSet<&str> = plan
+            .changes
+            .iter()
+            .filter(|change| change.action == ChangeAction::Prune)
+            .map(|change| change.alias.as_str())
+            .collect();
+
+        for change in &plan.changes {
+            match change.action {
+                ChangeAction::Create | ChangeAction::Update => {
+                    let host = manifest
+                        .hosts
+                        .iter()
+                        .find(|host| host.alias == change.alias)
+                        .ok_or_else(|| AppError::HostNotFound(change.alias.clone()))?;
+                    let options = Self::options(host);
+                    let generate = Self::generate_command(host, &options);
+                    if change.action == ChangeAction::Create {
+                        generate.execute(paths, keygen, store)?;
+                    } else {
+                        let config_path = paths.host_config_path(&host.alias);
+                        let current = fs::read_to_string(&config_path)?;
+                        let body = ownership::strip_header(&current);
+                        let (desired, _) = Self::updated_config(&generate, body);
+                        ownership::write_config(paths, &host.alias, &desired)?;
+                        if let Some(via) = &host.via
+                            && let Some(mut meta) = metadata::load(paths, &host.alias)?
+                            && meta.via.as_ref() != Some(via)
+                        {
+                            meta.via = Some(via.clone());
+                            metadata::save(paths, &host.alias, &meta)?;
+                        }
+                        let entry =
+                            HistoryEntry::new(Operation::Apply, &host.alias, vec![config_path]);
+                        history::record(paths, &entry)?;
+                    }
+                }
+                ChangeAction::Prune => {
+                    // Jump hosts may go once everything that jumps through them goes too.
+                    let dependents = RemoveHost::dependents(paths, &change.alias)?;
+                    let force = dependents.iter().all(|host| pruned.contains(host.as_str()));
+                    let remove = RemoveHost {
+                        host: &change.alias,
+                        force,
+                        force_foreign: false,
+                        allow_guess: false,
+                        tokens: &BTreeMap::new(),
+                    };
+                    remove.execute(paths, store)?;
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    fn load(&self) -> Result<Manifest, AppError> {
+        let contents = fs::read_to_string(self.manifest_path).map_err(|err| {
+            AppError::config_error(format!(
+                "failed to read manifest {}: {err}",
+                self.manifest_path.display()
+            ))
+        })?;
+        let manifest: Manifest = toml::from_str(&contents).map_err(|err| {
+            AppError::config_error(format!(
+                "invalid manifest {}: {err}",
+                self.manifest_path.display()
+            ))
+        })?;
+
+        let mut seen = BTreeSet::new();
+        for host in &manifest.hosts {
+            if !seen.insert(host.alias.as_str()) {
+                return Err(AppError::validation_error(format!(
+                    "host '{}' is declared more than once in the manifest",
+                    host.alias
+                )));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Render the desired config for an existing host, keeping whatever key it already uses so
+    /// updates never orphan key material. Directives the manifest does not mention, such as a
+    /// `ProxyJump` from `generate --via` or lines written by a template, are kept as they are.
+    fn updated_config(generate: &GenerateHost, current: &str) -> (String, Vec<String>) {
+        let directives = ssh_config::parse(current);
+        let current_identity = ssh_config::value(&directives, "IdentityFile");
+        let desired_identity = generate.default_identity_file();
+
+        let mut notes = Vec::new();
+        if let Some(identity) = current_identity.filter(|id| *id != desired_identity) {
+            notes.push(format!(
+                "keeping IdentityFile {identity}; key type changes require regenerating the key"
+            ));
+        }
+        let identity = current_identity.unwrap_or(&desired_identity);
+        let mentioned: BTreeSet<String> = ssh_config::parse(&generate.render_config(identity))
+            .into_iter()
+            .map(|directive| directive.key.to_ascii_lowercase())
+            .collect();
+        let mut options: Vec<(String, String)> = directives
+            .iter()
+            .filter(|directive| {
+                let key = directive.key.to_ascii_lowercase();
+                // The manifest's own fields decide these even when it leaves them unset.
+                !["host", "hostname", "user", "port"].contains(&key.as_str())
+                    && !mentioned.contains(&key)
+            })
+            .map(|directive| (directive.key.clone(), directive.value.clone()))
+            .collect();
+        options.extend_from_slice(generate.options);
+        (GenerateHost { options: &options, ..*generate }.render_config(identity), notes)
+    }
+
+    fn options(host: &ManifestHost) -> Vec<(String, String)> {
+        host.options.iter().map(|(key, value)| (key.clone(), value.clone())).collect()
+    }
+
+    fn generate_command<'h>(
+        host: &'h ManifestHost,
+        options: &'h [(String, String)],
+    ) -> GenerateHost<'h> {
+        GenerateHost {
+            host: &host.alias,
+            patterns: &[],
+            hostname: host.hostname.as_deref(),
+            key_type: &host.key_type,
+            bits: None,
+            passphrase: None,
+            user: host.user.as_deref(),
+            port: host.port,
+            via: host.via.as_deref(),
+            options,
+            comment: None,
+            purpose: None,
+            ticket: None,
+            expires: None,
+        }
+    }
+
+    fn diff(current: &str, desired: &str) -> Vec<String> {
+        let current_lines: Vec<&str> = current.lines().collect();
+        let desired_lines: Vec<&str> = desired.lines().collect();
+        let removed = current_lines.iter().filter(|line| !desired_lines.contains(line));
+        let added = desired_lines.iter().filter(|line| !current_lines.contains(line));
+        removed.map(|line| format!("-{line}")).chain(added.map(|line| format!("+{line}"))).collect()
+    }
+}
@@ -0,0 +1,341 @@
+use crate::commands::list_hosts::ListHosts;
+use crate::commands::remove_host::RemoveHost;
+use crate::error::AppError;
+use crate::key_info::{self, PublicKeyInfo};
+use crate::key_store::{self, KeyStore};
+use crate::metadata::{self, HostMetadata};
+use crate::ssh_paths::SshPaths;
+use crate::timestamp;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+/// Rules applied by [`AuditHosts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditPolicy {
+    /// Keys older than this many days violate the rotation policy.
+    pub max_age_days: u64,
+    /// RSA keys below this modulus size are considered weak.
+    pub min_rsa_bits: u32,
+    /// Treat unencrypted private keys as violations rather than warnings.
+    pub require_passphrase: bool,
+}
+
+impl Default for AuditPolicy {
+    fn default() -> Self {
+        Self { max_age_days: 180, min_rsa_bits: 3072, require_passphrase: false }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single policy observation about a host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+/// Octal permission bits observed for a managed file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileMode {
+    pub path: PathBuf,
+    pub mode: String,
+}
+
+/// Audit results for one managed host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostAudit {
+    pub host: String,
+    pub identity_file: Option<PathBuf>,
+    pub key_type: Option<String>,
+    pub bits: Option<u32>,
+    pub age_days: Option<u64>,
+    pub encrypted: Option<bool>,
+    pub permissions: Vec<FileMode>,
+    pub findings: Vec<Finding>,
+}
+
+/// Audit results for every managed host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditReport {
+    pub max_age_days: u64,
+    pub hosts: Vec<HostAudit>,
+}
+
+const RULES: &[(&str, &str)] = &[
+    ("missing-key", "Identity file referenced by the host config is missing"),
+    ("key-age", "Key is older than the maximum age allowed by policy"),
+    ("key-expired", "Key is past the expiry date recorded in its metadata"),
+    ("weak-key", "Key algorithm or size is below the accepted strength"),
+    ("unknown-strength", "Key size could not be determined"),
+    ("unencrypted-key", "Private key is not protected by a passphrase"),
+    ("permissions", "File is accessible to other users"),
+    ("invalid-metadata", "Metadata sidecar could not be read"),
+];
+
+impl AuditReport {
+    /// Number of findings with error severity.
+    pub fn violations(&self) -> usize {
+        self.hosts
+            .iter()
+            .flat_map(|host| &host.findings)
+            .filter(|finding| finding.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    /// Render the findings as a minimal SARIF 2.1.0 log for CI code-scanning integrations.
+    pub fn to_sarif(&self) -> Value {
+        let rules: Vec<Value> = RULES
+            .iter()
+            .map(|(id, text)| json!({ "id": id, "shortDescription": { "text": text } }))
+            .collect();
+        let results: Vec<Value> = self
+            .hosts
+            .iter()
+            .flat_map(|host| host.findings.iter().map(move |finding| (host, finding)))
+            .map(|(host, finding)| {
+                let uri = finding
+                    .path
+                    .as_ref()
+                    .or(host.identity_file.as_ref())
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|| host.host.clone());
+                json!({
+                    "ruleId": finding.rule,
+                    "level": match finding.severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                    },
+                    "message": { "text": format!("{}: {}", host.host, finding.message) },
+                    "locations": [{ "physicalLocation": { "artifactLocation": { "uri": uri } } }],
+                })
+            })
+            .collect();
+
+        json!({
+            "version": "2.1.0",
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "runs": [{
+                "tool": { "driver": {
+                    "name": "ssv",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                } },
+                "results": results,
+            }],
+        })
+    }
+}
+
+/// Command object that checks managed keys against an [`AuditPolicy`].
+pub(crate) struct AuditHosts<'a> {
+    pub policy: &'a AuditPolicy,
+}
+
+impl<'a> AuditHosts<'a> {
+    pub(crate) fn execute(
+        &self,
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+    ) -> Result<AuditReport, AppError> {
+        let hosts = ListHosts
+            .execute(paths)?
+            .into_iter()
+            .map(|host| self.audit_host(paths, store, host))
+            .collect::<Result<_, _>>()?;
+
+        Ok(AuditReport { max_age_days: self.policy.max_age_days, hosts })
+    }
+
+    fn audit_host(
+        &self,
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+        host: String,
+    ) -> Result<HostAudit, AppError> {
+        let config_path = paths.host_config_path(&host);
+        let config = fs::read_to_string(&config_path)?;
+        let meta = metadata::load(paths, &host);
+
+        let mut audit = HostAudit {
+            host,
+            identity_file: RemoveHost::parse_identity_files(&config, paths).into_iter().next(),
+            key_type: None,
+            bits: None,
+            age_days: None,
+            encrypted: None,
+            permissions: Vec::new(),
+            findings: Vec::new(),
+        };
+        self.check_mode(&mut audit, &config_path, 0o022);
+
+        // A broken sidecar is reported on its host instead of aborting the whole audit.
+        let metadata_path = paths.metadata_path(&audit.host);
+        let meta = match meta {
+            Ok(meta) => meta.unwrap_or_default(),
+            Err(err) => {
+                audit.error("invalid-metadata", err.to_string(), Some(metadata_path.clone()));
+                HostMetadata::default()
+            }
+        };
+        if let Some(expires) = meta.expires.as_deref() {
+            match timestamp::parse(expires) {
+                Ok(expiry) if expiry <= SystemTime::now() => {
+                    audit.error("key-expired", format!("key expired on {expires}"), None)
+                }
+                Ok(_) => {}
+                Err(err) => audit.error(
+                    "invalid-metadata",
+                    format!("expires: {err}"),
+                    Some(metadata_path.clone()),
+                ),
+            }
+        }
+
+        let Some(private_key) = audit.identity_file.clone() else {
+            return Ok(audit);
+        };
+        let public_key =
+            RemoveHost::to_public_key_path(&private_key).filter(|public_key| public_key.exists());
+        // A key locked in the key store is checked through its public half.
+        let locked = !private_key.exists() && key_store::stored(store, &private_key)?.is_some();
+        if !private_key.exists() && !locked {
+            let message = format!("identity file {} does not exist", private_key.display());
+            audit.error("missing-key", message, Some(private_key));
+            return Ok(audit);
+        }
+
+        if !locked {
+            self.check_mode(&mut audit, &private_key, 0o077);
+        }
+        let dated = if locked { public_key.as_deref() } else { Some(private_key.as_path()) };
+        if let Some(dated) = dated {
+            let created_at = match meta.created_at.as_deref().map(timestamp::parse) {
+                Some(Ok(created_at)) => Some(created_at),
+                Some(Err(err)) => {
+                    let message = format!("created_at: {err}");
+                    audit.error("invalid-metadata", message, Some(metadata_path));
+                    None
+                }
+                None => None,
+            };
+            self.check_age(&mut audit, dated, created_at)?;
+        }
+        if !locked {
+            self.check_encryption(&mut audit, &private_key)?;
+        }
+
+        if let Some(public_key) = public_key {
+            self.check_mode(&mut audit, &public_key, 0o022);
+            let contents = fs::read_to_string(&public_key)?;
+            if let Some(info) = PublicKeyInfo::parse(&contents) {
+                self.check_strength(&mut audit, &info);
+            }
+        }
+
+        Ok(audit)
+    }
+
+    fn check_age(
+        &self,
+        audit: &mut HostAudit,
+        private_key: &Path,
+        created_at: Option<SystemTime>,
+    ) -> Result<(), AppError> {
+        let created = match created_at {
+            Some(created_at) => created_at,
+            None => fs::metadata(private_key)?.modified()?,
+        };
+        let age = timestamp::days_since(created);
+        audit.age_days = Some(age);
+        if age > self.policy.max_age_days {
+            let message =
+                format!("key is {age} days old (maximum {} days)", self.policy.max_age_days);
+            audit.error("key-age", message, None);
+        }
+        Ok(())
+    }
+
+    fn check_encryption(&self, audit: &mut HostAudit, private_key: &Path) -> Result<(), AppError> {
+        let contents = fs::read(private_key)?;
+        audit.encrypted = key_info::private_key_encrypted(&String::from_utf8_lossy(&contents));
+        if audit.encrypted == Some(false) {
+            let finding = Finding {
+                rule: "unencrypted-key",
+                severity: if self.policy.require_passphrase {
+                    Severity::Error
+                } else {
+                    Severity::Warning
+                },
+                message: "private key has no passphrase".to_string(),
+                path: Some(private_key.to_path_buf()),
+            };
+            audit.findings.push(finding);
+        }
+        Ok(())
+    }
+
+    fn check_strength(&self, audit: &mut HostAudit, info: &PublicKeyInfo) {
+        let key_type = info.key_type().to_string();
+        audit.bits = info.bits();
+
+        match (key_type.as_str(), audit.bits) {
+            ("dsa", _) => {
+                audit.error("weak-key", "DSA keys are deprecated and insecure".to_string(), None)
+            }
+            ("rsa", Some(bits)) if bits < self.policy.min_rsa_bits => audit.error(
+                "weak-key",
+                format!("RSA key is {bits} bits (minimum {})", self.policy.min_rsa_bits),
+                None,
+            ),
+            (_, None) => audit.findings.push(Finding {
+                rule: "unknown-strength",
+                severity: Severity::Warning,
+                message: format!("could not determine the size of the {key_type} key"),
+                path: None,
+            }),
+            _ => {}
+        }
+        audit.key_type = Some(key_type);
+    }
+
+    fn check_mode(&self, audit: &mut HostAudit, path: &Path, forbidden: u32) {
+        #[cfg(unix)]
+        if let Ok(meta) = fs::metadata(path) {
+            let mode = meta.permissions().mode() & 0o777;
+            audit
+                .permissions
+                .push(FileMode { path: path.to_path_buf(), mode: format!("{mode:04o}") });
+            if mode & forbidden != 0 {
+                let message = format!("{} has mode {mode:04o}", path.display());
+                audit.error("permissions", message, Some(path.to_path_buf()));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = (audit, path, forbidden);
+    }
+}
+
+impl HostAudit {
+    fn error(&mut self, rule: &'static str, message: String, path: Option<PathBuf>) {
+        self.findings.push(Finding { rule, severity: Severity::Error, message, path });
+    }
+}
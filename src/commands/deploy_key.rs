@@ -0,0 +1,225 @@
+use crate::authorized_keys;
+use crate::commands::remove_host::RemoveHost;
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::metadata::{self, Deployment};
+use crate::ssh_paths::SshPaths;
+use crate::timestamp;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// How `ssv deploy` and `ssv undeploy` log in to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployAuth<'a> {
+    /// The host's own config, plus the agent and default keys (`IdentitiesOnly=no`).
+    Default,
+    /// Password or keyboard-interactive login only.
+    Password,
+    /// The private key of another managed host.
+    ManagedKey(&'a str),
+}
+
+/// Command object that appends a managed public key to the remote `authorized_keys`.
+pub(crate) struct DeployKey<'a> {
+    pub host: &'a str,
+    pub auth: DeployAuth<'a>,
+    /// `authorized_keys` options prefixed to the key, e.g. `from="10.0.0.0/8",no-pty`.
+    pub key_options: Option<&'a str>,
+}
+
+impl<'a> DeployKey<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<Deployment, AppError> {
+        let (algorithm, encoded, comment) = deployable_key(paths, self.host)?;
+        if let Some(options) = self.key_options {
+            authorized_keys::validate_options(options).map_err(|err| {
+                AppError::validation_error(format!(
+                    "invalid authorized_keys options '{options}': {err}"
+                ))
+            })?;
+        }
+
+        let blob = format!("{algorithm} {encoded}");
+        let line = authorized_line(&blob, &comment, self.key_options);
+        // Any earlier line for the same key is replaced, so repeated deploys leave one entry.
+        run_ssh(paths, self.host, self.auth, &append_script(&[&blob], &line))?;
+
+        let deployment = Deployment {
+            deployed_at: timestamp::now_rfc3339(),
+            key_options: self.key_options.map(str::to_string),
+        };
+        let mut meta = metadata::load(paths, self.host)?.unwrap_or_default();
+        meta.deployment = Some(deployment.clone());
+        metadata::save(paths, self.host, &meta)?;
+        let entry =
+            HistoryEntry::new(Operation::Deploy, self.host, vec![paths.metadata_path(self.host)]);
+        history::record(paths, &entry.with_key_line(&blob))?;
+        Ok(deployment)
+    }
+}
+
+/// Command object that swaps a deployed public key for its successor in the remote
+/// `authorized_keys`, logging in with the old key before it is replaced locally.
+pub(crate) struct RedeployKey<'a> {
+    pub host: &'a str,
+    /// The deployed public key line.
+    pub old_public_key: &'a str,
+    /// The public key line replacing it.
+    pub new_public_key: &'a str,
+    /// Options the old key was deployed with, carried over to the new one.
+    pub key_options: Option<&'a str>,
+}
+
+impl<'a> RedeployKey<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<Deployment, AppError> {
+        let malformed = || {
+            AppError::validation_error(format!("the public key of '{}' is malformed", self.host))
+        };
+        let mut old = self.old_public_key.split_whitespace();
+        let old_blob = old.next().zip(old.next()).ok_or_else(malformed)?;
+        let mut new = self.new_public_key.split_whitespace();
+        let (algorithm, encoded) = new.next().zip(new.next()).ok_or_else(malformed)?;
+        let comment = new.collect::<Vec<_>>().join(" ");
+
+        let old_blob = format!("{} {}", old_blob.0, old_blob.1);
+        let new_blob = format!("{algorithm} {encoded}");
+        let line = authorized_line(&new_blob, &comment, self.key_options);
+        run_ssh(
+            paths,
+            self.host,
+            DeployAuth::Default,
+            &append_script(&[&old_blob, &new_blob], &line),
+        )?;
+        Ok(Deployment {
+            deployed_at: timestamp::now_rfc3339(),
+            key_options: self.key_options.map(str::to_string),
+        })
+    }
+}
+
+/// Command object that deletes a managed public key from the remote `authorized_keys`.
+pub(crate) struct UndeployKey<'a> {
+    pub host: &'a str,
+    pub auth: DeployAuth<'a>,
+}
+
+impl<'a> UndeployKey<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<(), AppError> {
+        let (algorithm, encoded, _) = deployable_key(paths, self.host)?;
+        let blob = format!("{algorithm} {encoded}");
+        let script = format!(
+            "[ -f .ssh/authorized_keys ] || exit 0; umask 077 && \
+             {{ grep -vF -- {blob} .ssh/authorized_keys; true; }} > .ssh/authorized_keys.ssv \
+             && cat .ssh/authorized_keys.ssv > .ssh/authorized_keys \
+             && rm -f .ssh/authorized_keys.ssv",
+            blob = shell_quote(&blob),
+        );
+        run_ssh(paths, self.host, self.auth, &script)?;
+
+        if let Some(mut meta) = metadata::load(paths, self.host)?
+            && meta.deployment.take().is_some()
+        {
+            metadata::save(paths, self.host, &meta)?;
+        }
+        let entry =
+            HistoryEntry::new(Operation::Undeploy, self.host, vec![paths.metadata_path(self.host)]);
+        history::record(paths, &entry.with_key_line(&blob))
+    }
+}
+
+/// Algorithm, base64 blob and comment of a host's public key.
+fn deployable_key(paths: &SshPaths, host: &str) -> Result<(String, String, String), AppError> {
+    paths.validate_host(host)?;
+    if let Some(meta) = metadata::load(paths, host)?
+        && !meta.patterns.is_empty()
+    {
+        return Err(AppError::validation_error(format!(
+            "'{host}' is a pattern entry ({}); deploy to a concrete host instead",
+            meta.patterns.join(" ")
+        )));
+    }
+    let public_key = managed_keys(paths, host)?
+        .iter()
+        .filter_map(|key| RemoveHost::to_public_key_path(key))
+        .find(|key| key.exists())
+        .ok_or_else(|| {
+            AppError::validation_error(format!("host '{host}' has no public key on disk"))
+        })?;
+
+    let contents = fs::read_to_string(&public_key)?;
+    let mut fields = contents.split_whitespace();
+    match (fields.next(), fields.next()) {
+        (Some(algorithm), Some(encoded)) => {
+            Ok((algorithm.to_string(), encoded.to_string(), fields.collect::<Vec<_>>().join(" ")))
+        }
+        _ => Err(AppError::validation_error(format!(
+            "{} is empty or malformed",
+            public_key.display()
+        ))),
+    }
+}
+
+fn managed_keys(paths: &SshPaths, host: &str) -> Result<Vec<PathBuf>, AppError> {
+    let config = fs::read_to_string(paths.host_config_path(host))
+        .map_err(|_| AppError::HostNotFound(host.to_string()))?;
+    Ok(RemoveHost::parse_identity_files(&config, paths))
+}
+
+/// Run `script` on the host through ssh, leaving the terminal attached for password prompts.
+fn run_ssh(paths: &SshPaths, host: &str, auth: DeployAuth, script: &str) -> Result<(), AppError> {
+    let ssh = paths.ssh();
+    let mut command = Command::new(ssh);
+    match auth {
+        DeployAuth::Default => {
+            command.args(["-o", "IdentitiesOnly=no"]);
+        }
+        DeployAuth::Password => {
+            command.args([
+                "-o",
+                "PubkeyAuthentication=no",
+                "-o",
+                "PreferredAuthentications=password,keyboard-interactive",
+            ]);
+        }
+        DeployAuth::ManagedKey(other) => {
+            paths.validate_host(other)?;
+            let key = managed_keys(paths, other)?.into_iter().find(|key| key.exists()).ok_or_else(
+                || AppError::validation_error(format!("host '{other}' has no private key on disk")),
+            )?;
+            command.arg("-i").arg(key).args(["-o", "IdentitiesOnly=yes"]);
+        }
+    }
+
+    let status = command.arg(host).arg(script).status()?;
+    if status.success() { Ok(()) } else { Err(AppError::command_failed(ssh, status)) }
+}
+
+/// The `authorized_keys` line for `blob` (`ALGORITHM BASE64`), with options and comment.
+fn authorized_line(blob: &str, comment: &str, key_options: Option<&str>) -> String {
+    let mut line = blob.to_string();
+    if let Some(options) = key_options {
+        line = format!("{options} {line}");
+    }
+    if !comment.is_empty() {
+        line = format!("{line} {comment}");
+    }
+    line
+}
+
+/// Remote script that drops every line holding one of `blobs` and appends `line`.
+fn append_script(blobs: &[&str], line: &str) -> String {
+    let patterns: Vec<String> =
+        blobs.iter().map(|blob| format!("-e {}", shell_quote(blob))).collect();
+    format!(
+        "umask 077 && mkdir -p .ssh && touch .ssh/authorized_keys && \
+         {{ grep -vF {patterns} .ssh/authorized_keys; printf '%s\\n' {line}; }} \
+         > .ssh/authorized_keys.ssv && cat .ssh/authorized_keys.ssv > .ssh/authorized_keys \
+         && rm -f .ssh/authorized_keys.ssv",
+        patterns = patterns.join(" "),
+        line = shell_quote(line),
+    )
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
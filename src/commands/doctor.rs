@@ -0,0 +1,130 @@
+use crate::commands::list_hosts::ListHosts;
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::ownership::{self, Ownership};
+use crate::ssh_paths::SshPaths;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// How a host config differs from what ssv recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DriftKind {
+    /// In `conf.d` without a state entry or ssv's header.
+    Foreign,
+    /// Changed since ssv wrote it.
+    Modified,
+    /// Carries ssv's header but is missing from the state file, e.g. copied from another machine.
+    Untracked,
+    /// In the state file, but the config is gone.
+    Missing,
+}
+
+impl DriftKind {
+    pub fn describe(self) -> &'static str {
+        match self {
+            DriftKind::Foreign => "was not written by ssv",
+            DriftKind::Modified => "changed since ssv wrote it",
+            DriftKind::Untracked => "is stamped by ssv but not in its state file",
+            DriftKind::Missing => "is tracked by ssv but no longer exists",
+        }
+    }
+}
+
+/// One config that does not match ssv's records.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Drift {
+    pub host: String,
+    pub config_path: PathBuf,
+    pub kind: DriftKind,
+}
+
+/// Result of [`Doctor`]: how many configs were checked and which of them drifted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DoctorReport {
+    pub checked: usize,
+    pub drift: Vec<Drift>,
+    /// Hosts whose drift was resolved by adopting their configs as they are.
+    pub adopted: Vec<String>,
+}
+
+/// Which drifted configs [`Doctor`] accepts in their current form.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Adopt {
+    /// Only report.
+    #[default]
+    Nothing,
+    /// Configs ssv wrote that were edited or deleted since. Foreign and untracked configs are
+    /// left alone: their headers prove nothing.
+    Changed,
+    /// These hosts, whatever their drift, including configs ssv did not write.
+    Hosts(Vec<String>),
+}
+
+/// Command object that compares the configs in `conf.d` with ssv's state file.
+pub(crate) struct Doctor<'a> {
+    pub adopt: &'a Adopt,
+}
+
+impl<'a> Doctor<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<DoctorReport, AppError> {
+        let state = ownership::load(paths)?;
+        let hosts = ListHosts.execute(paths)?;
+        let mut report = DoctorReport { checked: hosts.len(), ..Default::default() };
+
+        for host in &hosts {
+            let config_path = paths.host_config_path(host);
+            let contents = fs::read_to_string(&config_path)?;
+            let kind = match ownership::classify(&state, host, &contents) {
+                Ownership::Foreign if ownership::header_checksum(&contents).is_some() => {
+                    DriftKind::Untracked
+                }
+                Ownership::Foreign => DriftKind::Foreign,
+                Ownership::Modified => DriftKind::Modified,
+                Ownership::Managed => continue,
+            };
+            report.drift.push(Drift { host: host.clone(), config_path, kind });
+        }
+        for host in state.configs.keys().filter(|host| !hosts.contains(host)) {
+            report.drift.push(Drift {
+                host: host.clone(),
+                config_path: paths.host_config_path(host),
+                kind: DriftKind::Missing,
+            });
+        }
+        report.drift.sort_by(|a, b| a.host.cmp(&b.host));
+
+        if let Adopt::Hosts(named) = self.adopt
+            && let Some(host) =
+                named.iter().find(|host| report.drift.iter().all(|d| &d.host != *host))
+        {
+            return Err(AppError::validation_error(format!(
+                "'{host}' has not drifted; nothing to adopt"
+            )));
+        }
+        for drift in &report.drift {
+            let adopt = match self.adopt {
+                Adopt::Nothing => false,
+                Adopt::Changed => matches!(drift.kind, DriftKind::Modified | DriftKind::Missing),
+                Adopt::Hosts(named) => named.contains(&drift.host),
+            };
+            if !adopt {
+                continue;
+            }
+            match drift.kind {
+                DriftKind::Missing => ownership::forget(paths, &drift.host)?,
+                _ => ownership::write_config(
+                    paths,
+                    &drift.host,
+                    &fs::read_to_string(&drift.config_path)?,
+                )?,
+            }
+            let entry =
+                HistoryEntry::new(Operation::Adopt, &drift.host, vec![drift.config_path.clone()]);
+            history::record(paths, &entry)?;
+            report.adopted.push(drift.host.clone());
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::bundle::{Bundle, BundledFile, BundledHost};
+use crate::commands::list_hosts::ListHosts;
+use crate::commands::remove_host::RemoveHost;
+use crate::error::AppError;
+use crate::key_store::{self, KeyStore};
+use crate::ssh_paths::SshPaths;
+use crate::{known_hosts, metadata, ssh_config, timestamp};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Command object that packs managed hosts into an encrypted bundle.
+pub(crate) struct ExportHosts<'a> {
+    /// Hosts to export; every managed host when empty.
+    pub hosts: &'a [String],
+    pub out: &'a Path,
+    pub passphrase: &'a str,
+}
+
+impl<'a> ExportHosts<'a> {
+    pub(crate) fn execute(
+        &self,
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+    ) -> Result<Vec<String>, AppError> {
+        let hosts =
+            if self.hosts.is_empty() { ListHosts.execute(paths)? } else { self.hosts.to_vec() };
+        if hosts.is_empty() {
+            return Err(AppError::validation_error("no managed hosts to export"));
+        }
+
+        let bundled = hosts
+            .iter()
+            .map(|host| Self::bundle_host(paths, store, host))
+            .collect::<Result<Vec<_>, _>>()?;
+        Bundle::new(timestamp::now_rfc3339(), bundled).write(self.out, self.passphrase)?;
+
+        Ok(hosts)
+    }
+
+    fn bundle_host(
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+        host: &str,
+    ) -> Result<BundledHost, AppError> {
+        paths.validate_host(host)?;
+        let config = fs::read_to_string(paths.host_config_path(host))
+            .map_err(|_| AppError::HostNotFound(host.to_string()))?;
+
+        let mut files = Vec::new();
+        for private in RemoveHost::parse_identity_files(&config, paths) {
+            let mut cert_name = private.file_name().unwrap_or_default().to_os_string();
+            cert_name.push("-cert.pub");
+            let candidates: Vec<PathBuf> = [
+                Some(private.clone()),
+                RemoveHost::to_public_key_path(&private),
+                Some(private.with_file_name(cert_name)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+
+            for path in candidates {
+                // Locked keys come out of the key store; import files them by its own `storage`.
+                let contents = match fs::read(&path) {
+                    Ok(contents) => contents,
+                    Err(_) if path == private => match key_store::stored(store, &path)? {
+                        Some(contents) => contents,
+                        None => continue,
+                    },
+                    Err(_) => continue,
+                };
+                let name = path
+                    .strip_prefix(paths.ssh_root())
+                    .map_err(|_| AppError::validation_error("identity file outside ~/.ssh"))?
+                    .to_string_lossy()
+                    .into_owned();
+                files.push(BundledFile::new(name, &contents));
+            }
+        }
+
+        let names = known_hosts::lookup_names(host, &ssh_config::parse(&config));
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        Ok(BundledHost {
+            host: host.to_string(),
+            metadata: metadata::load(paths, host)?,
+            known_hosts: known_hosts::entries_for(paths, &names)?,
+            config,
+            files,
+        })
+    }
+}
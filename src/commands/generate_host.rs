@@ -1,26 +1,61 @@
 use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::key_store::{self, KeyStore};
+use crate::keygen::{KeyGenerator, KeyRequest};
+use crate::metadata::{self, HostMetadata};
+use crate::ownership;
+use crate::ssh_config::{self, HostBlock};
 use crate::ssh_paths::SshPaths;
+use crate::{timestamp, whoami};
+use serde::Serialize;
 use std::fs;
-use std::io::Write;
-use std::path::Path;
-use std::process::Command;
+use std::path::PathBuf;
 
-#[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
+/// Files written for a newly generated host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GeneratedHost {
+    pub host: String,
+    pub config_path: PathBuf,
+    pub private_key: PathBuf,
+    pub public_key_path: PathBuf,
+    /// Contents of the public key file, ready to paste into a server or Git host.
+    pub public_key: String,
+}
 
 /// Command object that provisions keys and configuration for a host.
 pub(crate) struct GenerateHost<'a> {
     pub host: &'a str,
+    /// Host patterns for a wildcard entry; `host` is then only the file name.
+    pub patterns: &'a [String],
+    /// Real hostname to connect to when `host` is only an alias.
+    pub hostname: Option<&'a str>,
     pub key_type: &'a str,
+    /// Key size; the key generator's default applies when `None`.
+    pub bits: Option<u32>,
+    /// Passphrase protecting the private key; unencrypted when `None`.
+    pub passphrase: Option<&'a str>,
     pub user: Option<&'a str>,
     pub port: Option<u16>,
+    /// Managed host alias to jump through; written as `ProxyJump` and recorded in metadata.
+    pub via: Option<&'a str>,
+    /// Additional `Keyword value` directives for the host block.
+    pub options: &'a [(String, String)],
+    /// Key comment template; supports `{user}`, `{host}`, `{date}` and `{machine}`.
+    pub comment: Option<&'a str>,
+    pub purpose: Option<&'a str>,
+    pub ticket: Option<&'a str>,
+    pub expires: Option<&'a str>,
 }
 
 impl<'a> GenerateHost<'a> {
-    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<String, AppError> {
+    pub(crate) fn execute(
+        &self,
+        paths: &SshPaths,
+        keygen: &dyn KeyGenerator,
+        store: &dyn KeyStore,
+    ) -> Result<GeneratedHost, AppError> {
         paths.ensure_base_dirs()?;
-        paths.validate_host(self.host)?;
-        paths.validate_key_type(self.key_type)?;
+        self.validate(paths)?;
 
         let (private_key, public_key) = paths.key_paths(self.key_type, self.host);
         let config_path = paths.host_config_path(self.host);
@@ -32,56 +67,125 @@ impl<'a> GenerateHost<'a> {
             )));
         }
 
-        self.run_keygen(&private_key)?;
-        self.write_config(&config_path)?;
-
-        let public_key_contents = fs::read_to_string(&public_key)?;
-        Ok(public_key_contents)
-    }
-
-    fn run_keygen(&self, private_key: &Path) -> Result<(), AppError> {
-        let keygen = std::env::var("SSV_SSH_KEYGEN_PATH").unwrap_or_else(|_| "ssh-keygen".into());
-
-        let status = Command::new(&keygen)
-            .arg("-t")
-            .arg(self.key_type)
-            .arg("-f")
-            .arg(private_key)
-            .arg("-q")
-            .arg("-N")
-            .arg("")
-            .status()
-            .map_err(AppError::from)?;
+        let comment = self.comment.map(|template| self.render_comment(template));
+        keygen.generate(&KeyRequest {
+            key_type: self.key_type,
+            bits: self.bits,
+            passphrase: self.passphrase,
+            comment: comment.as_deref(),
+            private_key: &private_key,
+        })?;
+        if let Err(err) = key_store::lock(store, &private_key) {
+            let _ = fs::remove_file(&private_key);
+            let _ = fs::remove_file(&public_key);
+            return Err(err);
+        }
+        let config = self.render_config(&self.default_identity_file());
+        ownership::write_config(paths, self.host, &config)?;
+        metadata::save(
+            paths,
+            self.host,
+            &HostMetadata {
+                created_at: Some(timestamp::now_rfc3339()),
+                creator: Some(whoami::local_user()),
+                comment,
+                purpose: self.purpose.map(str::to_string),
+                ticket: self.ticket.map(str::to_string),
+                expires: self.expires.map(str::to_string),
+                via: self.via.map(str::to_string),
+                patterns: self.patterns.to_vec(),
+                registrations: Vec::new(),
+                deployment: None,
+            },
+        )?;
+        let files = vec![
+            private_key.clone(),
+            public_key.clone(),
+            config_path.clone(),
+            paths.metadata_path(self.host),
+        ];
+        history::record(
+            paths,
+            &HistoryEntry::new(Operation::Generate, self.host, files).with_key(&public_key),
+        )?;
 
-        if status.success() { Ok(()) } else { Err(AppError::command_failed(&keygen, status)) }
+        Ok(GeneratedHost {
+            host: self.host.to_string(),
+            public_key: fs::read_to_string(&public_key)?,
+            config_path,
+            private_key,
+            public_key_path: public_key,
+        })
     }
 
-    fn write_config(&self, config_path: &Path) -> Result<(), AppError> {
-        let mut contents = format!("Host {}\nHostName {}\n", self.host, self.host);
+    /// Check host names, key type, extra options and expiry without touching the filesystem.
+    pub(crate) fn validate(&self, paths: &SshPaths) -> Result<(), AppError> {
+        paths.validate_host(self.host)?;
+        paths.validate_key_type(self.key_type)?;
+        if !self.patterns.is_empty() {
+            ssh_config::validate_patterns(self.patterns)?;
+        }
+        if let Some(hostname) = self.hostname {
+            paths.validate_host(hostname)?;
+        }
         if let Some(user) = self.user {
-            contents.push_str(&format!("User {}\n", user));
+            ssh_config::validate_user(user)?;
         }
-        if let Some(port) = self.port {
-            contents.push_str(&format!("Port {}\n", port));
+        for (key, value) in self.options {
+            ssh_config::validate_extra_option(key, value)?;
         }
-        contents.push_str(&format!("IdentityFile ~/.ssh/id_{}_{}\n", self.key_type, self.host));
-        contents.push_str("IdentitiesOnly yes\n");
-
-        if let Some(parent) = config_path.parent() {
-            fs::create_dir_all(parent)?;
+        if let Some(expires) = self.expires {
+            timestamp::parse(expires)?;
+        }
+        if let Some(via) = self.via {
+            paths.validate_host(via)?;
+            if via == self.host {
+                return Err(AppError::validation_error(format!(
+                    "host '{via}' cannot jump through itself"
+                )));
+            }
+            if !paths.host_config_path(via).exists() {
+                return Err(AppError::validation_error(format!(
+                    "jump host '{via}' is not managed by ssv; generate it first"
+                )));
+            }
+            if self.options.iter().any(|(key, _)| key.eq_ignore_ascii_case("ProxyJump")) {
+                return Err(AppError::validation_error(
+                    "--via and a ProxyJump option cannot be combined",
+                ));
+            }
         }
+        Ok(())
+    }
 
-        let mut file = fs::File::create(config_path)?;
-        file.write_all(contents.as_bytes())?;
-        file.sync_all()?;
+    fn render_comment(&self, template: &str) -> String {
+        template
+            .replace("{user}", &whoami::local_user())
+            .replace("{host}", self.host)
+            .replace("{date}", &timestamp::today())
+            .replace("{machine}", &whoami::machine_name())
+    }
 
-        #[cfg(unix)]
-        {
-            let mut perms = file.metadata()?.permissions();
-            perms.set_mode(0o600);
-            fs::set_permissions(config_path, perms)?;
-        }
+    /// `IdentityFile` value for a freshly generated key.
+    pub(crate) fn default_identity_file(&self) -> String {
+        format!("~/.ssh/id_{}_{}", self.key_type, self.host)
+    }
 
-        Ok(())
+    pub(crate) fn render_config(&self, identity_file: &str) -> String {
+        let mut options = self.options.to_vec();
+        if let Some(via) = self.via {
+            options.insert(0, ("ProxyJump".to_string(), via.to_string()));
+        }
+        HostBlock {
+            alias: self.host,
+            patterns: self.patterns,
+            // A pattern block connects to whatever name matched, so HostName is opt-in.
+            hostname: self.hostname.or(self.patterns.is_empty().then_some(self.host)),
+            user: self.user,
+            port: self.port,
+            identity_file,
+            options: &options,
+        }
+        .render()
     }
 }
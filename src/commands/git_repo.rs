@@ -0,0 +1,386 @@
+use crate::commands::list_hosts::ListHosts;
+use crate::commands::remove_host::RemoveHost;
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::ssh_paths::SshPaths;
+use crate::{allowed_signers, ssh_config};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Which managed key a repository uses, through `core.sshCommand` or a remote pointing at an alias.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GitBinding {
+    pub repo: PathBuf,
+    /// Local `core.sshCommand`, if set.
+    pub ssh_command: Option<String>,
+    /// Managed host owning the key passed with `-i`.
+    pub host: Option<String>,
+    pub key: Option<PathBuf>,
+    pub remotes: Vec<GitRemote>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GitRemote {
+    pub name: String,
+    pub url: String,
+    /// Managed alias the URL connects to, if any.
+    pub host: Option<String>,
+}
+
+/// Git settings written by `ssv git signing`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SigningSetup {
+    pub host: String,
+    /// Repository configured, or `None` for the global git config.
+    pub repo: Option<PathBuf>,
+    pub signing_key: PathBuf,
+    pub principal: String,
+    pub allowed_signers: PathBuf,
+}
+
+/// Command object that configures SSH commit signing with a managed key.
+pub(crate) struct GitSigning<'a> {
+    pub host: &'a str,
+    /// Repository to configure; the global git config when `None`.
+    pub repo: Option<&'a Path>,
+    /// Principal for `allowed_signers`; defaults to git's `user.email`.
+    pub email: Option<&'a str>,
+}
+
+impl<'a> GitSigning<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<SigningSetup, AppError> {
+        paths.validate_host(self.host)?;
+        let Some(public_key) = managed_keys(paths, self.host)?
+            .iter()
+            .filter_map(|key| RemoveHost::to_public_key_path(key))
+            .find(|key| key.exists())
+        else {
+            return Err(AppError::validation_error(format!(
+                "host '{}' has no public key on disk",
+                self.host
+            )));
+        };
+
+        let (dir, scope) = match self.repo {
+            Some(repo) => (repo, "--local"),
+            None => (paths.home(), "--global"),
+        };
+        let principal = match self.email {
+            Some(email) => email.to_string(),
+            None => git_query(paths, dir, &["config", "user.email"])?
+                .filter(|email| !email.is_empty())
+                .ok_or_else(|| {
+                    AppError::validation_error("git user.email is not set; pass --email")
+                })?,
+        };
+        if principal.contains(char::is_whitespace) || principal.contains(',') {
+            return Err(AppError::validation_error(format!(
+                "invalid signer principal '{principal}'"
+            )));
+        }
+
+        allowed_signers::upsert(paths, self.host, &principal, &fs::read_to_string(&public_key)?)?;
+        let allowed_signers = paths.allowed_signers_path();
+        for (key, value) in [
+            ("gpg.format", "ssh".to_string()),
+            ("user.signingkey", public_key.to_string_lossy().into_owned()),
+            ("gpg.ssh.allowedSignersFile", allowed_signers.to_string_lossy().into_owned()),
+        ] {
+            git(paths, dir, &["config", scope, key, &value])?;
+        }
+        let mut files = vec![allowed_signers.clone()];
+        files.extend(self.repo.map(Path::to_path_buf));
+        let entry = HistoryEntry::new(Operation::GitSigning, self.host, files);
+        history::record(paths, &entry.with_key(&public_key))?;
+
+        Ok(SigningSetup {
+            host: self.host.to_string(),
+            repo: self.repo.map(Path::to_path_buf),
+            signing_key: public_key,
+            principal,
+            allowed_signers,
+        })
+    }
+}
+
+/// Command object that pins a repository to a managed key.
+pub(crate) struct GitUse<'a> {
+    pub host: &'a str,
+    pub repo: &'a Path,
+    /// Remote to rewrite onto the managed alias; `core.sshCommand` then keeps reading
+    /// `~/.ssh/config` so the alias resolves.
+    pub remote: Option<&'a str>,
+}
+
+impl<'a> GitUse<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<GitBinding, AppError> {
+        paths.validate_host(self.host)?;
+        let Some(key) = managed_keys(paths, self.host)?.into_iter().find(|key| key.exists()) else {
+            return Err(AppError::validation_error(format!(
+                "host '{}' has no private key on disk",
+                self.host
+            )));
+        };
+
+        if let Some(remote) = self.remote {
+            let url =
+                git_query(paths, self.repo, &["remote", "get-url", remote])?.ok_or_else(|| {
+                    AppError::validation_error(format!("remote '{remote}' does not exist"))
+                })?;
+            let config = fs::read_to_string(paths.host_config_path(self.host))
+                .map_err(|_| AppError::HostNotFound(self.host.to_string()))?;
+            let port = ssh_config::value(&ssh_config::parse(&config), "Port").map(str::to_string);
+            let rewritten = rewrite_remote(&url, self.host, port.as_deref())?;
+            git(paths, self.repo, &["remote", "set-url", remote, &rewritten])?;
+        }
+
+        let mut ssh_command = format!("ssh -i {} -o IdentitiesOnly=yes", shell_quote(&key));
+        if self.remote.is_none() {
+            ssh_command.push_str(" -F /dev/null");
+        }
+        git(paths, self.repo, &["config", "--local", "core.sshCommand", &ssh_command])?;
+        let mut entry =
+            HistoryEntry::new(Operation::GitUse, self.host, vec![self.repo.to_path_buf()]);
+        if let Some(public_key) = RemoveHost::to_public_key_path(&key) {
+            entry = entry.with_key(&public_key);
+        }
+        history::record(paths, &entry)?;
+
+        GitStatus { repos: &[self.repo.to_path_buf()] }
+            .execute(paths)
+            .map(|mut bindings| bindings.remove(0))
+    }
+}
+
+/// Command object that reports the managed keys used by repositories.
+pub(crate) struct GitStatus<'a> {
+    pub repos: &'a [PathBuf],
+}
+
+impl<'a> GitStatus<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<Vec<GitBinding>, AppError> {
+        let hosts = ListHosts.execute(paths)?;
+        let mut keys = Vec::new();
+        for host in &hosts {
+            for key in managed_keys(paths, host)? {
+                keys.push((host.clone(), key));
+            }
+        }
+
+        self.repos
+            .iter()
+            .map(|repo| {
+                let root = git_query(paths, repo, &["rev-parse", "--show-toplevel"])?.ok_or_else(
+                    || {
+                        AppError::validation_error(format!(
+                            "{} is not a git repository",
+                            repo.display()
+                        ))
+                    },
+                )?;
+                let ssh_command =
+                    git_query(paths, repo, &["config", "--local", "--get", "core.sshCommand"])?;
+                let key = ssh_command.as_deref().and_then(identity_argument);
+                let host = key.as_ref().and_then(|key| {
+                    keys.iter().find(|(_, managed)| managed == key).map(|(host, _)| host.clone())
+                });
+
+                let mut remotes = Vec::new();
+                for name in git(paths, repo, &["remote"])?.lines() {
+                    let url = git(paths, repo, &["remote", "get-url", name])?;
+                    let host = remote_host(&url)
+                        .filter(|target| hosts.iter().any(|host| host == target))
+                        .map(str::to_string);
+                    remotes.push(GitRemote { name: name.to_string(), url, host });
+                }
+
+                Ok(GitBinding { repo: PathBuf::from(root), ssh_command, host, key, remotes })
+            })
+            .collect()
+    }
+}
+
+fn managed_keys(paths: &SshPaths, host: &str) -> Result<Vec<PathBuf>, AppError> {
+    let config = fs::read_to_string(paths.host_config_path(host))
+        .map_err(|_| AppError::HostNotFound(host.to_string()))?;
+    Ok(RemoveHost::parse_identity_files(&config, paths))
+}
+
+/// Run git in `repo`, failing on a non-zero exit. `HOME` points at the managed home so
+/// `--global` reads and writes the same account ssv manages.
+fn git(paths: &SshPaths, repo: &Path, args: &[&str]) -> Result<String, AppError> {
+    let program = paths.git();
+    let output =
+        Command::new(program).env("HOME", paths.home()).arg("-C").arg(repo).args(args).output()?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(AppError::command_failed(program, output.status))
+    }
+}
+
+/// Run a git query whose failure means "not there" (unset config key, unknown remote).
+fn git_query(paths: &SshPaths, repo: &Path, args: &[&str]) -> Result<Option<String>, AppError> {
+    match git(paths, repo, args) {
+        Ok(output) => Ok(Some(output)),
+        Err(AppError::CommandFailed { .. }) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Path passed with `-i` in an ssh command line, honouring simple shell quoting.
+fn identity_argument(command: &str) -> Option<PathBuf> {
+    let words = shell_words(command);
+    let index = words.iter().position(|word| word == "-i")?;
+    words.get(index + 1).map(PathBuf::from)
+}
+
+fn shell_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut in_word = false;
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+fn shell_quote(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if path.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '.' | '_' | '-')) {
+        path.into_owned()
+    } else {
+        format!("'{}'", path.replace('\'', r"'\''"))
+    }
+}
+
+/// Split an SSH remote into `(user, host, path)`; `None` for local paths and other schemes.
+fn parse_remote(url: &str) -> Option<(Option<&str>, &str, &str)> {
+    let (authority, path) = if let Some(rest) = url.strip_prefix("ssh://") {
+        let (authority, path) = rest.split_once('/')?;
+        (authority, path)
+    } else if let Some(rest) = url.strip_prefix("https://") {
+        let (authority, path) = rest.split_once('/')?;
+        // Credentials in an https URL are not an ssh user.
+        (authority.rsplit_once('@').map_or(authority, |(_, host)| host), path)
+    } else if !url.contains("://") {
+        let (authority, path) = url.split_once(':')?;
+        if authority.contains('/') {
+            return None;
+        }
+        (authority, path)
+    } else {
+        return None;
+    };
+
+    let (user, host) = match authority.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, authority),
+    };
+    let host = host.split(':').next().unwrap_or(host);
+    (!host.is_empty()).then_some((user, host, path.trim_start_matches('/')))
+}
+
+fn remote_host(url: &str) -> Option<&str> {
+    parse_remote(url).map(|(_, host, _)| host)
+}
+
+/// Point an SSH or HTTPS remote at a managed alias, keeping the repository path and ssh user.
+///
+/// The alias form cannot carry a port, so an `ssh://` URL with a port other than 22 is only
+/// rewritten when the alias connects to that same port (`alias_port`, its `Port` directive).
+fn rewrite_remote(url: &str, alias: &str, alias_port: Option<&str>) -> Result<String, AppError> {
+    let (user, _, path) = parse_remote(url)
+        .ok_or_else(|| AppError::validation_error(format!("cannot rewrite remote URL '{url}'")))?;
+    let port = url
+        .strip_prefix("ssh://")
+        .and_then(|rest| rest.split('/').next())
+        .map(|authority| authority.rsplit_once('@').map_or(authority, |(_, host)| host))
+        .and_then(|host| host.split_once(':'))
+        .map(|(_, port)| port);
+    if let Some(port) = port
+        && port != "22"
+        && alias_port != Some(port)
+    {
+        return Err(AppError::validation_error(format!(
+            "remote URL '{url}' uses port {port}, but '{alias}' connects to port {}; \
+             set `Port {port}` for '{alias}' first",
+            alias_port.unwrap_or("22")
+        )));
+    }
+
+    let user = if url.starts_with("https://") { None } else { user };
+    Ok(match user {
+        Some(user) => format!("{user}@{alias}:{path}"),
+        None => format!("{alias}:{path}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_remote_urls_onto_alias() {
+        let rewrite = |url, alias, port| rewrite_remote(url, alias, port).ok();
+        assert_eq!(
+            rewrite("git@github.com:acme/app.git", "work-github", None).as_deref(),
+            Some("git@work-github:acme/app.git")
+        );
+        assert_eq!(
+            rewrite("ssh://git@gitlab.com:2222/acme/app.git", "lab", Some("2222")).as_deref(),
+            Some("git@lab:acme/app.git")
+        );
+        assert_eq!(
+            rewrite("ssh://git@gitlab.com:22/acme/app.git", "lab", None).as_deref(),
+            Some("git@lab:acme/app.git")
+        );
+        assert_eq!(
+            rewrite("https://token@github.com/acme/app.git", "work-github", None).as_deref(),
+            Some("work-github:acme/app.git")
+        );
+        assert_eq!(rewrite("/srv/git/app.git", "x", None), None);
+        assert_eq!(rewrite("./relative:path", "x", None), None);
+    }
+
+    #[test]
+    fn refuses_to_drop_a_remote_port() {
+        let err = rewrite_remote("ssh://git@gitlab.com:2222/acme/app.git", "lab", None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("uses port 2222, but 'lab' connects to port 22"), "{err}");
+        assert!(rewrite_remote("ssh://git@gitlab.com:2222/a.git", "lab", Some("22")).is_err());
+    }
+
+    #[test]
+    fn extracts_identity_from_quoted_ssh_command() {
+        assert_eq!(
+            identity_argument("ssh -i '/home/me/my keys/id' -o IdentitiesOnly=yes"),
+            Some(PathBuf::from("/home/me/my keys/id"))
+        );
+        assert_eq!(identity_argument("ssh -v"), None);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::bundle::{Bundle, BundledHost};
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::key_store::{self, KeyStore};
+use crate::ssh_paths::SshPaths;
+use crate::{metadata, ownership, secure_file};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+/// Command object that restores hosts from an encrypted bundle written by `ssv export`.
+pub(crate) struct ImportHosts<'a> {
+    pub bundle: &'a Path,
+    pub passphrase: &'a str,
+    /// `(old, new)` alias pairs applied before anything is written.
+    pub renames: &'a [(String, String)],
+}
+
+impl<'a> ImportHosts<'a> {
+    pub(crate) fn execute(
+        &self,
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+    ) -> Result<Vec<String>, AppError> {
+        let bundle = Bundle::read(self.bundle, self.passphrase)?;
+        for (old, new) in self.renames {
+            if !bundle.hosts.iter().any(|host| &host.host == old) {
+                return Err(AppError::validation_error(format!(
+                    "cannot rename '{old}': the bundle does not contain that host"
+                )));
+            }
+            paths.validate_host(new)?;
+        }
+
+        let hosts: Vec<BundledHost> = bundle.hosts.iter().map(|host| self.renamed(host)).collect();
+        let mut conflicts = Vec::new();
+        for host in &hosts {
+            paths.validate_host(&host.host)?;
+            let mut targets = vec![paths.host_config_path(&host.host)];
+            for file in &host.files {
+                targets.push(Self::target_path(paths, &file.name)?);
+            }
+            if targets.iter().any(|path| path.exists()) {
+                conflicts.push(host.host.clone());
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(AppError::validation_error(format!(
+                "hosts already exist locally: {}; use --rename OLD=NEW to import under another name",
+                conflicts.join(", ")
+            )));
+        }
+
+        paths.ensure_base_dirs()?;
+        for host in &hosts {
+            let mut files = Vec::new();
+            let mut public_key = None;
+            for file in &host.files {
+                let target = Self::target_path(paths, &file.name)?;
+                if file.name.ends_with(".pub") {
+                    secure_file::write(&target, &file.decode()?, 0o644)?;
+                    public_key = Some(target.clone());
+                } else {
+                    secure_file::write(&target, &file.decode()?, 0o600)?;
+                    key_store::lock(store, &target)?;
+                }
+                files.push(target);
+            }
+            let config_path = paths.host_config_path(&host.host);
+            ownership::write_config(paths, &host.host, &host.config)?;
+            files.push(config_path);
+            if let Some(meta) = &host.metadata {
+                metadata::save(paths, &host.host, meta)?;
+                files.push(paths.metadata_path(&host.host));
+            }
+            Self::merge_known_hosts(paths, &host.known_hosts)?;
+
+            let mut entry = HistoryEntry::new(Operation::Import, &host.host, files);
+            if let Some(public_key) = &public_key {
+                entry = entry.with_key(public_key);
+            }
+            history::record(paths, &entry)?;
+        }
+
+        Ok(hosts.into_iter().map(|host| host.host).collect())
+    }
+
+    /// Apply a requested rename to the alias, the `Host` line, and key file names that carry the
+    /// alias as their `_<host>` suffix.
+    fn renamed(&self, host: &BundledHost) -> BundledHost {
+        let Some((old, new)) = self.renames.iter().find(|(old, _)| *old == host.host) else {
+            return host.clone();
+        };
+
+        let config = host
+            .config
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                let indent = &line[..line.len() - trimmed.len()];
+                match trimmed.split_once(char::is_whitespace) {
+                    // Pattern entries keep their Host line; only the file name changes.
+                    Some((key, value))
+                        if key.eq_ignore_ascii_case("Host") && value.trim() == old =>
+                    {
+                        format!("Host {new}")
+                    }
+                    Some((key, value)) if key.eq_ignore_ascii_case("IdentityFile") => {
+                        format!("{indent}{key} {}", Self::rename_key_file(value.trim(), old, new))
+                    }
+                    _ => line.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n";
+
+        let mut renamed = host.clone();
+        renamed.host = new.clone();
+        renamed.config = config;
+        for file in &mut renamed.files {
+            file.name = Self::rename_key_file(&file.name, old, new);
+        }
+        renamed
+    }
+
+    fn rename_key_file(name: &str, old: &str, new: &str) -> String {
+        for suffix in ["", ".pub", "-cert.pub"] {
+            if let Some(stem) = name.strip_suffix(&format!("_{old}{suffix}")) {
+                return format!("{stem}_{new}{suffix}");
+            }
+        }
+        name.to_string()
+    }
+
+    /// Resolve a bundle entry under `~/.ssh`, refusing names that would escape it.
+    fn target_path(paths: &SshPaths, name: &str) -> Result<PathBuf, AppError> {
+        let relative = Path::new(name);
+        if name.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(AppError::validation_error(format!("unsafe path in bundle: {name}")));
+        }
+        Ok(paths.ssh_root().join(relative))
+    }
+
+    fn merge_known_hosts(paths: &SshPaths, lines: &[String]) -> Result<(), AppError> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let path = paths.known_hosts_path();
+        let mut contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(AppError::from(err)),
+        };
+
+        let missing: Vec<&String> = lines
+            .iter()
+            .filter(|line| !contents.lines().any(|existing| existing == *line))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        for line in missing {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+        secure_file::write(&path, contents.as_bytes(), 0o644)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_key_files_by_host_suffix() {
+        assert_eq!(
+            ImportHosts::rename_key_file("id_ed25519_web", "web", "web2"),
+            "id_ed25519_web2"
+        );
+        assert_eq!(
+            ImportHosts::rename_key_file("id_ed25519_web-cert.pub", "web", "web2"),
+            "id_ed25519_web2-cert.pub"
+        );
+        assert_eq!(ImportHosts::rename_key_file("id_deploy", "web", "web2"), "id_deploy");
+    }
+}
@@ -1,6 +1,28 @@
+use crate::commands::remove_host::RemoveHost;
 use crate::error::AppError;
+use crate::ownership::{self, Ownership};
+use crate::ssh_config;
 use crate::ssh_paths::SshPaths;
+use serde::Serialize;
 use std::fs;
+use std::path::PathBuf;
+
+/// A managed host with the connection settings of its config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostEntry {
+    pub host: String,
+    pub config_path: PathBuf,
+    /// Patterns on the `Host` line; only the host itself for a plain entry.
+    pub patterns: Vec<String>,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// Last `ProxyJump` hop, the host this one connects through.
+    pub via: Option<String>,
+    pub identity_file: Option<PathBuf>,
+    /// Whether ssv wrote the config and it is unchanged since.
+    pub ownership: Ownership,
+}
 
 pub(crate) struct ListHosts;
 
@@ -25,4 +47,48 @@ impl ListHosts {
         hosts.sort();
         Ok(hosts)
     }
+
+    /// Managed hosts with their parsed config.
+    pub(crate) fn entries(&self, paths: &SshPaths) -> Result<Vec<HostEntry>, AppError> {
+        let state = ownership::load(paths)?;
+        self.execute(paths)?
+            .into_iter()
+            .map(|host| {
+                let config_path = paths.host_config_path(&host);
+                let contents = fs::read_to_string(&config_path)?;
+                let config = ssh_config::parse(&contents);
+                let value = |key| ssh_config::value(&config, key).map(str::to_string);
+                Ok(HostEntry {
+                    patterns: value("Host")
+                        .map(|line| line.split_whitespace().map(str::to_string).collect())
+                        .unwrap_or_default(),
+                    hostname: value("HostName"),
+                    user: value("User"),
+                    port: value("Port").and_then(|port| port.parse().ok()),
+                    via: ssh_config::jump_hosts(&config).pop(),
+                    identity_file: RemoveHost::parse_identity_files(&contents, paths)
+                        .into_iter()
+                        .next(),
+                    ownership: ownership::classify(&state, &host, &contents),
+                    config_path,
+                    host,
+                })
+            })
+            .collect()
+    }
+
+    /// Each managed host with the last hop of its `ProxyJump` chain, the host it connects from.
+    pub(crate) fn topology(
+        &self,
+        paths: &SshPaths,
+    ) -> Result<Vec<(String, Option<String>)>, AppError> {
+        self.execute(paths)?
+            .into_iter()
+            .map(|host| {
+                let contents = fs::read_to_string(paths.host_config_path(&host))?;
+                let via = ssh_config::jump_hosts(&ssh_config::parse(&contents)).pop();
+                Ok((host, via))
+            })
+            .collect()
+    }
 }
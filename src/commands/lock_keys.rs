@@ -0,0 +1,133 @@
+use crate::commands::remove_host::RemoveHost;
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::key_store::{self, KeyStore};
+use crate::ssh_paths::SshPaths;
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Result of locking or unlocking one host's private key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyLock {
+    pub host: String,
+    /// The `IdentityFile` written or deleted.
+    pub key: PathBuf,
+    /// Whether the file changed; `false` when the key already was in the requested state.
+    pub changed: bool,
+}
+
+/// Command object that moves a host's private key into the key store, deleting the plain file.
+pub(crate) struct LockKey<'a> {
+    pub host: &'a str,
+}
+
+impl<'a> LockKey<'a> {
+    pub(crate) fn execute(
+        &self,
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+    ) -> Result<KeyLock, AppError> {
+        let key = external_key(paths, store, self.host)?;
+        if !key.exists() && key_store::stored(store, &key)?.is_none() {
+            return Err(AppError::validation_error(format!(
+                "'{}' has no private key at {} or in the key store",
+                self.host,
+                key.display()
+            )));
+        }
+        let changed = key_store::lock(store, &key)?;
+        if changed {
+            record(paths, Operation::Lock, self.host, vec![key.clone()])?;
+        }
+        Ok(KeyLock { host: self.host.to_string(), key, changed })
+    }
+}
+
+/// Command object that writes a host's stored private key back to its `IdentityFile`.
+pub(crate) struct UnlockKey<'a> {
+    pub host: &'a str,
+}
+
+impl<'a> UnlockKey<'a> {
+    pub(crate) fn execute(
+        &self,
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+    ) -> Result<KeyLock, AppError> {
+        let key = external_key(paths, store, self.host)?;
+        // An existing file may be newer than the stored key, so it is never overwritten.
+        let changed = !key.exists() && key_store::unlock(store, &key)?;
+        if !changed && !key.exists() {
+            return Err(AppError::validation_error(format!(
+                "the key store holds no private key for '{}'",
+                self.host
+            )));
+        }
+        if changed {
+            record(paths, Operation::Unlock, self.host, vec![key.clone()])?;
+        }
+        Ok(KeyLock { host: self.host.to_string(), key, changed })
+    }
+}
+
+/// Command object that hands a host's stored private key to the running ssh-agent through
+/// `ssh-add -`, so it is never written to disk.
+///
+/// With `IdentitiesOnly yes`, ssh still offers the agent's copy because the public key next to
+/// the missing `IdentityFile` names it.
+pub(crate) struct AgentKey<'a> {
+    pub host: &'a str,
+    /// How long the agent keeps the key, in `ssh-add -t` syntax such as `3600` or `1h`.
+    pub lifetime: Option<&'a str>,
+}
+
+impl<'a> AgentKey<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths, store: &dyn KeyStore) -> Result<(), AppError> {
+        let key = external_key(paths, store, self.host)?;
+        let Some(contents) = key_store::stored(store, &key)? else {
+            return Err(AppError::validation_error(format!(
+                "the key store holds no private key for '{}'",
+                self.host
+            )));
+        };
+
+        let program = paths.ssh_add();
+        let mut command = Command::new(program);
+        if let Some(lifetime) = self.lifetime {
+            command.args(["-t", lifetime]);
+        }
+        let mut child = command.arg("-").stdin(Stdio::piped()).spawn()?;
+        let written = child.stdin.take().expect("stdin is piped").write_all(&contents);
+        let status = child.wait()?;
+        written?;
+        if !status.success() {
+            return Err(AppError::command_failed(program, status));
+        }
+        record(paths, Operation::Unlock, self.host, Vec::new())
+    }
+}
+
+fn record(
+    paths: &SshPaths,
+    operation: Operation,
+    host: &str,
+    files: Vec<PathBuf>,
+) -> Result<(), AppError> {
+    let (_, public_key) = RemoveHost::managed_key_pair(paths, host)?;
+    let entry = HistoryEntry::new(operation, host, files);
+    history::record(paths, &entry.with_key(&public_key))
+}
+
+/// The host's `IdentityFile`, provided the key store keeps keys somewhere else.
+fn external_key(paths: &SshPaths, store: &dyn KeyStore, host: &str) -> Result<PathBuf, AppError> {
+    paths.validate_host(host)?;
+    let (key, _) = RemoveHost::managed_key_pair(paths, host)?;
+    if !key_store::is_external(store, &key) {
+        return Err(AppError::validation_error(
+            "private keys are stored as plain files; set `storage = \"vault\"` to lock them away",
+        ));
+    }
+    Ok(key)
+}
@@ -1,3 +1,17 @@
+pub mod apply_manifest;
+pub mod audit_hosts;
+pub mod deploy_key;
+pub mod doctor;
+pub mod export_hosts;
 pub mod generate_host;
+pub mod git_repo;
+pub mod import_hosts;
 pub mod list_hosts;
+pub mod lock_keys;
+pub mod register_key;
 pub mod remove_host;
+pub mod restore_host;
+pub mod rotate_key;
+pub mod select_hosts;
+pub mod show_host;
+pub mod test_host;
@@ -0,0 +1,79 @@
+use crate::commands::remove_host::RemoveHost;
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::metadata::{self, Registration};
+use crate::providers::Provider;
+use crate::ssh_paths::SshPaths;
+use crate::whoami;
+use std::fs;
+
+/// Command object that uploads a managed public key to a Git hosting provider.
+pub(crate) struct RegisterKey<'a> {
+    pub host: &'a str,
+    pub provider: Provider,
+    /// API base; the provider's public instance when `None`.
+    pub api_url: Option<&'a str>,
+    pub token: &'a str,
+    /// Key title shown by the provider; defaults to `<HOST> (<MACHINE>)`.
+    pub title: Option<&'a str>,
+}
+
+impl<'a> RegisterKey<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<Registration, AppError> {
+        paths.validate_host(self.host)?;
+        let config = fs::read_to_string(paths.host_config_path(self.host))
+            .map_err(|_| AppError::HostNotFound(self.host.to_string()))?;
+        let Some(public_key) = RemoveHost::parse_identity_files(&config, paths)
+            .iter()
+            .filter_map(|key| RemoveHost::to_public_key_path(key))
+            .find(|key| key.exists())
+        else {
+            return Err(AppError::validation_error(format!(
+                "host '{}' has no public key on disk",
+                self.host
+            )));
+        };
+        let public_key = fs::read_to_string(&public_key)?;
+        // Providers compare keys without the trailing comment.
+        let key: String = public_key.split_whitespace().take(2).collect::<Vec<_>>().join(" ");
+
+        let api_url = self.api_url.unwrap_or(self.provider.default_api_url()).trim_end_matches('/');
+        let mut meta = metadata::load(paths, self.host)?.unwrap_or_default();
+        if let Some(existing) =
+            meta.registrations.iter().find(|r| r.provider == self.provider && r.api_url == api_url)
+        {
+            return Err(AppError::validation_error(format!(
+                "'{}' is already registered with {} as key {}",
+                self.host, self.provider, existing.key_id
+            )));
+        }
+
+        let title = match self.title {
+            Some(title) => title.to_string(),
+            None => format!("{} ({})", self.host, whoami::machine_name()),
+        };
+        // Re-registering a key the account already has would be rejected; adopt it instead.
+        let remote = match self
+            .provider
+            .list(api_url, self.token)?
+            .into_iter()
+            .find(|remote| remote.key.split_whitespace().take(2).eq(key.split_whitespace()))
+        {
+            Some(remote) => remote,
+            None => self.provider.upload(api_url, self.token, &title, &key)?,
+        };
+
+        let registration = Registration {
+            provider: self.provider,
+            api_url: api_url.to_string(),
+            key_id: remote.id,
+            title: if remote.title.is_empty() { title } else { remote.title },
+        };
+        meta.registrations.push(registration.clone());
+        metadata::save(paths, self.host, &meta)?;
+        let entry =
+            HistoryEntry::new(Operation::Register, self.host, vec![paths.metadata_path(self.host)]);
+        history::record(paths, &entry.with_key_line(&key))?;
+        Ok(registration)
+    }
+}
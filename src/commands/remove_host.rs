@@ -1,43 +1,227 @@
+use crate::commands::list_hosts::ListHosts;
 use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::key_store::{self, KeyStore};
+use crate::metadata::Registration;
+use crate::ownership::{self, Ownership};
+use crate::providers::Provider;
 use crate::ssh_paths::SshPaths;
+use crate::{allowed_signers, metadata, ssh_config, trash};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Component, Path, PathBuf};
 
+/// What removing a host left behind elsewhere.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RemovalReport {
+    /// Managed hosts that still jump through the removed host.
+    pub dependents: Vec<String>,
+    /// Provider keys deleted with the supplied tokens.
+    pub deregistered: Vec<Registration>,
+    /// Provider keys still registered because no token was supplied for their provider.
+    pub registrations_left: Vec<Registration>,
+    /// Whether the removed key was still deployed to the server's `authorized_keys`.
+    pub still_deployed: bool,
+    /// Whether the files went to the trash, from where `ssv restore` brings them back.
+    pub trashed: bool,
+}
+
+/// Files a removal would delete.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RemovalPlan {
+    pub files: Vec<PathBuf>,
+    /// Identity files whose private keys are held in the key store and deleted from it.
+    pub stored: Vec<PathBuf>,
+    /// The key files were matched by file name because the host has no config to name them.
+    pub guessed: bool,
+}
+
 pub(crate) struct RemoveHost<'a> {
     pub host: &'a str,
+    /// Remove the host even when other managed hosts still jump through it.
+    pub force: bool,
+    /// Delete the config even when ssv did not write it.
+    pub force_foreign: bool,
+    /// Delete key files matched by file name when the host has no config.
+    pub allow_guess: bool,
+    /// Provider API tokens used to delete keys uploaded with `ssv register`.
+    pub tokens: &'a BTreeMap<Provider, String>,
 }
 
 impl<'a> RemoveHost<'a> {
-    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<(), AppError> {
+    pub(crate) fn execute(
+        &self,
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+    ) -> Result<RemovalReport, AppError> {
         paths.ensure_base_dirs()?;
         paths.validate_host(self.host)?;
 
+        let dependents = Self::dependents(paths, self.host)?;
+        if !dependents.is_empty() && !self.force {
+            return Err(AppError::validation_error(format!(
+                "'{}' is the jump host for {}; remove those first or pass --force",
+                self.host,
+                dependents.join(", ")
+            )));
+        }
+
         let config_path = paths.host_config_path(self.host);
-        let mut identity_candidates = Vec::new();
+        if !self.force_foreign
+            && config_path.exists()
+            && ownership::status(paths, self.host)? == Ownership::Foreign
+        {
+            return Err(AppError::validation_error(format!(
+                "{} was not written by ssv; pass --force-foreign to delete it anyway",
+                config_path.display()
+            )));
+        }
 
-        if let Ok(config_contents) = fs::read_to_string(&config_path) {
-            identity_candidates.extend(self.parse_identity_files(&config_contents, paths));
+        let plan = self.plan(paths, store)?;
+        // Without a config, metadata or key there is nothing to remove, let alone restore.
+        if plan.files.is_empty() && plan.stored.is_empty() {
+            return Err(AppError::HostNotFound(self.host.to_string()));
+        }
+        if plan.guessed && !self.allow_guess {
+            return Err(AppError::validation_error(format!(
+                "'{}' has no config; refusing to delete key files guessed by name ({}); \
+                 pass --allow-guess to delete them",
+                self.host,
+                plan.files
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
         }
 
-        Self::remove_if_exists(&config_path)?;
+        let files = plan.files.iter().chain(&plan.stored).cloned().collect();
+        let mut entry = HistoryEntry::new(Operation::Remove, self.host, files);
+        if let Some(public_key) =
+            plan.files.iter().find(|path| path.extension() == Some("pub".as_ref()))
+        {
+            entry = entry.with_key(public_key);
+        }
 
-        if identity_candidates.is_empty() {
-            identity_candidates.extend(self.guess_identity_files(paths));
+        // Remote keys go first so a failed API call leaves the host intact for a retry.
+        let meta = metadata::load(paths, self.host)?.unwrap_or_default();
+        let registrations = meta.registrations;
+        let mut report = RemovalReport {
+            dependents,
+            still_deployed: meta.deployment.is_some(),
+            ..RemovalReport::default()
+        };
+        for registration in registrations {
+            match self.tokens.get(&registration.provider) {
+                Some(token) => {
+                    registration.provider.delete(
+                        &registration.api_url,
+                        token,
+                        registration.key_id,
+                    )?;
+                    report.deregistered.push(registration);
+                }
+                None => report.registrations_left.push(registration),
+            }
         }
 
-        identity_candidates.retain(|p| p.starts_with(paths.ssh_root()));
+        report.trashed = trash::put(paths, store, self.host, &plan.files, &plan.stored)?.is_some();
+        if !report.trashed {
+            for path in &plan.files {
+                Self::remove_if_exists(path)?;
+            }
+            for key in &plan.stored {
+                key_store::forget(store, key)?;
+            }
+        }
+        allowed_signers::remove(paths, self.host)?;
+        ownership::forget(paths, self.host)?;
+        history::record(paths, &entry)?;
 
-        for key_path in identity_candidates {
-            Self::remove_if_exists(&key_path)?;
-            if let Some(pub_path) = Self::to_public_key_path(&key_path) {
-                Self::remove_if_exists(&pub_path)?;
+        Ok(report)
+    }
+
+    /// Existing files that [`execute`](Self::execute) would delete: the config, the metadata
+    /// sidecar and the key pair named by the config, or guessed from file names without one, plus
+    /// the keys it would delete from an external key store.
+    pub(crate) fn plan(
+        &self,
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+    ) -> Result<RemovalPlan, AppError> {
+        paths.validate_host(self.host)?;
+        let config_path = paths.host_config_path(self.host);
+        let mut identity_files = match fs::read_to_string(&config_path) {
+            Ok(contents) => Self::parse_identity_files(&contents, paths),
+            Err(_) => Vec::new(),
+        };
+
+        let guessed = identity_files.is_empty();
+        if guessed {
+            identity_files = self.guess_identity_files(paths);
+        }
+        identity_files.retain(|p| p.starts_with(paths.ssh_root()));
+        // Guesses come from a directory listing, so a match always names an existing file.
+        let guessed = guessed && !identity_files.is_empty();
+
+        let mut files = vec![config_path, paths.metadata_path(self.host)];
+        let mut stored = Vec::new();
+        for key_path in identity_files {
+            if key_store::is_external(store, &key_path)
+                && key_store::stored(store, &key_path)?.is_some()
+            {
+                stored.push(key_path.clone());
             }
+            let public = Self::to_public_key_path(&key_path);
+            files.push(key_path);
+            files.extend(public);
         }
+        files.retain(|path| path.exists());
+        Ok(RemovalPlan { files, stored, guessed })
+    }
 
-        Ok(())
+    /// Managed hosts whose `ProxyJump` chain or recorded `via` goes through `host`.
+    pub(crate) fn dependents(paths: &SshPaths, host: &str) -> Result<Vec<String>, AppError> {
+        let mut dependents = Vec::new();
+        for other in ListHosts.execute(paths)? {
+            if other == host {
+                continue;
+            }
+            let Ok(contents) = fs::read_to_string(paths.host_config_path(&other)) else {
+                continue;
+            };
+            let jumps = ssh_config::jump_hosts(&ssh_config::parse(&contents));
+            let via = metadata::load(paths, &other).ok().flatten().and_then(|meta| meta.via);
+            if jumps.iter().any(|jump| jump == host) || via.as_deref() == Some(host) {
+                dependents.push(other);
+            }
+        }
+        Ok(dependents)
+    }
+
+    /// The first key pair named by a host's config that ssv manages: an `IdentityFile` underneath
+    /// `~/.ssh` whose public key exists. The private key may be locked away in the key store.
+    pub(crate) fn managed_key_pair(
+        paths: &SshPaths,
+        host: &str,
+    ) -> Result<(PathBuf, PathBuf), AppError> {
+        let config = fs::read_to_string(paths.host_config_path(host))
+            .map_err(|_| AppError::HostNotFound(host.to_string()))?;
+        Self::parse_identity_files(&config, paths)
+            .into_iter()
+            .filter(|key| key.starts_with(paths.ssh_root()))
+            .find_map(|key| {
+                let public_key = Self::to_public_key_path(&key)?;
+                public_key.exists().then_some((key, public_key))
+            })
+            .ok_or_else(|| {
+                AppError::validation_error(format!("host '{host}' has no managed key pair on disk"))
+            })
     }
 
-    fn parse_identity_files(&self, contents: &str, paths: &SshPaths) -> Vec<PathBuf> {
+    /// Resolve the `IdentityFile` entries of a host config that live underneath `~/.ssh`.
+    pub(crate) fn parse_identity_files(contents: &str, paths: &SshPaths) -> Vec<PathBuf> {
         contents
             .lines()
             .filter_map(|line| {
@@ -108,7 +292,7 @@ impl<'a> RemoveHost<'a> {
         }
     }
 
-    fn to_public_key_path(private: &Path) -> Option<PathBuf> {
+    pub(crate) fn to_public_key_path(private: &Path) -> Option<PathBuf> {
         let mut file_name = private.file_name()?.to_os_string();
         file_name.push(".pub");
         Some(private.with_file_name(file_name))
@@ -0,0 +1,38 @@
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::key_store::KeyStore;
+use crate::ssh_paths::SshPaths;
+use crate::trash::{self, TrashEntry};
+
+/// Command object that brings the most recently removed copy of a host back from the trash.
+pub(crate) struct RestoreHost<'a> {
+    pub host: &'a str,
+}
+
+impl<'a> RestoreHost<'a> {
+    pub(crate) fn execute(
+        &self,
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+    ) -> Result<TrashEntry, AppError> {
+        paths.validate_host(self.host)?;
+        if paths.host_config_path(self.host).exists() {
+            return Err(AppError::validation_error(format!(
+                "'{}' is managed already; remove it before restoring the old one",
+                self.host
+            )));
+        }
+        paths.ensure_base_dirs()?;
+        let entry = trash::restore(paths, store, self.host)?;
+
+        let files = entry.files.iter().chain(&entry.stored).cloned().collect();
+        let mut record = HistoryEntry::new(Operation::Restore, self.host, files);
+        if let Some(public_key) =
+            entry.files.iter().find(|path| path.extension() == Some("pub".as_ref()))
+        {
+            record = record.with_key(public_key);
+        }
+        history::record(paths, &record)?;
+        Ok(entry)
+    }
+}
@@ -0,0 +1,145 @@
+use crate::commands::deploy_key::RedeployKey;
+use crate::commands::remove_host::RemoveHost;
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::key_info::{self, PublicKeyInfo};
+use crate::key_store::{self, KeyStore};
+use crate::keygen::{KeyGenerator, KeyRequest};
+use crate::metadata::{self, Registration};
+use crate::ssh_paths::SshPaths;
+use crate::{allowed_signers, timestamp};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Result of replacing a host's key pair.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RotationReport {
+    pub host: String,
+    pub key: PathBuf,
+    pub old_fingerprint: Option<String>,
+    pub new_fingerprint: Option<String>,
+    /// The old key was deployed with `ssv deploy` and has been replaced by the new one there.
+    pub redeployed: bool,
+    /// Provider registrations that still point at the old key.
+    pub stale_registrations: Vec<Registration>,
+}
+
+/// Command object that replaces a host's key pair in place, keeping type, size and comment.
+pub(crate) struct RotateKey<'a> {
+    pub host: &'a str,
+    /// Passphrase for the new private key; required when the old one is encrypted, and an empty
+    /// one explicitly drops the protection.
+    pub passphrase: Option<&'a str>,
+}
+
+impl<'a> RotateKey<'a> {
+    pub(crate) fn execute(
+        &self,
+        paths: &SshPaths,
+        keygen: &dyn KeyGenerator,
+        store: &dyn KeyStore,
+    ) -> Result<RotationReport, AppError> {
+        paths.validate_host(self.host)?;
+        let (private_key, public_key) = RemoveHost::managed_key_pair(paths, self.host)?;
+
+        let old = fs::read_to_string(&public_key)?;
+        let old_info = PublicKeyInfo::parse(&old).ok_or_else(|| {
+            AppError::validation_error(format!("{} is malformed", public_key.display()))
+        })?;
+
+        if self.passphrase.is_none() && Self::encrypted(store, &private_key)? {
+            return Err(AppError::validation_error(format!(
+                "the key of '{}' is protected by a passphrase; give one for the new key (an empty \
+                 one leaves it unencrypted)",
+                self.host
+            )));
+        }
+
+        // Generate next to the old pair and swap only once the new pair is complete.
+        let staged = sibling(&private_key, ".rotating");
+        let request = KeyRequest {
+            key_type: old_info.key_type(),
+            bits: old_info.bits().filter(|_| matches!(old_info.key_type(), "rsa" | "ecdsa")),
+            passphrase: self.passphrase.filter(|passphrase| !passphrase.is_empty()),
+            comment: old_info.comment.as_deref(),
+            private_key: &staged,
+        };
+        let staged_public = request.public_key();
+        let mut meta = metadata::load(paths, self.host)?.unwrap_or_default();
+        let result = keygen.generate(&request).and_then(|()| {
+            // A deployed key is swapped on the server while the old key can still log in;
+            // replacing it locally first would lock the user out.
+            if let Some(deployment) = &meta.deployment {
+                let redeploy = RedeployKey {
+                    host: self.host,
+                    old_public_key: &old,
+                    new_public_key: &fs::read_to_string(&staged_public)?,
+                    key_options: deployment.key_options.as_deref(),
+                };
+                meta.deployment = Some(redeploy.execute(paths).map_err(|err| {
+                    AppError::validation_error(format!(
+                        "could not deploy the new key to '{}', so the old key was kept: {err}",
+                        self.host
+                    ))
+                })?);
+            }
+            key_store::install(store, &staged, &private_key)?;
+            fs::rename(&staged_public, &public_key)?;
+            Ok(())
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&staged);
+            let _ = fs::remove_file(&staged_public);
+        }
+        result?;
+
+        let new = fs::read_to_string(&public_key)?;
+        allowed_signers::replace_key(paths, self.host, &new)?;
+        let redeployed = meta.deployment.is_some();
+        meta.created_at = Some(timestamp::now_rfc3339());
+        metadata::save(paths, self.host, &meta)?;
+        let files = vec![private_key.clone(), public_key.clone(), paths.metadata_path(self.host)];
+        history::record(
+            paths,
+            &HistoryEntry::new(Operation::Rotate, self.host, files).with_key(&public_key),
+        )?;
+
+        Ok(RotationReport {
+            host: self.host.to_string(),
+            key: private_key,
+            old_fingerprint: old_info.fingerprint(),
+            new_fingerprint: PublicKeyInfo::parse(&new).and_then(|info| info.fingerprint()),
+            redeployed,
+            stale_registrations: meta.registrations,
+        })
+    }
+
+    /// Whether the current private key of `host` is protected by a passphrase.
+    pub(crate) fn key_encrypted(
+        paths: &SshPaths,
+        store: &dyn KeyStore,
+        host: &str,
+    ) -> Result<bool, AppError> {
+        paths.validate_host(host)?;
+        let (private_key, _) = RemoveHost::managed_key_pair(paths, host)?;
+        Self::encrypted(store, &private_key)
+    }
+
+    fn encrypted(store: &dyn KeyStore, private_key: &Path) -> Result<bool, AppError> {
+        let contents = match key_store::stored(store, private_key)? {
+            Some(contents) => contents,
+            None => match fs::read(private_key) {
+                Ok(contents) => contents,
+                Err(_) => return Ok(false),
+            },
+        };
+        Ok(key_info::private_key_encrypted(&String::from_utf8_lossy(&contents)) == Some(true))
+    }
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
@@ -0,0 +1,222 @@
+use crate::commands::list_hosts::ListHosts;
+use crate::commands::remove_host::RemoveHost;
+use crate::error::AppError;
+use crate::key_info::PublicKeyInfo;
+use crate::metadata::{self, HostMetadata};
+use crate::ssh_config::{self, Directive};
+use crate::ssh_paths::SshPaths;
+use crate::timestamp;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Attributes accepted by `--filter KEY=VALUE`.
+pub const FILTER_KEYS: &[&str] = &[
+    "hostname", "user", "port", "type", "via", "purpose", "ticket", "comment", "creator",
+    "deployed", "provider",
+];
+
+/// Which hosts a command acts on. An empty selector means every managed host.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostSelector {
+    /// Hosts named explicitly; they are taken as given, even when no config exists for them.
+    pub names: Vec<String>,
+    /// `*`/`?` globs matched against the managed host names.
+    pub patterns: Vec<String>,
+    /// Conditions every selected host has to meet.
+    pub filters: Vec<HostFilter>,
+}
+
+impl HostSelector {
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.patterns.is_empty() && self.filters.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostFilter {
+    /// One of [`FILTER_KEYS`] whose value matches a glob, case-insensitively.
+    Attribute { key: String, value: String },
+    /// The key pair was generated more than this many days ago.
+    OlderThan(u64),
+}
+
+impl HostFilter {
+    /// Parse an age such as `180d`, `26w` or `1y` into an [`OlderThan`](Self::OlderThan) filter.
+    pub fn older_than(raw: &str) -> Result<Self, AppError> {
+        let invalid = || {
+            AppError::validation_error(format!(
+                "invalid age '{raw}'; expected a number of days, weeks or years such as 180d, 26w or 1y"
+            ))
+        };
+        let unit_start = raw.char_indices().last().map(|(i, _)| i).ok_or_else(invalid)?;
+        let (count, unit) = raw.split_at(unit_start);
+        let count: u64 = count.parse().map_err(|_| invalid())?;
+        let days_per_unit = match unit {
+            "d" => 1,
+            "w" => 7,
+            "y" => 365,
+            _ => return Err(invalid()),
+        };
+        Ok(HostFilter::OlderThan(count.checked_mul(days_per_unit).ok_or_else(invalid)?))
+    }
+}
+
+impl FromStr for HostFilter {
+    type Err = AppError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let (key, value) = raw
+            .split_once('=')
+            .map(|(key, value)| (key.trim().to_ascii_lowercase(), value.trim()))
+            .filter(|(_, value)| !value.is_empty())
+            .ok_or_else(|| {
+                AppError::validation_error(format!("expected KEY=VALUE filter, got '{raw}'"))
+            })?;
+        if !FILTER_KEYS.contains(&key.as_str()) {
+            return Err(AppError::validation_error(format!(
+                "unknown filter '{key}'; expected one of {}",
+                FILTER_KEYS.join(", ")
+            )));
+        }
+        Ok(HostFilter::Attribute { key, value: value.to_string() })
+    }
+}
+
+/// Command object that resolves a [`HostSelector`] against the managed inventory.
+pub(crate) struct SelectHosts<'a> {
+    pub selector: &'a HostSelector,
+}
+
+impl<'a> SelectHosts<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<Vec<String>, AppError> {
+        let selector = self.selector;
+        let managed = ListHosts.execute(paths)?;
+
+        let mut hosts: Vec<String> = Vec::new();
+        for name in &selector.names {
+            paths.validate_host(name)?;
+            if !hosts.contains(name) {
+                hosts.push(name.clone());
+            }
+        }
+        for host in managed {
+            let wanted = if selector.names.is_empty() && selector.patterns.is_empty() {
+                true
+            } else {
+                selector.patterns.iter().any(|pattern| ssh_config::pattern_matches(pattern, &host))
+            };
+            if wanted && !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+
+        if selector.filters.is_empty() {
+            return Ok(hosts);
+        }
+        let mut selected = Vec::new();
+        for host in hosts {
+            let facts = HostFacts::load(paths, &host)?;
+            if selector.filters.iter().all(|filter| facts.matches(filter)) {
+                selected.push(host);
+            }
+        }
+        Ok(selected)
+    }
+}
+
+/// What filters are evaluated against for one host.
+struct HostFacts {
+    host: String,
+    config: Vec<Directive>,
+    meta: HostMetadata,
+    private_key: Option<PathBuf>,
+}
+
+impl HostFacts {
+    fn load(paths: &SshPaths, host: &str) -> Result<Self, AppError> {
+        let contents = fs::read_to_string(paths.host_config_path(host)).unwrap_or_default();
+        let private_key =
+            RemoveHost::parse_identity_files(&contents, paths).into_iter().find(|key| key.exists());
+        Ok(Self {
+            host: host.to_string(),
+            config: ssh_config::parse(&contents),
+            meta: metadata::load(paths, host)?.unwrap_or_default(),
+            private_key,
+        })
+    }
+
+    fn matches(&self, filter: &HostFilter) -> bool {
+        match filter {
+            HostFilter::Attribute { key, value } => self
+                .attribute(key)
+                .iter()
+                .any(|candidate| ssh_config::pattern_matches(value, candidate)),
+            HostFilter::OlderThan(days) => self.age_days().is_some_and(|age| age > *days),
+        }
+    }
+
+    fn attribute(&self, key: &str) -> Vec<String> {
+        let directive = |name| ssh_config::value(&self.config, name).map(str::to_string);
+        let meta = &self.meta;
+        let value = match key {
+            "hostname" => directive("HostName").or_else(|| Some(self.host.clone())),
+            "user" => directive("User"),
+            "port" => directive("Port").or_else(|| Some("22".to_string())),
+            "type" => self.key_type(),
+            "via" => meta.via.clone().or_else(|| ssh_config::jump_hosts(&self.config).pop()),
+            "purpose" => meta.purpose.clone(),
+            "ticket" => meta.ticket.clone(),
+            "comment" => meta.comment.clone(),
+            "creator" => meta.creator.clone(),
+            "deployed" => Some(if meta.deployment.is_some() { "yes" } else { "no" }.to_string()),
+            "provider" => {
+                return meta.registrations.iter().map(|r| r.provider.to_string()).collect();
+            }
+            _ => None,
+        };
+        value.into_iter().collect()
+    }
+
+    fn key_type(&self) -> Option<String> {
+        let public_key = RemoveHost::to_public_key_path(self.private_key.as_ref()?)?;
+        let contents = fs::read_to_string(public_key).ok()?;
+        PublicKeyInfo::parse(&contents).map(|info| info.key_type().to_string())
+    }
+
+    /// Age from the recorded creation time, falling back to the key file's mtime like `audit`.
+    fn age_days(&self) -> Option<u64> {
+        let created = match &self.meta.created_at {
+            Some(created_at) => timestamp::parse(created_at).ok()?,
+            None => fs::metadata(self.private_key.as_ref()?).ok()?.modified().ok()?,
+        };
+        Some(timestamp::days_since(created))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_attribute_filters() {
+        assert_eq!(
+            "User=git".parse::<HostFilter>().unwrap(),
+            HostFilter::Attribute { key: "user".into(), value: "git".into() }
+        );
+        assert!("user".parse::<HostFilter>().is_err());
+        assert!("user=".parse::<HostFilter>().is_err());
+        let err = "colour=red".parse::<HostFilter>().unwrap_err().to_string();
+        assert!(err.contains("unknown filter 'colour'"), "{err}");
+    }
+
+    #[test]
+    fn parses_ages() {
+        assert_eq!(HostFilter::older_than("180d").unwrap(), HostFilter::OlderThan(180));
+        assert_eq!(HostFilter::older_than("2w").unwrap(), HostFilter::OlderThan(14));
+        assert_eq!(HostFilter::older_than("1y").unwrap(), HostFilter::OlderThan(365));
+        for raw in ["", "d", "180", "180m", "-1d", "1.5w", "é"] {
+            assert!(HostFilter::older_than(raw).is_err(), "{raw} should be rejected");
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::commands::remove_host::RemoveHost;
+use crate::error::AppError;
+use crate::key_info::{CertificateInfo, PublicKeyInfo};
+use crate::known_hosts;
+use crate::metadata::{self, HostMetadata};
+use crate::ssh_config::{self, Directive};
+use crate::ssh_paths::SshPaths;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+/// Key types probed when a host config carries no `IdentityFile`.
+const FALLBACK_KEY_TYPES: &[&str] = &["ed25519", "ecdsa", "rsa", "dsa"];
+
+/// A key-related file belonging to a host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyFile {
+    /// `private`, `public` or `certificate`.
+    pub role: &'static str,
+    pub path: PathBuf,
+    pub exists: bool,
+    pub mode: Option<String>,
+    pub size: Option<u64>,
+}
+
+/// Everything ssv knows about one managed host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostDetails {
+    pub host: String,
+    pub config_path: PathBuf,
+    pub config: Vec<Directive>,
+    pub keys: Vec<KeyFile>,
+    pub fingerprint: Option<String>,
+    pub public_key: Option<String>,
+    pub certificate: Option<CertificateInfo>,
+    pub known_hosts: Vec<String>,
+    pub metadata: Option<HostMetadata>,
+}
+
+/// Command object that gathers [`HostDetails`] for a host.
+pub(crate) struct ShowHost<'a> {
+    pub host: &'a str,
+}
+
+impl<'a> ShowHost<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<HostDetails, AppError> {
+        paths.validate_host(self.host)?;
+
+        let config_path = paths.host_config_path(self.host);
+        let contents = match fs::read_to_string(&config_path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(AppError::HostNotFound(self.host.to_string()));
+            }
+            Err(err) => return Err(AppError::from(err)),
+        };
+        let config = ssh_config::parse(&contents);
+
+        let mut identities = RemoveHost::parse_identity_files(&contents, paths);
+        if identities.is_empty() {
+            identities = FALLBACK_KEY_TYPES
+                .iter()
+                .map(|key_type| paths.key_paths(key_type, self.host).0)
+                .filter(|path| path.exists())
+                .collect();
+        }
+
+        let mut keys = Vec::new();
+        let mut public_key = None;
+        let mut certificate = None;
+        for private in &identities {
+            keys.push(Self::key_file("private", private.clone()));
+            if let Some(public) = RemoveHost::to_public_key_path(private) {
+                if public_key.is_none() {
+                    public_key = fs::read_to_string(&public).ok().map(|s| s.trim().to_string());
+                }
+                keys.push(Self::key_file("public", public));
+            }
+
+            let mut cert_name = private.file_name().unwrap_or_default().to_os_string();
+            cert_name.push("-cert.pub");
+            let cert = private.with_file_name(cert_name);
+            if cert.exists() {
+                if certificate.is_none() {
+                    certificate = fs::read_to_string(&cert)
+                        .ok()
+                        .and_then(|line| PublicKeyInfo::parse(&line))
+                        .and_then(|info| CertificateInfo::parse(&info));
+                }
+                keys.push(Self::key_file("certificate", cert));
+            }
+        }
+
+        let fingerprint =
+            public_key.as_deref().and_then(PublicKeyInfo::parse).and_then(|key| key.fingerprint());
+
+        let names = known_hosts::lookup_names(self.host, &config);
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        Ok(HostDetails {
+            host: self.host.to_string(),
+            config_path,
+            config,
+            keys,
+            fingerprint,
+            public_key,
+            certificate,
+            known_hosts: known_hosts::entries_for(paths, &names)?,
+            metadata: metadata::load(paths, self.host)?,
+        })
+    }
+
+    fn key_file(role: &'static str, path: PathBuf) -> KeyFile {
+        let meta = fs::metadata(&path).ok();
+        KeyFile {
+            role,
+            exists: meta.is_some(),
+            mode: meta.as_ref().and_then(Self::mode),
+            size: meta.map(|meta| meta.len()),
+            path,
+        }
+    }
+
+    fn mode(meta: &fs::Metadata) -> Option<String> {
+        #[cfg(unix)]
+        return Some(format!("{:04o}", meta.permissions().mode() & 0o777));
+        #[cfg(not(unix))]
+        {
+            let _ = meta;
+            None
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::error::AppError;
+use crate::metadata;
+use crate::ssh_paths::SshPaths;
+use serde::Serialize;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Output fragments that git hosting providers print after a successful key login. Most of these
+/// services close the session with a non-zero status because they refuse shell access.
+const SUCCESS_PATTERNS: &[&str] = &[
+    "successfully authenticated",
+    "welcome to gitlab",
+    "authenticated via ssh key",
+    "logged in as",
+];
+
+/// Extra time granted beyond `ConnectTimeout` before the ssh process is killed.
+const KILL_GRACE: Duration = Duration::from_secs(5);
+
+/// Outcome of a connection test against a managed host alias.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConnectionReport {
+    pub host: String,
+    pub passed: bool,
+    /// Human-readable explanation of the verdict.
+    pub reason: String,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// Combined stdout and stderr of the ssh invocation.
+    pub output: String,
+}
+
+/// Command object that runs a non-interactive `ssh -T` against a managed host.
+pub(crate) struct TestHost<'a> {
+    pub host: &'a str,
+    pub timeout: Duration,
+}
+
+impl<'a> TestHost<'a> {
+    pub(crate) fn execute(&self, paths: &SshPaths) -> Result<ConnectionReport, AppError> {
+        paths.validate_host(self.host)?;
+        if !paths.host_config_path(self.host).exists() {
+            return Err(AppError::HostNotFound(self.host.to_string()));
+        }
+        if let Some(meta) = metadata::load(paths, self.host)?
+            && !meta.patterns.is_empty()
+        {
+            return Err(AppError::validation_error(format!(
+                "'{}' is a pattern entry ({}); connect to a concrete host name instead",
+                self.host,
+                meta.patterns.join(" ")
+            )));
+        }
+
+        let mut child = Command::new(paths.ssh())
+            .arg("-T")
+            .arg("-o")
+            .arg("BatchMode=yes")
+            .arg("-o")
+            .arg(format!("ConnectTimeout={}", self.timeout.as_secs().max(1)))
+            .arg(self.host)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let readers: Vec<_> = [
+            child.stdout.take().map(|s| Box::new(s) as Box<dyn Read + Send>),
+            child.stderr.take().map(|s| Box::new(s) as Box<dyn Read + Send>),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|mut stream| {
+            thread::spawn(move || {
+                let mut buffer = String::new();
+                let _ = stream.read_to_string(&mut buffer);
+                buffer
+            })
+        })
+        .collect();
+
+        let deadline = Instant::now() + self.timeout + KILL_GRACE;
+        let mut timed_out = false;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break Some(status);
+            }
+            if Instant::now() >= deadline {
+                timed_out = true;
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+            thread::sleep(Duration::from_millis(50));
+        };
+
+        let output: String = readers.into_iter().filter_map(|reader| reader.join().ok()).collect();
+        let exit_code = status.and_then(|status| status.code());
+        let (passed, reason) = Self::interpret(exit_code, timed_out, &output);
+
+        Ok(ConnectionReport {
+            host: self.host.to_string(),
+            passed,
+            reason,
+            exit_code,
+            timed_out,
+            output: output.trim().to_string(),
+        })
+    }
+
+    fn interpret(exit_code: Option<i32>, timed_out: bool, output: &str) -> (bool, String) {
+        let lowered = output.to_ascii_lowercase();
+        if timed_out {
+            return (false, "connection timed out".to_string());
+        }
+        if let Some(pattern) = SUCCESS_PATTERNS.iter().find(|pattern| lowered.contains(**pattern)) {
+            return (true, format!("server reported \"{pattern}\""));
+        }
+        if lowered.contains("permission denied") {
+            return (false, "authentication was rejected; is the public key registered?".into());
+        }
+        if lowered.contains("host key verification failed") {
+            return (false, "host key is unknown or has changed".into());
+        }
+
+        match exit_code {
+            Some(0) => (true, "ssh exited successfully".to_string()),
+            Some(255) => (false, "ssh could not connect (exit status 255)".to_string()),
+            Some(code) => (false, format!("ssh exited with status {code}")),
+            None => (false, "ssh was terminated by a signal".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn github_greeting_passes_despite_exit_status() {
+        let output = "Hi octocat! You've successfully authenticated, but GitHub does not provide shell access.";
+        assert!(TestHost::interpret(Some(1), false, output).0);
+    }
+
+    #[test]
+    fn rejected_keys_and_timeouts_fail() {
+        assert!(
+            !TestHost::interpret(Some(255), false, "git@host: Permission denied (publickey).").0
+        );
+        assert!(!TestHost::interpret(None, true, "").0);
+        assert!(TestHost::interpret(Some(0), false, "").0);
+    }
+}
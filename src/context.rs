@@ -0,0 +1,519 @@
+//! [`Ssv`], the library's entry point: every operation against one explicit home directory.
+
+use crate::GenerateOptions;
+use crate::authorized_keys::{self, AuthorizedKey};
+use crate::commands::apply_manifest::{ApplyManifest, Plan};
+use crate::commands::audit_hosts::{AuditHosts, AuditPolicy, AuditReport};
+use crate::commands::deploy_key::{DeployAuth, DeployKey, UndeployKey};
+use crate::commands::doctor::{Adopt, Doctor, DoctorReport};
+use crate::commands::export_hosts::ExportHosts;
+use crate::commands::generate_host::{GenerateHost, GeneratedHost};
+use crate::commands::git_repo::{GitBinding, GitSigning, GitStatus, GitUse, SigningSetup};
+use crate::commands::import_hosts::ImportHosts;
+use crate::commands::list_hosts::{HostEntry, ListHosts};
+use crate::commands::lock_keys::{AgentKey, KeyLock, LockKey, UnlockKey};
+use crate::commands::register_key::RegisterKey;
+use crate::commands::remove_host::{RemovalPlan, RemovalReport, RemoveHost};
+use crate::commands::restore_host::RestoreHost;
+use crate::commands::rotate_key::{RotateKey, RotationReport};
+use crate::commands::select_hosts::{HostSelector, SelectHosts};
+use crate::commands::show_host::{HostDetails, ShowHost};
+use crate::commands::test_host::{ConnectionReport, TestHost};
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, Operation};
+use crate::key_store::{FileStore, KeyStore, VaultStore};
+use crate::keygen::{KeyBackend, KeyGenerator};
+use crate::metadata::{self, Deployment, HostMetadata, Registration};
+use crate::ownership::{self, Ownership};
+use crate::providers::Provider;
+use crate::settings::{self, Settings};
+use crate::ssh_paths::SshPaths;
+use crate::templates::{self, Template};
+use crate::trash::{self, TrashEntry};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Options accepted by [`Ssv::remove`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoveOptions {
+    /// Remove the host even when other managed hosts still jump through it.
+    pub force: bool,
+    /// Remove the host even when ssv did not write its config.
+    pub force_foreign: bool,
+    /// Without a config naming the keys, also delete key files matched by name.
+    pub allow_guess: bool,
+    /// Provider API tokens used to delete keys uploaded with [`Ssv::register`]; keys registered
+    /// with other providers are left and reported.
+    pub tokens: BTreeMap<Provider, String>,
+}
+
+/// Options accepted by [`Ssv::rotate_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RotateOptions {
+    /// Passphrase for the new private key. Required when the old key is encrypted, see
+    /// [`Ssv::key_encrypted`]; an empty one leaves the new key unencrypted.
+    pub passphrase: Option<String>,
+}
+
+/// Handle on the SSH assets under one home directory.
+///
+/// Paths, backend and vault passphrase are taken from the environment once, by
+/// [`from_env`](Self::from_env). Only the local user and machine name, which go into key
+/// comments, metadata and the history, are looked up when they are used. No method prints;
+/// results come back as values for the caller to present.
+///
+/// Key pairs come from the [`KeyGenerator`] given to [`with_key_generator`](Self::with_key_generator)
+/// or [`with_backend`](Self::with_backend), otherwise from the `backend` setting, otherwise from
+/// `ssh-keygen`. Private keys are kept by the [`KeyStore`] given to
+/// [`with_key_store`](Self::with_key_store), otherwise by the one the `storage` setting names.
+#[derive(Clone)]
+pub struct Ssv {
+    paths: SshPaths,
+    keygen: Option<Arc<dyn KeyGenerator>>,
+    store: Option<Arc<dyn KeyStore>>,
+    vault_passphrase: Option<String>,
+}
+
+impl fmt::Debug for Ssv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ssv")
+            .field("paths", &self.paths)
+            .field("keygen", &self.keygen.as_ref().map(|_| "custom"))
+            .field("store", &self.store.as_ref().map(|_| "custom"))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Ssv {
+    /// Manage `<home>/.ssh`, with the defaults of [`SshPaths::new`].
+    pub fn new(home: impl Into<PathBuf>) -> Self {
+        Self::with_paths(SshPaths::new(home))
+    }
+
+    pub fn with_paths(paths: SshPaths) -> Self {
+        Self { paths, keygen: None, store: None, vault_passphrase: None }
+    }
+
+    /// Resolve everything the way the `ssv` binary does: the paths from [`SshPaths::from_env`],
+    /// the key backend from `SSV_BACKEND` and the vault passphrase from `SSV_VAULT_PASSPHRASE`
+    /// when they are set.
+    pub fn from_env() -> Result<Self, AppError> {
+        let mut ssv = Self::with_paths(SshPaths::from_env()?);
+        if let Ok(backend) = std::env::var("SSV_BACKEND")
+            && !backend.is_empty()
+        {
+            ssv = ssv.with_backend(backend.parse()?);
+        }
+        if let Ok(passphrase) = std::env::var("SSV_VAULT_PASSPHRASE")
+            && !passphrase.is_empty()
+        {
+            ssv = ssv.with_vault_passphrase(passphrase);
+        }
+        Ok(ssv)
+    }
+
+    /// Generate keys with one of the built-in backends, ignoring the `backend` setting.
+    pub fn with_backend(self, backend: KeyBackend) -> Self {
+        let keygen = backend.generator(&self.paths);
+        Self { keygen: Some(keygen), ..self }
+    }
+
+    /// Generate keys with a custom [`KeyGenerator`], for example one backed by a secrets manager.
+    pub fn with_key_generator(self, keygen: impl KeyGenerator + 'static) -> Self {
+        Self { keygen: Some(Arc::new(keygen)), ..self }
+    }
+
+    /// Keep private keys in a custom [`KeyStore`], ignoring the `storage` setting.
+    pub fn with_key_store(self, store: impl KeyStore + 'static) -> Self {
+        Self { store: Some(Arc::new(store)), ..self }
+    }
+
+    /// Passphrase for the vault at [`SshPaths::vault_path`], used when `storage = "vault"`.
+    pub fn with_vault_passphrase(self, passphrase: impl Into<String>) -> Self {
+        Self { vault_passphrase: Some(passphrase.into()), ..self }
+    }
+
+    /// Whether key operations need [`with_vault_passphrase`](Self::with_vault_passphrase)
+    /// before they can run: the `storage` setting selects the vault and no passphrase is set.
+    pub fn needs_vault_passphrase(&self) -> Result<bool, AppError> {
+        Ok(self.store.is_none()
+            && self.vault_passphrase.is_none()
+            && settings::load(&self.paths)?.storage.as_deref() == Some("vault"))
+    }
+
+    pub fn paths(&self) -> &SshPaths {
+        &self.paths
+    }
+
+    /// Generate a key pair, configuration, and metadata sidecar.
+    pub fn generate(&self, options: &GenerateOptions) -> Result<GeneratedHost, AppError> {
+        let keygen = self.key_generator()?;
+        generate_command(options).execute(&self.paths, keygen.as_ref(), self.key_store()?.as_ref())
+    }
+
+    /// Validate the options and render the host config [`generate`](Self::generate) would write,
+    /// without creating anything.
+    pub fn preview_config(&self, options: &GenerateOptions) -> Result<String, AppError> {
+        let command = generate_command(options);
+        command.validate(&self.paths)?;
+        Ok(command.render_config(&command.default_identity_file()))
+    }
+
+    /// Managed hosts underneath `~/.ssh/conf.d` with their connection settings. Nothing is
+    /// created; a missing `~/.ssh` lists no hosts.
+    pub fn list(&self) -> Result<Vec<HostEntry>, AppError> {
+        ListHosts.entries(&self.paths)
+    }
+
+    /// Names of the managed hosts, sorted.
+    pub fn hosts(&self) -> Result<Vec<String>, AppError> {
+        ListHosts.execute(&self.paths)
+    }
+
+    /// Managed hosts paired with the host they jump through (the last `ProxyJump` hop), if any.
+    pub fn topology(&self) -> Result<Vec<(String, Option<String>)>, AppError> {
+        ListHosts.topology(&self.paths)
+    }
+
+    /// Hosts picked by a selector: the named hosts plus managed hosts matching a glob (every
+    /// managed host when neither is given), narrowed down by the filters.
+    pub fn select(&self, selector: &HostSelector) -> Result<Vec<String>, AppError> {
+        SelectHosts { selector }.execute(&self.paths)
+    }
+
+    /// The metadata sidecar recorded for a host, if any.
+    pub fn metadata(&self, host: &str) -> Result<Option<HostMetadata>, AppError> {
+        self.paths.validate_host(host)?;
+        metadata::load(&self.paths, host)
+    }
+
+    /// Collect the config, key files, fingerprint, certificate, known_hosts entries and metadata
+    /// of a managed host.
+    pub fn show(&self, host: &str) -> Result<HostDetails, AppError> {
+        ShowHost { host }.execute(&self.paths)
+    }
+
+    /// Run `ssh -T` in batch mode against a managed host alias and judge whether login succeeded.
+    pub fn test_connection(
+        &self,
+        host: &str,
+        timeout: Duration,
+    ) -> Result<ConnectionReport, AppError> {
+        TestHost { host, timeout }.execute(&self.paths)
+    }
+
+    /// Check every managed host against a key policy.
+    pub fn audit(&self, policy: &AuditPolicy) -> Result<AuditReport, AppError> {
+        AuditHosts { policy }.execute(&self.paths, self.key_store()?.as_ref())
+    }
+
+    /// Files that removing `host` would delete, for confirmation before [`remove`](Self::remove).
+    pub fn removal_plan(&self, host: &str) -> Result<RemovalPlan, AppError> {
+        let command = RemoveHost {
+            host,
+            force: false,
+            force_foreign: false,
+            allow_guess: false,
+            tokens: &BTreeMap::new(),
+        };
+        command.plan(&self.paths, self.key_store()?.as_ref())
+    }
+
+    /// Remove a host's config, key pair, metadata and `allowed_signers` entry.
+    ///
+    /// Fails while other managed hosts jump through it unless `force` is set, when its config is
+    /// [foreign](Ownership::Foreign) unless `force_foreign` is set, and refuses key files guessed
+    /// by name unless `allow_guess` is set.
+    pub fn remove(&self, host: &str, options: &RemoveOptions) -> Result<RemovalReport, AppError> {
+        let command = RemoveHost {
+            host,
+            force: options.force,
+            force_foreign: options.force_foreign,
+            allow_guess: options.allow_guess,
+            tokens: &options.tokens,
+        };
+        command.execute(&self.paths, self.key_store()?.as_ref())
+    }
+
+    /// Hosts removed within the `trash_retention` setting, oldest first.
+    pub fn trash(&self) -> Result<Vec<TrashEntry>, AppError> {
+        trash::list(&self.paths, None)
+    }
+
+    /// Bring the most recently removed `host` back from the trash: config, key pair, metadata and
+    /// ownership. Provider registrations and `allowed_signers` entries are not restored.
+    pub fn restore(&self, host: &str) -> Result<TrashEntry, AppError> {
+        RestoreHost { host }.execute(&self.paths, self.key_store()?.as_ref())
+    }
+
+    /// Replace a host's key pair with a fresh one of the same type, size and comment. The config,
+    /// metadata and `allowed_signers` entry keep pointing at the same files.
+    ///
+    /// Fails for a passphrase-protected key, which needs [`rotate_with`](Self::rotate_with).
+    pub fn rotate(&self, host: &str) -> Result<RotationReport, AppError> {
+        self.rotate_with(host, &RotateOptions::default())
+    }
+
+    /// [`rotate`](Self::rotate) with a passphrase for the new key.
+    pub fn rotate_with(
+        &self,
+        host: &str,
+        options: &RotateOptions,
+    ) -> Result<RotationReport, AppError> {
+        let keygen = self.key_generator()?;
+        let command = RotateKey { host, passphrase: options.passphrase.as_deref() };
+        command.execute(&self.paths, keygen.as_ref(), self.key_store()?.as_ref())
+    }
+
+    /// Whether the private key of `host` is protected by a passphrase.
+    pub fn key_encrypted(&self, host: &str) -> Result<bool, AppError> {
+        RotateKey::key_encrypted(&self.paths, self.key_store()?.as_ref(), host)
+    }
+
+    /// Move a host's private key into the key store and delete the plain `IdentityFile`.
+    pub fn lock(&self, host: &str) -> Result<KeyLock, AppError> {
+        LockKey { host }.execute(&self.paths, self.key_store()?.as_ref())
+    }
+
+    /// Write a host's stored private key to its `IdentityFile` so ssh can use it again. An
+    /// existing file is left alone.
+    pub fn unlock(&self, host: &str) -> Result<KeyLock, AppError> {
+        UnlockKey { host }.execute(&self.paths, self.key_store()?.as_ref())
+    }
+
+    /// Load a host's stored private key into the running ssh-agent instead of writing it to its
+    /// `IdentityFile`, for `lifetime` (`ssh-add -t` syntax) or until the agent exits.
+    pub fn unlock_to_agent(&self, host: &str, lifetime: Option<&str>) -> Result<(), AppError> {
+        AgentKey { host, lifetime }.execute(&self.paths, self.key_store()?.as_ref())
+    }
+
+    /// Logged changes, oldest first, for every host or only `host`.
+    pub fn history(&self, host: Option<&str>) -> Result<Vec<HistoryEntry>, AppError> {
+        history::load(&self.paths, host)
+    }
+
+    /// Whether ssv wrote `host`'s config and it is unchanged since.
+    pub fn ownership(&self, host: &str) -> Result<Ownership, AppError> {
+        self.paths.validate_host(host)?;
+        ownership::status(&self.paths, host)
+    }
+
+    /// Compare every config in `conf.d` with ssv's state file, accepting the drifted configs
+    /// `adopt` selects as managed in their current form.
+    pub fn doctor(&self, adopt: &Adopt) -> Result<DoctorReport, AppError> {
+        Doctor { adopt }.execute(&self.paths)
+    }
+
+    /// Log that `host`'s config was changed outside ssv, such as in an editor opened by `ssv ui`,
+    /// and accept the new contents as managed.
+    pub fn record_edit(&self, host: &str) -> Result<(), AppError> {
+        let details = self.show(host)?;
+        let contents = std::fs::read_to_string(&details.config_path)?;
+        ownership::write_config(&self.paths, host, &contents)?;
+        let mut entry = HistoryEntry::new(Operation::Edit, host, vec![details.config_path]);
+        entry.fingerprint = details.fingerprint;
+        history::record(&self.paths, &entry)
+    }
+
+    /// User defaults from `config.toml`; a missing file yields empty settings.
+    pub fn settings(&self) -> Result<Settings, AppError> {
+        settings::load(&self.paths)
+    }
+
+    /// Validate and persist a single `config.toml` value.
+    pub fn config_set(&self, key: &str, value: &str) -> Result<(), AppError> {
+        let mut current = settings::load(&self.paths)?;
+        current.set(key, value)?;
+        settings::save(&self.paths, &current)
+    }
+
+    /// Remove a `config.toml` value, returning whether it was set.
+    pub fn config_unset(&self, key: &str) -> Result<bool, AppError> {
+        let mut current = settings::load(&self.paths)?;
+        let removed = current.unset(key)?;
+        if removed {
+            settings::save(&self.paths, &current)?;
+        }
+        Ok(removed)
+    }
+
+    /// Look up a host template by name; user templates shadow built-ins.
+    pub fn template(&self, name: &str) -> Result<Template, AppError> {
+        templates::load(&self.paths, name)
+    }
+
+    /// All available host templates, built-in and user-defined.
+    pub fn templates(&self) -> Result<Vec<Template>, AppError> {
+        templates::list(&self.paths)
+    }
+
+    /// Compare a host manifest with the managed conf.d state without changing anything.
+    pub fn plan(&self, manifest: &Path, prune: bool) -> Result<Plan, AppError> {
+        ApplyManifest { manifest_path: manifest, prune }.plan(&self.paths)
+    }
+
+    /// Converge the managed conf.d state on a host manifest, returning the changes performed.
+    pub fn apply(&self, manifest: &Path, prune: bool) -> Result<Plan, AppError> {
+        self.paths.ensure_base_dirs()?;
+        let keygen = self.key_generator()?;
+        let store = self.key_store()?;
+        ApplyManifest { manifest_path: manifest, prune }.apply(
+            &self.paths,
+            keygen.as_ref(),
+            store.as_ref(),
+        )
+    }
+
+    /// Make a repository use a managed key through its local `core.sshCommand`, optionally
+    /// rewriting `remote` onto the managed alias.
+    pub fn git_use(
+        &self,
+        host: &str,
+        repo: &Path,
+        remote: Option<&str>,
+    ) -> Result<GitBinding, AppError> {
+        GitUse { host, repo, remote }.execute(&self.paths)
+    }
+
+    /// Report which managed key each repository uses.
+    pub fn git_status(&self, repos: &[PathBuf]) -> Result<Vec<GitBinding>, AppError> {
+        GitStatus { repos }.execute(&self.paths)
+    }
+
+    /// Configure SSH commit signing with a managed key, in `repo` or globally when `repo` is
+    /// `None`, and record the key in `allowed_signers` for `email` (default: git's `user.email`).
+    pub fn git_signing(
+        &self,
+        host: &str,
+        repo: Option<&Path>,
+        email: Option<&str>,
+    ) -> Result<SigningSetup, AppError> {
+        GitSigning { host, repo, email }.execute(&self.paths)
+    }
+
+    /// Upload a managed public key to a Git hosting provider and record the registration.
+    pub fn register(
+        &self,
+        host: &str,
+        provider: Provider,
+        api_url: Option<&str>,
+        token: &str,
+        title: Option<&str>,
+    ) -> Result<Registration, AppError> {
+        RegisterKey { host, provider, api_url, token, title }.execute(&self.paths)
+    }
+
+    /// Install a host's public key in the server's `authorized_keys`, replacing any earlier line
+    /// for the same key, and record the deployment in metadata.
+    pub fn deploy(
+        &self,
+        host: &str,
+        auth: DeployAuth,
+        key_options: Option<&str>,
+    ) -> Result<Deployment, AppError> {
+        DeployKey { host, auth, key_options }.execute(&self.paths)
+    }
+
+    /// Delete a host's public key from the server's `authorized_keys`.
+    pub fn undeploy(&self, host: &str, auth: DeployAuth) -> Result<(), AppError> {
+        UndeployKey { host, auth }.execute(&self.paths)
+    }
+
+    /// Labelled entries ssv manages in `~/.ssh/authorized_keys`.
+    pub fn authorized_keys(&self) -> Result<Vec<AuthorizedKey>, AppError> {
+        authorized_keys::list(&self.paths)
+    }
+
+    /// Allow `key` to log in to this account, with sshd `options` such as `from="10.0.0.0/8"`
+    /// or `restrict`. Unmanaged lines in `authorized_keys` are kept as they are.
+    pub fn authorize(
+        &self,
+        label: &str,
+        key: &str,
+        options: &[String],
+    ) -> Result<AuthorizedKey, AppError> {
+        authorized_keys::add(&self.paths, label, key, options)
+    }
+
+    /// Remove the `authorized_keys` entry added under `label`.
+    pub fn unauthorize(&self, label: &str) -> Result<(), AppError> {
+        if authorized_keys::remove(&self.paths, label)? {
+            Ok(())
+        } else {
+            Err(AppError::validation_error(format!("no authorized key labelled '{label}'")))
+        }
+    }
+
+    /// Write the selected hosts (all managed hosts when `hosts` is empty) with their keys,
+    /// metadata and known_hosts entries to a passphrase-encrypted bundle. Returns the exported
+    /// host names.
+    pub fn export(
+        &self,
+        out: &Path,
+        hosts: &[String],
+        passphrase: &str,
+    ) -> Result<Vec<String>, AppError> {
+        ExportHosts { hosts, out, passphrase }.execute(&self.paths, self.key_store()?.as_ref())
+    }
+
+    /// Restore hosts from a bundle written by [`export`](Self::export), applying `(old, new)`
+    /// alias renames first. Returns the imported host names.
+    pub fn import(
+        &self,
+        bundle: &Path,
+        passphrase: &str,
+        renames: &[(String, String)],
+    ) -> Result<Vec<String>, AppError> {
+        ImportHosts { bundle, passphrase, renames }.execute(&self.paths, self.key_store()?.as_ref())
+    }
+
+    fn key_generator(&self) -> Result<Arc<dyn KeyGenerator>, AppError> {
+        if let Some(keygen) = &self.keygen {
+            return Ok(Arc::clone(keygen));
+        }
+        let backend = match settings::load(&self.paths)?.backend {
+            Some(backend) => backend.parse()?,
+            None => KeyBackend::default(),
+        };
+        Ok(backend.generator(&self.paths))
+    }
+
+    fn key_store(&self) -> Result<Arc<dyn KeyStore>, AppError> {
+        if let Some(store) = &self.store {
+            return Ok(Arc::clone(store));
+        }
+        match settings::load(&self.paths)?.storage.as_deref() {
+            Some("vault") => {
+                let passphrase = self.vault_passphrase.clone().ok_or_else(|| {
+                    AppError::config_error(
+                        "private keys are kept in the vault; set SSV_VAULT_PASSPHRASE to unlock it",
+                    )
+                })?;
+                Ok(Arc::new(VaultStore::new(self.paths.vault_path(), passphrase)))
+            }
+            _ => Ok(Arc::new(FileStore::new(self.paths.ssh_root()))),
+        }
+    }
+}
+
+fn generate_command(options: &GenerateOptions) -> GenerateHost<'_> {
+    GenerateHost {
+        host: &options.host,
+        patterns: &options.patterns,
+        hostname: options.hostname.as_deref(),
+        key_type: &options.key_type,
+        bits: options.bits,
+        passphrase: options.passphrase.as_deref(),
+        user: options.user.as_deref(),
+        port: options.port,
+        via: options.via.as_deref(),
+        options: &options.options,
+        comment: options.comment.as_deref(),
+        purpose: options.purpose.as_deref(),
+        ticket: options.ticket.as_deref(),
+        expires: options.expires.as_deref(),
+    }
+}
@@ -1,6 +1,7 @@
 use std::error::Error;
 use std::fmt::{self, Display};
 use std::io;
+use std::path::Path;
 use std::process::ExitStatus;
 
 /// Library-wide error type capturing filesystem, validation, and command execution failures.
@@ -18,6 +19,12 @@ pub enum AppError {
         program: String,
         status: ExitStatus,
     },
+    /// An HTTP request to a provider API failed or was rejected.
+    RequestFailed {
+        url: String,
+        status: Option<u16>,
+        message: String,
+    },
 }
 
 impl Display for AppError {
@@ -30,6 +37,12 @@ impl Display for AppError {
             AppError::CommandFailed { program, status } => {
                 write!(f, "Command '{program}' exited with status {status}")
             }
+            AppError::RequestFailed { url, status: Some(status), message } => {
+                write!(f, "Request to {url} failed with status {status}: {message}")
+            }
+            AppError::RequestFailed { url, status: None, message } => {
+                write!(f, "Request to {url} failed: {message}")
+            }
         }
     }
 }
@@ -41,7 +54,8 @@ impl Error for AppError {
             AppError::ConfigError(_)
             | AppError::HostNotFound(_)
             | AppError::ValidationError(_)
-            | AppError::CommandFailed { .. } => None,
+            | AppError::CommandFailed { .. }
+            | AppError::RequestFailed { .. } => None,
         }
     }
 }
@@ -61,8 +75,16 @@ impl AppError {
         AppError::ValidationError(message.into())
     }
 
-    pub(crate) fn command_failed(program: &str, status: ExitStatus) -> Self {
-        AppError::CommandFailed { program: program.to_string(), status }
+    pub(crate) fn command_failed(program: &Path, status: ExitStatus) -> Self {
+        AppError::CommandFailed { program: program.display().to_string(), status }
+    }
+
+    pub(crate) fn request_failed<S: Into<String>>(
+        url: &str,
+        status: Option<u16>,
+        message: S,
+    ) -> Self {
+        AppError::RequestFailed { url: url.to_string(), status, message: message.into() }
     }
 
     /// Provide an `io::ErrorKind`-like view for callers expecting legacy behavior.
@@ -71,7 +93,7 @@ impl AppError {
             AppError::Io(err) => err.kind(),
             AppError::ConfigError(_) | AppError::ValidationError(_) => io::ErrorKind::InvalidInput,
             AppError::HostNotFound(_) => io::ErrorKind::NotFound,
-            AppError::CommandFailed { .. } => io::ErrorKind::Other,
+            AppError::CommandFailed { .. } | AppError::RequestFailed { .. } => io::ErrorKind::Other,
         }
     }
 }
@@ -0,0 +1,167 @@
+//! Append-only log of the changes ssv makes, one JSON object per line in `~/.ssh/.ssv/log.jsonl`.
+
+use crate::error::AppError;
+use crate::key_info::PublicKeyInfo;
+use crate::ssh_paths::SshPaths;
+use crate::{timestamp, whoami};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+/// What was done to a host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Operation {
+    Generate,
+    /// The host config was changed by hand, e.g. from `ssv ui`.
+    Edit,
+    /// The host config was updated to match a manifest.
+    Apply,
+    /// A drifted config was accepted by `ssv doctor --adopt`.
+    Adopt,
+    Rotate,
+    Remove,
+    /// A removed host was brought back from the trash.
+    Restore,
+    /// The host was restored from an export bundle.
+    Import,
+    Lock,
+    Unlock,
+    /// The public key was installed in the server's `authorized_keys`.
+    Deploy,
+    Undeploy,
+    /// The public key was uploaded to a Git hosting provider.
+    Register,
+    /// A key was allowed to log in to this account.
+    Authorize,
+    Unauthorize,
+    /// A repository was pinned to the host's key.
+    GitUse,
+    /// Git was set up to sign commits with the host's key.
+    GitSigning,
+}
+
+impl Operation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operation::Generate => "generate",
+            Operation::Edit => "edit",
+            Operation::Apply => "apply",
+            Operation::Adopt => "adopt",
+            Operation::Rotate => "rotate",
+            Operation::Remove => "remove",
+            Operation::Restore => "restore",
+            Operation::Import => "import",
+            Operation::Lock => "lock",
+            Operation::Unlock => "unlock",
+            Operation::Deploy => "deploy",
+            Operation::Undeploy => "undeploy",
+            Operation::Register => "register",
+            Operation::Authorize => "authorize",
+            Operation::Unauthorize => "unauthorize",
+            Operation::GitUse => "git-use",
+            Operation::GitSigning => "git-signing",
+        }
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One line of the log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// RFC 3339 timestamp of the change.
+    pub timestamp: String,
+    pub operation: Operation,
+    /// The host changed; for `authorize` and `unauthorize`, the label of the `authorized_keys`
+    /// entry.
+    pub host: String,
+    /// `SHA256:` fingerprint of the host's public key; for `remove`, of the key deleted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// Files written or deleted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<PathBuf>,
+    /// Local account that ran ssv.
+    pub user: String,
+}
+
+impl HistoryEntry {
+    pub(crate) fn new(operation: Operation, host: &str, files: Vec<PathBuf>) -> Self {
+        Self {
+            timestamp: timestamp::now_rfc3339(),
+            operation,
+            host: host.to_string(),
+            fingerprint: None,
+            files,
+            user: whoami::local_user(),
+        }
+    }
+
+    /// Fingerprint the public key at `public_key`, if it exists and parses.
+    pub(crate) fn with_key(self, public_key: &Path) -> Self {
+        match fs::read_to_string(public_key) {
+            Ok(contents) => self.with_key_line(contents.lines().next().unwrap_or_default()),
+            Err(_) => self,
+        }
+    }
+
+    /// Fingerprint a public key line (`ALGORITHM BASE64 [COMMENT]`), if it parses.
+    pub(crate) fn with_key_line(mut self, line: &str) -> Self {
+        self.fingerprint = PublicKeyInfo::parse(line).and_then(|info| info.fingerprint());
+        self
+    }
+}
+
+/// Append `entry` to the log, creating it (mode 0600) on first use.
+pub(crate) fn record(paths: &SshPaths, entry: &HistoryEntry) -> Result<(), AppError> {
+    paths.ensure_dir_with_mode(&paths.state_dir())?;
+    let mut line = serde_json::to_string(entry)
+        .map_err(|err| AppError::config_error(format!("failed to encode log entry: {err}")))?;
+    line.push('\n');
+
+    let mut options = OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    // One write per line, so entries from concurrent runs do not interleave.
+    options.open(paths.history_path())?.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Every logged entry, oldest first, optionally only those for `host`.
+pub(crate) fn load(paths: &SshPaths, host: Option<&str>) -> Result<Vec<HistoryEntry>, AppError> {
+    let path = paths.history_path();
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut entries = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: HistoryEntry = serde_json::from_str(line).map_err(|err| {
+            AppError::config_error(format!(
+                "invalid log entry {}:{}: {err}",
+                path.display(),
+                index + 1
+            ))
+        })?;
+        if host.is_none_or(|host| entry.host == host) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
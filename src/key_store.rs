@@ -0,0 +1,255 @@
+//! Where private keys live between uses.
+//!
+//! Host configs always name `~/.ssh/id_<type>_<host>` as the `IdentityFile`. With the default
+//! plain-file store that file is the key itself. Other [`KeyStore`]s hold the key elsewhere and
+//! the file only exists while the key is unlocked (materialized) for ssh to read.
+
+use crate::error::AppError;
+use crate::{sealed, secure_file};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Values accepted by the `storage` setting.
+pub const STORAGES: &[&str] = &["files", "vault"];
+
+const VAULT_MAGIC: &[u8; 8] = b"SSVVLT01";
+
+/// Keeps private keys under their file names, such as `id_ed25519_github.com`.
+pub trait KeyStore: Send + Sync {
+    /// Store `private_key` under `name`, replacing any earlier key of that name.
+    fn put(&self, name: &str, private_key: &[u8]) -> Result<(), AppError>;
+    /// The key stored under `name`, if any.
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, AppError>;
+    /// Forget the key stored under `name`, returning whether there was one.
+    fn delete(&self, name: &str) -> Result<bool, AppError>;
+    /// The plain file holding `name`, for stores that keep keys as files ssh can read directly.
+    fn location(&self, _name: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Private keys as plain files in one directory; over `~/.ssh` this is how ssh expects them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl KeyStore for FileStore {
+    fn put(&self, name: &str, private_key: &[u8]) -> Result<(), AppError> {
+        secure_file::write(&self.dir.join(file_name(name)?), private_key, 0o600)
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, AppError> {
+        match fs::read(self.dir.join(file_name(name)?)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn delete(&self, name: &str) -> Result<bool, AppError> {
+        match fs::remove_file(self.dir.join(file_name(name)?)) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn location(&self, name: &str) -> Option<PathBuf> {
+        Some(self.dir.join(name))
+    }
+}
+
+/// Every private key in one passphrase-encrypted file (Argon2id and XChaCha20-Poly1305, like
+/// export bundles). Each operation decrypts the whole vault and writes it back.
+#[derive(Clone)]
+pub struct VaultStore {
+    path: PathBuf,
+    passphrase: String,
+}
+
+impl VaultStore {
+    pub fn new(path: impl Into<PathBuf>, passphrase: impl Into<String>) -> Self {
+        Self { path: path.into(), passphrase: passphrase.into() }
+    }
+
+    fn load(&self) -> Result<VaultContents, AppError> {
+        let sealed = match fs::read(&self.path) {
+            Ok(sealed) => sealed,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(VaultContents::default()),
+            Err(err) => return Err(err.into()),
+        };
+        let plaintext = sealed::open(VAULT_MAGIC, &self.passphrase, &sealed)
+            .map_err(|err| AppError::validation_error(format!("{}: {err}", self.path.display())))?;
+        serde_json::from_slice(&plaintext).map_err(|err| {
+            AppError::config_error(format!("invalid vault {}: {err}", self.path.display()))
+        })
+    }
+
+    fn save(&self, contents: &VaultContents) -> Result<(), AppError> {
+        let plaintext = serde_json::to_vec(contents)
+            .map_err(|err| AppError::config_error(format!("failed to encode vault: {err}")))?;
+        let sealed = sealed::seal(VAULT_MAGIC, &self.passphrase, &plaintext)?;
+        secure_file::write(&self.path, &sealed, 0o600)
+    }
+}
+
+impl std::fmt::Debug for VaultStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultStore").field("path", &self.path).finish_non_exhaustive()
+    }
+}
+
+impl KeyStore for VaultStore {
+    fn put(&self, name: &str, private_key: &[u8]) -> Result<(), AppError> {
+        let mut contents = self.load()?;
+        contents.keys.insert(file_name(name)?.to_string(), STANDARD.encode(private_key));
+        self.save(&contents)
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, AppError> {
+        let contents = self.load()?;
+        let Some(encoded) = contents.keys.get(name) else {
+            return Ok(None);
+        };
+        STANDARD.decode(encoded).map(Some).map_err(|_| {
+            AppError::config_error(format!(
+                "vault entry '{name}' in {} is corrupt",
+                self.path.display()
+            ))
+        })
+    }
+
+    fn delete(&self, name: &str) -> Result<bool, AppError> {
+        let mut contents = self.load()?;
+        if contents.keys.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.save(&contents)?;
+        Ok(true)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct VaultContents {
+    keys: BTreeMap<String, String>,
+}
+
+/// Key names are plain file names; anything that could step outside a directory is rejected.
+fn file_name(name: &str) -> Result<&str, AppError> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(AppError::validation_error(format!("invalid key name '{name}'")));
+    }
+    Ok(name)
+}
+
+fn key_name(private_key: &Path) -> Result<&str, AppError> {
+    private_key.file_name().and_then(|name| name.to_str()).ok_or_else(|| {
+        AppError::validation_error(format!("invalid key path {}", private_key.display()))
+    })
+}
+
+/// Whether `store` keeps the key for `private_key` somewhere other than that file.
+pub(crate) fn is_external(store: &dyn KeyStore, private_key: &Path) -> bool {
+    key_name(private_key)
+        .map(|name| store.location(name).as_deref() != Some(private_key))
+        .unwrap_or(false)
+}
+
+/// The key an external store holds for `private_key`; `None` for the plain-file store.
+pub(crate) fn stored(
+    store: &dyn KeyStore,
+    private_key: &Path,
+) -> Result<Option<Vec<u8>>, AppError> {
+    if !is_external(store, private_key) {
+        return Ok(None);
+    }
+    store.get(key_name(private_key)?)
+}
+
+/// Put a freshly generated key file at `staged` where `private_key` is looked up: into an
+/// external store, removing the staged file and any stale unlocked copy, or renamed into place.
+pub(crate) fn install(
+    store: &dyn KeyStore,
+    staged: &Path,
+    private_key: &Path,
+) -> Result<(), AppError> {
+    if !is_external(store, private_key) {
+        return Ok(fs::rename(staged, private_key)?);
+    }
+    store.put(key_name(private_key)?, &fs::read(staged)?)?;
+    fs::remove_file(staged)?;
+    match fs::remove_file(private_key) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Move the key file at `private_key` into an external store, leaving no plain copy. Returns
+/// whether a file was moved.
+pub(crate) fn lock(store: &dyn KeyStore, private_key: &Path) -> Result<bool, AppError> {
+    if !is_external(store, private_key) || !private_key.exists() {
+        return Ok(false);
+    }
+    store.put(key_name(private_key)?, &fs::read(private_key)?)?;
+    fs::remove_file(private_key)?;
+    Ok(true)
+}
+
+/// Write the stored key back to `private_key` for ssh to read. Returns whether the stored key
+/// was found.
+pub(crate) fn unlock(store: &dyn KeyStore, private_key: &Path) -> Result<bool, AppError> {
+    if !is_external(store, private_key) {
+        return Ok(false);
+    }
+    match store.get(key_name(private_key)?)? {
+        Some(contents) => {
+            secure_file::write(private_key, &contents, 0o600)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Delete the stored copy of `private_key` from an external store.
+pub(crate) fn forget(store: &dyn KeyStore, private_key: &Path) -> Result<bool, AppError> {
+    if !is_external(store, private_key) {
+        return Ok(false);
+    }
+    store.delete(key_name(private_key)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_key_names_that_leave_the_store() {
+        assert!(file_name("id_ed25519_github.com").is_ok());
+        for name in ["", "../id_rsa", "keys/id_rsa", ".vault", "a\\b"] {
+            assert!(file_name(name).is_err(), "{name} should be rejected");
+        }
+    }
+
+    #[test]
+    fn file_store_over_the_key_directory_is_not_external() {
+        let store = FileStore::new("/home/me/.ssh");
+        assert!(!is_external(&store, Path::new("/home/me/.ssh/id_ed25519_db")));
+        assert!(is_external(&store, Path::new("/home/me/.ssh/keys/id_ed25519_db")));
+        assert!(is_external(
+            &VaultStore::new("/v", "pw"),
+            Path::new("/home/me/.ssh/id_ed25519_db")
+        ));
+    }
+}
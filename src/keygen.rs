@@ -0,0 +1,267 @@
+//! Pluggable key pair generation behind `ssv generate` and `ssv rotate`.
+//!
+//! [`KeyBackend`] names the built-in generators selectable with `--backend` or the `backend`
+//! setting; library users can hand [`Ssv`](crate::Ssv) any other [`KeyGenerator`].
+
+use crate::error::AppError;
+use crate::ssh_paths::SshPaths;
+use crate::{secure_file, whoami};
+use ssh_key::private::RsaKeypair;
+use ssh_key::rand_core::OsRng;
+use ssh_key::{Algorithm, EcdsaCurve, LineEnding, PrivateKey};
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Names accepted by `--backend` and the `backend` setting.
+pub const BACKENDS: &[&str] = &["openssh", "native", "test"];
+
+/// RSA size used when none is requested, matching `ssh-keygen`.
+const DEFAULT_RSA_BITS: usize = 3072;
+
+/// One key pair to create.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyRequest<'a> {
+    /// Family name as accepted by `--type` (`ed25519`, `rsa`, `ecdsa`, ...).
+    pub key_type: &'a str,
+    /// Key size; the backend's default applies when `None`.
+    pub bits: Option<u32>,
+    /// Passphrase protecting the private key; unencrypted when `None`.
+    pub passphrase: Option<&'a str>,
+    /// Key comment; backends fall back to `user@machine` like `ssh-keygen`.
+    pub comment: Option<&'a str>,
+    /// Where the private key goes; the public key is written next to it with a `.pub` suffix.
+    pub private_key: &'a Path,
+}
+
+impl KeyRequest<'_> {
+    /// Path of the public key file, `private_key` plus `.pub`.
+    pub fn public_key(&self) -> PathBuf {
+        let mut name = self.private_key.as_os_str().to_owned();
+        name.push(".pub");
+        PathBuf::from(name)
+    }
+}
+
+/// Creates the key pairs for new and rotated hosts.
+///
+/// Implementations write the private key (mode 0600) and the one-line public key described by a
+/// [`KeyRequest`]; neither file exists beforehand. Everything else (configs, metadata, swapping
+/// rotated keys into place) stays with ssv.
+pub trait KeyGenerator: Send + Sync {
+    fn generate(&self, request: &KeyRequest<'_>) -> Result<(), AppError>;
+}
+
+/// The generators that ship with ssv.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyBackend {
+    /// Run `ssh-keygen` (or `SSV_SSH_KEYGEN_PATH`); see [`OpenSshKeygen`].
+    #[default]
+    OpenSsh,
+    /// Generate in-process without external programs; see [`NativeKeygen`].
+    Native,
+    /// Fixed placeholder keys for tests; see [`TestKeygen`].
+    Test,
+}
+
+impl KeyBackend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeyBackend::OpenSsh => "openssh",
+            KeyBackend::Native => "native",
+            KeyBackend::Test => "test",
+        }
+    }
+
+    /// The generator for this backend; `OpenSsh` runs the keygen program configured in `paths`.
+    pub fn generator(self, paths: &SshPaths) -> Arc<dyn KeyGenerator> {
+        match self {
+            KeyBackend::OpenSsh => Arc::new(OpenSshKeygen::new(paths.ssh_keygen())),
+            KeyBackend::Native => Arc::new(NativeKeygen),
+            KeyBackend::Test => Arc::new(TestKeygen),
+        }
+    }
+}
+
+impl Display for KeyBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for KeyBackend {
+    type Err = AppError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw {
+            "openssh" => Ok(KeyBackend::OpenSsh),
+            "native" => Ok(KeyBackend::Native),
+            "test" => Ok(KeyBackend::Test),
+            other => Err(AppError::validation_error(format!(
+                "unknown backend '{other}'; expected one of {}",
+                BACKENDS.join(", ")
+            ))),
+        }
+    }
+}
+
+/// Runs `ssh-keygen -t TYPE -f PATH -q -N '' [-b BITS] [-C COMMENT]`.
+///
+/// A passphrase is never handed to `ssh-keygen`, where it would show up in the process list;
+/// the key is generated unencrypted and then encrypted in-process, like [`NativeKeygen`] does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenSshKeygen {
+    program: PathBuf,
+}
+
+impl OpenSshKeygen {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self { program: program.into() }
+    }
+}
+
+impl KeyGenerator for OpenSshKeygen {
+    fn generate(&self, request: &KeyRequest<'_>) -> Result<(), AppError> {
+        let mut command = Command::new(&self.program);
+        command.arg("-t").arg(request.key_type).arg("-f").arg(request.private_key).arg("-q");
+        command.arg("-N").arg("");
+        if let Some(bits) = request.bits {
+            command.arg("-b").arg(bits.to_string());
+        }
+        if let Some(comment) = request.comment {
+            command.arg("-C").arg(comment);
+        }
+        let status = command.status()?;
+        if !status.success() {
+            return Err(AppError::command_failed(&self.program, status));
+        }
+
+        match request.passphrase.filter(|passphrase| !passphrase.is_empty()) {
+            Some(passphrase) => encrypt_in_place(request.private_key, passphrase),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Rewrite the unencrypted private key at `path` encrypted with `passphrase`.
+fn encrypt_in_place(path: &Path, passphrase: &str) -> Result<(), AppError> {
+    let native_error =
+        |err: ssh_key::Error| AppError::validation_error(format!("failed to encrypt key: {err}"));
+    let result = PrivateKey::read_openssh_file(path)
+        .and_then(|key| key.encrypt(&mut OsRng, passphrase))
+        .and_then(|key| key.to_openssh(LineEnding::LF).map(|pem| pem.to_string()))
+        .map_err(native_error)
+        .and_then(|pem| secure_file::write(path, pem.as_bytes(), 0o600));
+    if result.is_err() {
+        // Never leave a key behind that is less protected than requested.
+        let _ = std::fs::remove_file(path);
+        let mut public = path.as_os_str().to_owned();
+        public.push(".pub");
+        let _ = std::fs::remove_file(PathBuf::from(public));
+    }
+    result
+}
+
+/// Generates ed25519, rsa and ecdsa keys in-process and writes them in the OpenSSH format,
+/// encrypting with bcrypt-pbkdf and aes256-ctr when a passphrase is given, as `ssh-keygen` does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NativeKeygen;
+
+impl KeyGenerator for NativeKeygen {
+    fn generate(&self, request: &KeyRequest<'_>) -> Result<(), AppError> {
+        let mut rng = OsRng;
+        let native_error =
+            |err: ssh_key::Error| AppError::validation_error(format!("native keygen: {err}"));
+
+        let mut key = match (request.key_type, request.bits) {
+            ("ed25519", _) => PrivateKey::random(&mut rng, Algorithm::Ed25519),
+            ("rsa", bits) => {
+                let bits = bits.map_or(DEFAULT_RSA_BITS, |bits| bits as usize);
+                RsaKeypair::random(&mut rng, bits).map(PrivateKey::from)
+            }
+            ("ecdsa", bits) => {
+                let curve = match bits.unwrap_or(256) {
+                    256 => EcdsaCurve::NistP256,
+                    384 => EcdsaCurve::NistP384,
+                    521 => EcdsaCurve::NistP521,
+                    other => {
+                        return Err(AppError::validation_error(format!(
+                            "ecdsa keys are 256, 384 or 521 bits, not {other}"
+                        )));
+                    }
+                };
+                PrivateKey::random(&mut rng, Algorithm::Ecdsa { curve })
+            }
+            (other, _) => {
+                return Err(AppError::validation_error(format!(
+                    "the native backend cannot generate '{other}' keys; use ed25519, rsa or ecdsa, \
+                     or --backend openssh"
+                )));
+            }
+        }
+        .map_err(native_error)?;
+
+        key.set_comment(request.comment.map_or_else(default_comment, str::to_string));
+        // Encrypting drops the comment from the embedded public key, so render that first.
+        let public = key.public_key().to_openssh().map_err(native_error)?;
+        if let Some(passphrase) = request.passphrase.filter(|passphrase| !passphrase.is_empty()) {
+            key = key.encrypt(&mut rng, passphrase).map_err(native_error)?;
+        }
+
+        let private = key.to_openssh(LineEnding::LF).map_err(native_error)?;
+        secure_file::write(request.private_key, private.as_bytes(), 0o600)?;
+        secure_file::write(&request.public_key(), format!("{public}\n").as_bytes(), 0o644)
+    }
+}
+
+/// Writes the same placeholder for every request: `PRIVATE-<type>` as the private key and
+/// `ssh-<type> AAAATESTKEY <comment>` as the public key, with `<type>@ssv` as the default comment.
+///
+/// The output is deterministic and instant, which suits tests; it cannot authenticate anywhere.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TestKeygen;
+
+impl KeyGenerator for TestKeygen {
+    fn generate(&self, request: &KeyRequest<'_>) -> Result<(), AppError> {
+        let key_type = request.key_type;
+        let comment = request.comment.map_or_else(|| format!("{key_type}@ssv"), str::to_string);
+        secure_file::write(request.private_key, format!("PRIVATE-{key_type}\n").as_bytes(), 0o600)?;
+        secure_file::write(
+            &request.public_key(),
+            format!("ssh-{key_type} AAAATESTKEY {comment}\n").as_bytes(),
+            0o644,
+        )
+    }
+}
+
+fn default_comment() -> String {
+    format!("{}@{}", whoami::local_user(), whoami::machine_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_backend_names() {
+        for name in BACKENDS {
+            assert_eq!(name.parse::<KeyBackend>().unwrap().as_str(), *name);
+        }
+        let err = "gpg".parse::<KeyBackend>().unwrap_err().to_string();
+        assert!(err.contains("unknown backend 'gpg'"), "{err}");
+    }
+
+    #[test]
+    fn public_key_sits_next_to_the_private_key() {
+        let request = KeyRequest {
+            key_type: "ed25519",
+            bits: None,
+            passphrase: None,
+            comment: None,
+            private_key: Path::new("/home/me/.ssh/id_ed25519_db"),
+        };
+        assert_eq!(request.public_key(), Path::new("/home/me/.ssh/id_ed25519_db.pub"));
+    }
+}
@@ -0,0 +1,95 @@
+//! Matching of `~/.ssh/known_hosts` entries, including hashed (`|1|salt|hash`) host fields.
+
+use crate::error::AppError;
+use crate::ssh_config::{self, Directive};
+use crate::ssh_paths::SshPaths;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::fs;
+use std::io::ErrorKind;
+
+/// Lines of `~/.ssh/known_hosts` recorded for any of the given host names.
+pub(crate) fn entries_for(paths: &SshPaths, names: &[&str]) -> Result<Vec<String>, AppError> {
+    let contents = match fs::read_to_string(paths.known_hosts_path()) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(AppError::from(err)),
+    };
+
+    Ok(contents
+        .lines()
+        .filter(|line| names.iter().any(|name| line_matches(line, name)))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Names under which ssh records host keys for a managed alias: the alias, its `HostName`, and the
+/// bracketed `[hostname]:port` form for non-default ports.
+pub(crate) fn lookup_names(alias: &str, config: &[Directive]) -> Vec<String> {
+    let hostname = ssh_config::value(config, "HostName").unwrap_or(alias);
+    let mut names = vec![alias.to_string(), hostname.to_string()];
+    if let Some(port) = ssh_config::value(config, "Port").filter(|port| *port != "22") {
+        names.push(format!("[{hostname}]:{port}"));
+    }
+    names.dedup();
+    names
+}
+
+/// Whether a known_hosts line applies to `name` (a bare host or `[host]:port`).
+pub(crate) fn line_matches(line: &str, name: &str) -> bool {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return false;
+    }
+
+    let mut fields = line.split_whitespace();
+    let mut hosts = fields.next().unwrap_or_default();
+    if hosts.starts_with('@') {
+        // Marker such as @cert-authority or @revoked precedes the host field.
+        hosts = fields.next().unwrap_or_default();
+    }
+
+    hosts.split(',').any(|pattern| match pattern.strip_prefix("|1|") {
+        Some(hashed) => hashed_matches(hashed, name),
+        None => pattern == name,
+    })
+}
+
+fn hashed_matches(hashed: &str, name: &str) -> bool {
+    let Some((salt, digest)) = hashed.split_once('|') else {
+        return false;
+    };
+    let (Ok(salt), Ok(digest)) = (STANDARD.decode(salt), STANDARD.decode(digest)) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&salt) else {
+        return false;
+    };
+    mac.update(name.as_bytes());
+    mac.verify_slice(&digest).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAlUFMG3D4/ol4Gm9aH5E4iBjHU7jyNJlXygA4pfxqbB";
+
+    #[test]
+    fn matches_plain_and_bracketed_hosts() {
+        assert!(line_matches(&format!("github.com,140.82.112.3 {KEY}"), "github.com"));
+        assert!(line_matches(&format!("[git.corp]:2222 {KEY}"), "[git.corp]:2222"));
+        assert!(!line_matches(&format!("gitlab.com {KEY}"), "github.com"));
+        assert!(!line_matches("# github.com", "github.com"));
+    }
+
+    #[test]
+    fn matches_hashed_hosts() {
+        let line = format!("|1|Rp9DbTcbW3TFvpXzYQbqNKqtCCo=|cBDSRyVfft4pBC/RW0b5mFa3RCM= {KEY}");
+        assert!(line_matches(&line, "github.com"));
+        assert!(!line_matches(&line, "gitlab.com"));
+    }
+}
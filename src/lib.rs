@@ -1,40 +1,374 @@
-//! Library entry point exposing the core command handlers for `ssv`.
+//! Library behind the `ssv` command line tool.
+//!
+//! [`Ssv`] works on an explicit home directory and never prints. The free functions are
+//! shortcuts that resolve it from the environment on every call, as the binary does.
 
+mod allowed_signers;
+mod authorized_keys;
+mod bundle;
 mod commands;
+mod context;
 pub mod error;
+mod history;
+mod key_info;
+mod key_store;
+mod keygen;
+mod known_hosts;
+pub mod metadata;
+mod ownership;
+mod providers;
+mod sealed;
+mod secure_file;
+mod settings;
+mod ssh_config;
 mod ssh_paths;
+mod templates;
+mod timestamp;
+mod trash;
+mod whoami;
 
-use commands::{generate_host::GenerateHost, list_hosts::ListHosts, remove_host::RemoveHost};
 use error::AppError;
-use ssh_paths::SshPaths;
+use metadata::HostMetadata;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-/// Generate a new SSH key pair and configuration for the provided host.
+pub use authorized_keys::AuthorizedKey;
+pub use commands::apply_manifest::{ChangeAction, Manifest, ManifestHost, Plan, PlannedChange};
+pub use commands::audit_hosts::{AuditPolicy, AuditReport, FileMode, Finding, HostAudit, Severity};
+pub use commands::deploy_key::DeployAuth;
+pub use commands::doctor::{Adopt, DoctorReport, Drift, DriftKind};
+pub use commands::generate_host::GeneratedHost;
+pub use commands::git_repo::{GitBinding, GitRemote, SigningSetup};
+pub use commands::list_hosts::HostEntry;
+pub use commands::lock_keys::KeyLock;
+pub use commands::remove_host::{RemovalPlan, RemovalReport};
+pub use commands::rotate_key::RotationReport;
+pub use commands::select_hosts::{FILTER_KEYS, HostFilter, HostSelector};
+pub use commands::show_host::{HostDetails, KeyFile};
+pub use commands::test_host::ConnectionReport;
+pub use context::{RemoveOptions, RotateOptions, Ssv};
+pub use history::{HistoryEntry, Operation};
+pub use key_info::CertificateInfo;
+pub use key_store::{FileStore, KeyStore, STORAGES, VaultStore};
+pub use keygen::{
+    BACKENDS, KeyBackend, KeyGenerator, KeyRequest, NativeKeygen, OpenSshKeygen, TestKeygen,
+};
+pub use metadata::{Deployment, Registration};
+pub use ownership::Ownership;
+pub use providers::{Provider, RemoteKey};
+pub use settings::Settings;
+pub use ssh_config::Directive;
+pub use ssh_paths::SshPaths;
+pub use templates::Template;
+pub use trash::{DEFAULT_RETENTION_DAYS, TrashEntry};
+
+/// Options accepted by [`Ssv::generate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerateOptions {
+    /// Host alias, or the entry name when `patterns` is set.
+    pub host: String,
+    /// `Host` patterns (`*`, `?`, leading `!`) for an entry shared by many hosts.
+    pub patterns: Vec<String>,
+    /// Real hostname when `host` is an alias; defaults to `host`.
+    pub hostname: Option<String>,
+    pub key_type: String,
+    /// Key size; the key generator's default applies when `None`.
+    pub bits: Option<u32>,
+    /// Passphrase protecting the private key; unencrypted when `None`.
+    pub passphrase: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// Managed host alias to reach this host through (`ProxyJump`).
+    pub via: Option<String>,
+    /// Extra `Keyword value` directives appended to the host block.
+    pub options: Vec<(String, String)>,
+    /// Key comment template; supports `{user}`, `{host}`, `{date}` and `{machine}`.
+    pub comment: Option<String>,
+    pub purpose: Option<String>,
+    pub ticket: Option<String>,
+    /// Expiry date in `YYYY-MM-DD` form.
+    pub expires: Option<String>,
+}
+
+impl GenerateOptions {
+    /// Options for an ed25519 key with no overrides.
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            patterns: Vec::new(),
+            hostname: None,
+            key_type: "ed25519".to_string(),
+            bits: None,
+            passphrase: None,
+            user: None,
+            port: None,
+            via: None,
+            options: Vec::new(),
+            comment: None,
+            purpose: None,
+            ticket: None,
+            expires: None,
+        }
+    }
+
+    /// Options for a wildcard entry named after a slug of its patterns.
+    pub fn for_patterns(patterns: Vec<String>) -> Self {
+        let mut options = Self::new(ssh_config::pattern_slug(&patterns));
+        options.patterns = patterns;
+        options
+    }
+}
+
+/// Generate a new SSH key pair and configuration for the provided host, returning the public key.
 pub fn generate(
     host: &str,
     key_type: &str,
     user: Option<&str>,
     port: Option<u16>,
 ) -> Result<String, AppError> {
-    let paths = SshPaths::from_env()?;
-    let command = GenerateHost { host, key_type, user, port };
-    command.execute(&paths)
+    let mut options = GenerateOptions::new(host);
+    options.key_type = key_type.to_string();
+    options.user = user.map(str::to_string);
+    options.port = port;
+    generate_with(&options)
+}
+
+/// See [`Ssv::generate`]; returns the public key.
+pub fn generate_with(options: &GenerateOptions) -> Result<String, AppError> {
+    Ssv::from_env()?.generate(options).map(|generated| generated.public_key)
+}
+
+/// See [`Ssv::preview_config`].
+pub fn preview_config(options: &GenerateOptions) -> Result<String, AppError> {
+    Ssv::from_env()?.preview_config(options)
+}
+
+/// Check a host alias or hostname against the characters ssv accepts.
+pub fn validate_host(host: &str) -> Result<(), AppError> {
+    SshPaths::from_env()?.validate_host(host)
 }
 
-/// List all managed hosts underneath ~/.ssh/conf.d.
+/// Check a key type name such as `ed25519` or `rsa`.
+pub fn validate_key_type(key_type: &str) -> Result<(), AppError> {
+    SshPaths::from_env()?.validate_key_type(key_type)
+}
+
+/// Check an extra `Keyword value` directive for a host block.
+pub fn validate_option(key: &str, value: &str) -> Result<(), AppError> {
+    ssh_config::validate_option(key, value)
+}
+
+/// Check a login name for the `User` directive.
+pub fn validate_user(user: &str) -> Result<(), AppError> {
+    ssh_config::validate_user(user)
+}
+
+/// Names of all managed hosts underneath ~/.ssh/conf.d; see [`Ssv::list`] for their settings.
 pub fn list() -> Result<Vec<String>, AppError> {
-    let paths = SshPaths::from_env()?;
-    paths.ensure_base_dirs()?;
+    Ssv::from_env()?.hosts()
+}
 
-    let command = ListHosts;
-    command.execute(&paths)
+/// See [`Ssv::metadata`].
+pub fn metadata(host: &str) -> Result<Option<HostMetadata>, AppError> {
+    Ssv::from_env()?.metadata(host)
 }
 
-/// Remove the key pair and configuration associated with a host.
-pub fn remove(host: &str) -> Result<(), AppError> {
-    let paths = SshPaths::from_env()?;
-    let command = RemoveHost { host };
-    command.execute(&paths)?;
+/// See [`Ssv::settings`].
+pub fn settings() -> Result<Settings, AppError> {
+    Ssv::from_env()?.settings()
+}
 
-    println!("🗑️  Removed SSH assets for '{host}'");
-    Ok(())
+/// See [`Ssv::config_set`].
+pub fn config_set(key: &str, value: &str) -> Result<(), AppError> {
+    Ssv::from_env()?.config_set(key, value)
+}
+
+/// See [`Ssv::config_unset`].
+pub fn config_unset(key: &str) -> Result<bool, AppError> {
+    Ssv::from_env()?.config_unset(key)
+}
+
+/// See [`Ssv::template`].
+pub fn template(name: &str) -> Result<Template, AppError> {
+    Ssv::from_env()?.template(name)
+}
+
+/// See [`Ssv::templates`].
+pub fn templates() -> Result<Vec<Template>, AppError> {
+    Ssv::from_env()?.templates()
+}
+
+/// See [`Ssv::show`].
+pub fn show(host: &str) -> Result<HostDetails, AppError> {
+    Ssv::from_env()?.show(host)
+}
+
+/// See [`Ssv::test_connection`].
+pub fn test_connection(host: &str, timeout: Duration) -> Result<ConnectionReport, AppError> {
+    Ssv::from_env()?.test_connection(host, timeout)
+}
+
+/// See [`Ssv::audit`].
+pub fn audit(policy: &AuditPolicy) -> Result<AuditReport, AppError> {
+    Ssv::from_env()?.audit(policy)
+}
+
+/// See [`Ssv::plan`].
+pub fn plan(manifest: &Path, prune: bool) -> Result<Plan, AppError> {
+    Ssv::from_env()?.plan(manifest, prune)
+}
+
+/// See [`Ssv::apply`].
+pub fn apply(manifest: &Path, prune: bool) -> Result<Plan, AppError> {
+    Ssv::from_env()?.apply(manifest, prune)
+}
+
+/// See [`Ssv::git_use`].
+pub fn git_use(host: &str, repo: &Path, remote: Option<&str>) -> Result<GitBinding, AppError> {
+    Ssv::from_env()?.git_use(host, repo, remote)
+}
+
+/// See [`Ssv::git_status`].
+pub fn git_status(repos: &[PathBuf]) -> Result<Vec<GitBinding>, AppError> {
+    Ssv::from_env()?.git_status(repos)
+}
+
+/// See [`Ssv::git_signing`].
+pub fn git_signing(
+    host: &str,
+    repo: Option<&Path>,
+    email: Option<&str>,
+) -> Result<SigningSetup, AppError> {
+    Ssv::from_env()?.git_signing(host, repo, email)
+}
+
+/// See [`Ssv::register`].
+pub fn register(
+    host: &str,
+    provider: Provider,
+    api_url: Option<&str>,
+    token: &str,
+    title: Option<&str>,
+) -> Result<Registration, AppError> {
+    Ssv::from_env()?.register(host, provider, api_url, token, title)
+}
+
+/// See [`Ssv::authorized_keys`].
+pub fn authorized_keys() -> Result<Vec<AuthorizedKey>, AppError> {
+    Ssv::from_env()?.authorized_keys()
+}
+
+/// See [`Ssv::authorize`].
+pub fn authorize(label: &str, key: &str, options: &[String]) -> Result<AuthorizedKey, AppError> {
+    Ssv::from_env()?.authorize(label, key, options)
+}
+
+/// See [`Ssv::unauthorize`].
+pub fn unauthorize(label: &str) -> Result<(), AppError> {
+    Ssv::from_env()?.unauthorize(label)
+}
+
+/// See [`Ssv::deploy`].
+pub fn deploy(
+    host: &str,
+    auth: DeployAuth,
+    key_options: Option<&str>,
+) -> Result<Deployment, AppError> {
+    Ssv::from_env()?.deploy(host, auth, key_options)
+}
+
+/// See [`Ssv::undeploy`].
+pub fn undeploy(host: &str, auth: DeployAuth) -> Result<(), AppError> {
+    Ssv::from_env()?.undeploy(host, auth)
+}
+
+/// See [`Ssv::export`].
+pub fn export(out: &Path, hosts: &[String], passphrase: &str) -> Result<Vec<String>, AppError> {
+    Ssv::from_env()?.export(out, hosts, passphrase)
+}
+
+/// See [`Ssv::import`].
+pub fn import(
+    bundle: &Path,
+    passphrase: &str,
+    renames: &[(String, String)],
+) -> Result<Vec<String>, AppError> {
+    Ssv::from_env()?.import(bundle, passphrase, renames)
+}
+
+/// See [`Ssv::select`].
+pub fn select(selector: &HostSelector) -> Result<Vec<String>, AppError> {
+    Ssv::from_env()?.select(selector)
+}
+
+/// See [`Ssv::topology`].
+pub fn topology() -> Result<Vec<(String, Option<String>)>, AppError> {
+    Ssv::from_env()?.topology()
+}
+
+/// See [`Ssv::removal_plan`].
+pub fn removal_plan(host: &str) -> Result<RemovalPlan, AppError> {
+    Ssv::from_env()?.removal_plan(host)
+}
+
+/// See [`Ssv::remove`].
+pub fn remove_with(host: &str, options: &RemoveOptions) -> Result<RemovalReport, AppError> {
+    Ssv::from_env()?.remove(host, options)
+}
+
+/// See [`Ssv::trash`].
+pub fn trash() -> Result<Vec<TrashEntry>, AppError> {
+    Ssv::from_env()?.trash()
+}
+
+/// See [`Ssv::restore`].
+pub fn restore(host: &str) -> Result<TrashEntry, AppError> {
+    Ssv::from_env()?.restore(host)
+}
+
+/// See [`Ssv::rotate`].
+pub fn rotate(host: &str) -> Result<RotationReport, AppError> {
+    Ssv::from_env()?.rotate(host)
+}
+
+/// See [`Ssv::lock`].
+pub fn lock(host: &str) -> Result<KeyLock, AppError> {
+    Ssv::from_env()?.lock(host)
+}
+
+/// See [`Ssv::unlock`].
+pub fn unlock(host: &str) -> Result<KeyLock, AppError> {
+    Ssv::from_env()?.unlock(host)
+}
+
+/// See [`Ssv::unlock_to_agent`].
+pub fn unlock_to_agent(host: &str, lifetime: Option<&str>) -> Result<(), AppError> {
+    Ssv::from_env()?.unlock_to_agent(host, lifetime)
+}
+
+/// See [`Ssv::history`].
+pub fn history(host: Option<&str>) -> Result<Vec<HistoryEntry>, AppError> {
+    Ssv::from_env()?.history(host)
+}
+
+/// See [`Ssv::ownership`].
+pub fn ownership(host: &str) -> Result<Ownership, AppError> {
+    Ssv::from_env()?.ownership(host)
+}
+
+/// See [`Ssv::doctor`].
+pub fn doctor(adopt: &Adopt) -> Result<DoctorReport, AppError> {
+    Ssv::from_env()?.doctor(adopt)
+}
+
+/// See [`Ssv::record_edit`].
+pub fn record_edit(host: &str) -> Result<(), AppError> {
+    Ssv::from_env()?.record_edit(host)
+}
+
+/// Remove the key pair and configuration associated with a host.
+///
+/// Fails while other managed hosts still use it as a jump host; see [`Ssv::remove`].
+pub fn remove(host: &str) -> Result<RemovalReport, AppError> {
+    Ssv::from_env()?.remove(host, &RemoveOptions::default())
 }
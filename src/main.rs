@@ -1,5 +1,19 @@
-use clap::{Parser, Subcommand};
+mod ui;
+mod wizard;
+
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::engine::{ArgValueCandidates, CompletionCandidate};
+use clap_complete::env::{CompleteEnv, Shells};
 use ssv::error::AppError;
+use ssv::{
+    Adopt, AuditPolicy, AuditReport, ChangeAction, DeployAuth, DoctorReport, Drift, DriftKind,
+    GenerateOptions, HistoryEntry, HostDetails, HostFilter, HostSelector, KeyBackend, KeyLock,
+    Ownership, Plan, Provider, RemoveOptions, RotateOptions, RotationReport, Severity, Ssv,
+};
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use wizard::Wizard;
 
 #[derive(Parser)]
 #[command(name = "ssv")]
@@ -9,58 +23,819 @@ struct Cli {
     command: Commands,
 }
 
+// Parsed once per process, so the size of the `Generate` variant does not matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Generate a key pair and host configuration file
     #[clap(visible_alias = "gen")]
     Generate {
-        /// Hostname to manage (e.g., github.com)
+        /// Hostname to manage (e.g., github.com); names the entry when --pattern is given.
+        /// Without --host or --pattern on a terminal, ssv asks for each setting.
         #[arg(long, value_name = "HOST")]
-        host: String,
-        /// Key type to generate (default: ed25519)
-        #[arg(long = "type", default_value = "ed25519", value_name = "TYPE")]
-        key_type: String,
-        /// Optional user override for SSH config
+        host: Option<String>,
+        /// Host pattern such as '*.internal.example.com' (repeatable); shares one key
+        #[arg(long = "pattern", value_name = "PATTERN")]
+        patterns: Vec<String>,
+        /// Fill in unset fields from a named host template (see `ssv template list`)
+        #[arg(long, value_name = "NAME", add = ArgValueCandidates::new(template_names))]
+        template: Option<String>,
+        /// Real hostname when --host is an alias (default: same as --host)
+        #[arg(long, value_name = "HOSTNAME")]
+        hostname: Option<String>,
+        /// Key type to generate (default: config `key_type`, then ed25519)
+        #[arg(long = "type", env = "SSV_KEY_TYPE", value_name = "TYPE")]
+        key_type: Option<String>,
+        /// Key size in bits (default: config `bits`)
+        #[arg(long, env = "SSV_KEY_BITS", value_name = "BITS")]
+        bits: Option<u32>,
+        /// Optional user override for SSH config (default: config `users` pattern match)
         #[arg(long, value_name = "USER")]
         user: Option<String>,
         /// Optional port override for SSH config
         #[arg(long, value_name = "PORT")]
         port: Option<u16>,
+        /// Managed host to reach this one through (writes ProxyJump)
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        via: Option<String>,
+        /// Extra ssh_config directive for the host block (repeatable)
+        #[arg(long = "option", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        options: Vec<(String, String)>,
+        /// Key comment; supports {user}, {host}, {date} and {machine} placeholders
+        #[arg(long, env = "SSV_COMMENT", value_name = "TEMPLATE")]
+        comment: Option<String>,
+        /// What the key is used for (stored in metadata)
+        #[arg(long, value_name = "TEXT")]
+        purpose: Option<String>,
+        /// Ticket or change reference (stored in metadata)
+        #[arg(long, value_name = "ID")]
+        ticket: Option<String>,
+        /// Date after which the key should be retired (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE")]
+        expires: Option<String>,
+        /// Key generator: openssh, native or test (default: SSV_BACKEND, then config `backend`,
+        /// then openssh)
+        #[arg(long, value_name = "BACKEND", value_parser = parse_backend)]
+        backend: Option<KeyBackend>,
     },
     /// List managed hosts
     #[clap(visible_alias = "ls")]
-    List,
-    /// Remove key pairs and configuration for a host
+    List {
+        /// Show recorded metadata alongside each host
+        #[arg(short, long, conflicts_with = "tree")]
+        long: bool,
+        /// Show hosts nested under the jump hosts they connect through
+        #[arg(long)]
+        tree: bool,
+        #[command(flatten)]
+        selection: Selection,
+    },
+    /// Show everything ssv knows about one or more hosts
+    Show {
+        /// Hostname to inspect
+        #[arg(
+            long,
+            value_name = "HOST",
+            add = ArgValueCandidates::new(managed_hosts),
+            required_unless_present_any = SELECTION_ARGS
+        )]
+        host: Option<String>,
+        /// Output format (default: SSV_FORMAT, then config `format`, then human)
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+        #[command(flatten)]
+        selection: Selection,
+    },
+    /// Check that ssh can authenticate to a managed host
+    Test {
+        /// Hostname to connect to
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        host: String,
+        /// Connection timeout in seconds
+        #[arg(long, default_value_t = 10, value_name = "SECONDS")]
+        timeout: u64,
+    },
+    /// Report key age, strength, encryption and permissions for managed hosts
+    Audit {
+        /// Maximum key age in days before rotation is required
+        #[arg(long, env = "SSV_MAX_KEY_AGE_DAYS", default_value_t = 180, value_name = "DAYS")]
+        max_age_days: u64,
+        /// Minimum accepted RSA modulus size
+        #[arg(long, default_value_t = 3072, value_name = "BITS")]
+        min_rsa_bits: u32,
+        /// Treat private keys without a passphrase as violations
+        #[arg(long)]
+        require_passphrase: bool,
+        /// Output format (default: SSV_FORMAT, then config `format`, then human)
+        #[arg(long, value_enum)]
+        format: Option<AuditFormat>,
+    },
+    /// Report host configs that ssv did not write or that changed since it wrote them
+    Doctor {
+        /// Accept drifted configs as managed in their current form: without HOSTS, the ones ssv
+        /// wrote that were edited or deleted; configs ssv did not write only when named
+        #[arg(long, value_name = "HOSTS", num_args = 0.., value_delimiter = ',')]
+        adopt: Option<Vec<String>>,
+        /// Output format (default: SSV_FORMAT, then config `format`, then human)
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+    /// Remove key pairs and configuration for one or more hosts
     #[clap(visible_alias = "rm")]
     Remove {
         /// Hostname to remove
+        #[arg(
+            long,
+            value_name = "HOST",
+            add = ArgValueCandidates::new(managed_hosts),
+            required_unless_present_any = SELECTION_ARGS
+        )]
+        host: Option<String>,
+        /// Remove a jump host that other hosts still connect through
+        #[arg(long)]
+        force: bool,
+        /// Delete a config ssv did not write
+        #[arg(long)]
+        force_foreign: bool,
+        /// Delete keys uploaded to PROVIDER with `ssv register`, using the API token in VAR,
+        /// e.g. github=GITHUB_TOKEN (repeatable)
+        #[arg(long, value_name = "PROVIDER=VAR", value_parser = parse_token_env)]
+        token_env: Vec<(Provider, String)>,
+        /// Delete without asking for confirmation on a terminal
+        #[arg(short, long)]
+        yes: bool,
+        /// Without a config, also delete key files matched by file name (id_*_HOST)
+        #[arg(long)]
+        allow_guess: bool,
+        #[command(flatten)]
+        selection: Selection,
+    },
+    /// Browse and manage hosts in an interactive terminal interface
+    Ui,
+    /// Replace the key pair of one or more hosts with a fresh one of the same type
+    Rotate {
+        /// Managed host whose key is replaced
+        #[arg(
+            long,
+            value_name = "HOST",
+            add = ArgValueCandidates::new(managed_hosts),
+            required_unless_present_any = SELECTION_ARGS
+        )]
+        host: Option<String>,
+        /// Key generator: openssh, native or test (default: SSV_BACKEND, then config `backend`,
+        /// then openssh)
+        #[arg(long, value_name = "BACKEND", value_parser = parse_backend)]
+        backend: Option<KeyBackend>,
+        /// Read the new key's passphrase from this environment variable instead of prompting
+        /// for passphrase-protected keys; an empty value leaves the new key unencrypted
+        #[arg(long, value_name = "VAR")]
+        passphrase_env: Option<String>,
+        #[command(flatten)]
+        selection: Selection,
+    },
+    /// Move private keys into the vault and delete the plain files (all hosts by default)
+    Lock {
+        /// Managed host whose private key is locked away
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        host: Option<String>,
+        #[command(flatten)]
+        selection: Selection,
+    },
+    /// Write private keys from the vault back to their IdentityFile, or load them into
+    /// ssh-agent (all hosts by default)
+    Unlock {
+        /// Managed host whose private key is written out
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        host: Option<String>,
+        /// Load the keys into the running ssh-agent instead of writing them to disk
+        #[arg(long)]
+        agent: bool,
+        /// How long the agent keeps the keys, e.g. 3600 or 1h (ssh-add -t)
+        #[arg(long, value_name = "TIME", requires = "agent")]
+        lifetime: Option<String>,
+        #[command(flatten)]
+        selection: Selection,
+    },
+    /// Append a managed public key to the server's authorized_keys (like ssh-copy-id)
+    Deploy {
+        /// Managed host whose public key is installed
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        host: String,
+        /// Log in with a password instead of a key
+        #[arg(long, conflicts_with = "using_key")]
+        using_password: bool,
+        /// Log in with the key of another managed host
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        using_key: Option<String>,
+        /// authorized_keys options written before the key, e.g. 'from="10.0.0.0/8",no-pty'
+        #[arg(long, value_name = "OPTIONS")]
+        key_options: Option<String>,
+    },
+    /// Remove a managed public key from the server's authorized_keys
+    Undeploy {
+        /// Managed host whose public key is removed
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        host: String,
+        /// Log in with a password instead of a key
+        #[arg(long, conflicts_with = "using_key")]
+        using_password: bool,
+        /// Log in with the key of another managed host
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        using_key: Option<String>,
+    },
+    /// Upload a managed public key to GitHub, GitLab or Gitea
+    Register {
+        /// Managed host whose public key is uploaded
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        host: String,
+        /// Hosting provider
+        #[arg(long, value_parser = parse_provider, value_name = "github|gitlab|gitea")]
+        provider: Provider,
+        /// Environment variable holding the API token
+        #[arg(long, value_name = "VAR")]
+        token_env: String,
+        /// API base URL for self-hosted instances (default: the provider's public API)
+        #[arg(long, value_name = "URL")]
+        api_url: Option<String>,
+        /// Key title shown by the provider (default: "<HOST> (<MACHINE>)")
+        #[arg(long, value_name = "TITLE")]
+        title: Option<String>,
+    },
+    /// Show how a host manifest differs from the managed hosts
+    Plan {
+        /// Manifest file declaring the desired hosts
+        #[arg(short = 'f', long = "file", value_name = "FILE", default_value = "ssv.toml")]
+        file: PathBuf,
+        /// Also plan removal of managed hosts missing from the manifest
+        #[arg(long)]
+        prune: bool,
+    },
+    /// Create and update managed hosts to match a manifest
+    Apply {
+        /// Manifest file declaring the desired hosts
+        #[arg(short = 'f', long = "file", value_name = "FILE", default_value = "ssv.toml")]
+        file: PathBuf,
+        /// Remove managed hosts missing from the manifest
+        #[arg(long)]
+        prune: bool,
+    },
+    /// Manage labelled entries in ~/.ssh/authorized_keys
+    Authorized {
+        #[command(subcommand)]
+        action: AuthorizedAction,
+    },
+    /// Select managed keys per git repository
+    Git {
+        #[command(subcommand)]
+        action: GitAction,
+    },
+    /// Write managed hosts, keys and metadata to an encrypted bundle
+    Export {
+        /// Bundle file to create
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+        /// Comma-separated hosts to export (default: all managed hosts)
+        #[arg(long, value_delimiter = ',', value_name = "HOSTS")]
+        hosts: Vec<String>,
+        /// Read the bundle passphrase from this environment variable instead of prompting
+        #[arg(long, value_name = "VAR")]
+        passphrase_env: Option<String>,
+        #[command(flatten)]
+        selection: Selection,
+    },
+    /// Restore hosts from an encrypted bundle
+    Import {
+        /// Bundle file written by `ssv export`
+        #[arg(value_name = "FILE")]
+        bundle: PathBuf,
+        /// Import a host under a new alias (repeatable)
+        #[arg(long, value_name = "OLD=NEW", value_parser = parse_key_value)]
+        rename: Vec<(String, String)>,
+        /// Read the bundle passphrase from this environment variable instead of prompting
+        #[arg(long, value_name = "VAR")]
+        passphrase_env: Option<String>,
+    },
+    /// Bring a removed host back from the trash, or list the trash without --host
+    Restore {
+        /// Removed host to restore; its most recent removal is brought back
         #[arg(long, value_name = "HOST")]
+        host: Option<String>,
+    },
+    /// Show the log of the changes ssv made to hosts, keys and settings
+    History {
+        /// Only entries for this host, including removed ones
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        host: Option<String>,
+        /// Output format (default: SSV_FORMAT, then config `format`, then human)
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+    /// Inspect host templates used by `generate --template`
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// Read and change defaults stored in ~/.config/ssv/config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate the script for
+        #[arg(value_enum)]
+        shell: CompletionShell,
+    },
+    /// Render roff man pages
+    Man {
+        /// Write one page per subcommand into this directory instead of printing `ssv.1`
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+/// Arguments of [`Selection`] that can stand in for `--host`.
+const SELECTION_ARGS: [&str; 3] = ["patterns", "filters", "older_than"];
+
+/// Host selection shared by the commands that act on several hosts at once.
+#[derive(Args)]
+struct Selection {
+    /// Managed hosts whose name matches this glob, e.g. '*.staging.example' (repeatable)
+    #[arg(long = "match", value_name = "GLOB")]
+    patterns: Vec<String>,
+    /// Only hosts whose attribute matches, e.g. user=git or type=rsa (repeatable)
+    #[arg(long = "filter", value_name = "KEY=VALUE", value_parser = parse_filter)]
+    filters: Vec<HostFilter>,
+    /// Only hosts whose key pair is older than this, e.g. 180d, 26w or 1y
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    older_than: Option<HostFilter>,
+}
+
+impl Selection {
+    fn is_empty(&self) -> bool {
+        self.patterns.is_empty() && self.filters.is_empty() && self.older_than.is_none()
+    }
+
+    fn into_selector(self, names: Vec<String>) -> HostSelector {
+        let mut filters = self.filters;
+        filters.extend(self.older_than);
+        HostSelector { names, patterns: self.patterns, filters }
+    }
+}
+
+#[derive(Subcommand)]
+enum AuthorizedAction {
+    /// Allow a public key to log in to this account
+    Add {
+        /// Label identifying the entry for later removal
+        #[arg(long, value_name = "LABEL")]
+        label: String,
+        /// Public key line ("ALGORITHM BASE64 [COMMENT]")
+        #[arg(value_name = "KEY", required_unless_present = "key_file")]
+        key: Option<String>,
+        /// Read the public key from a file instead
+        #[arg(long, value_name = "FILE", conflicts_with = "key")]
+        key_file: Option<PathBuf>,
+        /// Accept the key only from these source addresses (from="...")
+        #[arg(long, value_name = "PATTERNS")]
+        from: Option<String>,
+        /// Force this command for every login with the key (command="...")
+        #[arg(long, value_name = "COMMAND")]
+        command: Option<String>,
+        /// Stop accepting the key after this time, YYYYMMDD[HHMM[SS]] (expiry-time="...")
+        #[arg(long, value_name = "TIME")]
+        expiry_time: Option<String>,
+        /// Disable forwarding, pty allocation and ~/.ssh/rc for the key
+        #[arg(long)]
+        restrict: bool,
+        /// Additional raw authorized_keys option, e.g. 'permitopen="localhost:8080"'
+        #[arg(long = "option", value_name = "OPTION")]
+        options: Vec<String>,
+    },
+    /// Remove an entry added with `ssv authorized add`
+    Remove {
+        /// Label of the entry
+        #[arg(long, value_name = "LABEL")]
+        label: String,
+    },
+    /// List the entries managed by ssv
+    List,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print a single value
+    Get {
+        /// key_type, bits, comment, format, backend, users.<PATTERN> or options.<KEYWORD>
+        key: String,
+    },
+    /// Store a value
+    Set {
+        /// key_type, bits, comment, format, backend, users.<PATTERN> or options.<KEYWORD>
+        key: String,
+        value: String,
+    },
+    /// Remove a value so the built-in default applies again
+    Unset {
+        /// key_type, bits, comment, format, backend, users.<PATTERN> or options.<KEYWORD>
+        key: String,
+    },
+    /// Print every configured value
+    List,
+}
+
+#[derive(Subcommand)]
+enum GitAction {
+    /// Set core.sshCommand in a repository to a managed key
+    Use {
+        /// Managed host whose key the repository should use
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
         host: String,
+        /// Repository to configure
+        #[arg(long, value_name = "PATH", default_value = ".")]
+        repo: PathBuf,
+        /// Also point this remote (default: origin) at the managed alias
+        #[arg(long, value_name = "REMOTE", num_args = 0..=1, default_missing_value = "origin")]
+        rewrite_remote: Option<String>,
+    },
+    /// Sign commits with a managed key and trust it in ~/.ssh/allowed_signers
+    Signing {
+        /// Managed host whose key signs commits
+        #[arg(long, value_name = "HOST", add = ArgValueCandidates::new(managed_hosts))]
+        host: String,
+        /// Write the global git config instead of the repository's
+        #[arg(long, conflicts_with = "repo")]
+        global: bool,
+        /// Repository to configure
+        #[arg(long, value_name = "PATH", default_value = ".")]
+        repo: PathBuf,
+        /// Signer identity for allowed_signers (default: git user.email)
+        #[arg(long, value_name = "EMAIL")]
+        email: Option<String>,
+    },
+    /// Show the managed key used by each repository
+    Status {
+        /// Repositories to inspect
+        #[arg(value_name = "PATH", default_value = ".")]
+        repos: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateAction {
+    /// List built-in and user templates
+    List,
+    /// Print a template body
+    Show {
+        /// Template name
+        #[arg(add = ArgValueCandidates::new(template_names))]
+        name: String,
     },
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+    Elvish,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum AuditFormat {
+    #[default]
+    Human,
+    Json,
+    Sarif,
+}
+
+/// Exit status used when `ssv audit` finds policy violations.
+const EXIT_POLICY_VIOLATION: i32 = 2;
+
+/// Exit status used when `ssv doctor` finds drift it was not asked to adopt.
+const EXIT_DRIFT: i32 = 2;
+
 fn main() {
+    CompleteEnv::with_factory(Cli::command).complete();
     let cli = Cli::parse();
 
     let result: Result<(), AppError> = match cli.command {
-        Commands::Generate { host, key_type, user, port } => {
-            ssv::generate(&host, &key_type, user.as_deref(), port).map(|public_key| {
+        Commands::Generate {
+            mut host,
+            patterns,
+            template,
+            mut hostname,
+            mut key_type,
+            bits,
+            mut user,
+            mut port,
+            via,
+            mut options,
+            comment,
+            purpose,
+            ticket,
+            expires,
+            backend,
+        } => ssv::settings().and_then(|settings| {
+            let interactive = host.is_none() && patterns.is_empty();
+            let mut passphrase = None;
+            if interactive {
+                if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+                    missing_host_error();
+                }
+                let default_type = key_type
+                    .clone()
+                    .or(settings.key_type.clone())
+                    .unwrap_or_else(|| "ed25519".to_string());
+                let answers = Wizard::new(io::stdin().lock(), io::stdout(), |prompt: &str| {
+                    rpassword::prompt_password(prompt)
+                })
+                .run(&ssv::list()?, &default_type)?;
+                host = Some(answers.host);
+                hostname = answers.hostname;
+                user = answers.user;
+                port = answers.port;
+                key_type = Some(answers.key_type);
+                passphrase = answers.passphrase;
+                options.extend(answers.options);
+            }
+
+            let base = match host {
+                Some(host) => GenerateOptions { patterns, ..GenerateOptions::new(host) },
+                None => GenerateOptions::for_patterns(patterns),
+            };
+            let host = base.host.clone();
+            let mut options = GenerateOptions {
+                hostname,
+                key_type: key_type
+                    .or(settings.key_type.clone())
+                    .unwrap_or_else(|| "ed25519".to_string()),
+                bits: bits.or(settings.bits),
+                user,
+                port,
+                via,
+                options,
+                comment: comment.or(settings.comment.clone()),
+                purpose,
+                ticket,
+                expires,
+                ..base
+            };
+            if let Some(name) = template {
+                ssv::template(&name)?.apply(&mut options)?;
+            }
+            let target = options
+                .hostname
+                .as_deref()
+                .or(options.patterns.first().map(String::as_str))
+                .unwrap_or(&host);
+            if options.user.is_none() {
+                options.user = settings.user_for(target).map(str::to_string);
+            }
+            options.options = settings.merged_options(&options.options);
+            options.passphrase = passphrase;
+            if interactive {
+                let config = ssv::preview_config(&options)?;
+                let mut wizard = Wizard::new(io::stdin().lock(), io::stdout(), |prompt: &str| {
+                    rpassword::prompt_password(prompt)
+                });
+                if !wizard.confirm(&config)? {
+                    println!("Nothing was written.");
+                    return Ok(());
+                }
+            }
+            context(backend)?.generate(&options).map(|generated| {
                 println!("✅ Generated SSH assets for '{host}'");
-                println!("{public_key}");
+                println!("{}", generated.public_key);
+                if !generated.private_key.exists() {
+                    println!("🔒 The private key is in the vault; run `ssv unlock --agent --host {host}` to use it");
+                }
             })
+        }),
+        Commands::List { long, tree, selection } => {
+            let filtered = !selection.is_empty();
+            ssv::select(&selection.into_selector(Vec::new())).and_then(|hosts| {
+                if hosts.is_empty() {
+                    println!(
+                        "{}",
+                        if filtered { "(no hosts match)" } else { "(no hosts managed yet)" }
+                    );
+                } else if tree {
+                    let mut links = ssv::topology()?;
+                    links.retain(|(host, _)| hosts.contains(host));
+                    print_tree(&links);
+                } else if long {
+                    print_long_listing(&hosts)?;
+                } else {
+                    for host in hosts {
+                        match ssv::ownership(&host)? {
+                            Ownership::Managed => println!("{host}"),
+                            other => println!("{host} ({other})"),
+                        }
+                    }
+                }
+                Ok(())
+            })
+        }
+        Commands::Show { host, format, selection } => {
+            configured_format(format).and_then(|format| {
+                let single = selection.is_empty();
+                let hosts = ssv::select(&selection.into_selector(host.into_iter().collect()))?;
+                let details =
+                    hosts.iter().map(|host| ssv::show(host)).collect::<Result<Vec<_>, _>>()?;
+                match format {
+                    OutputFormat::Human if details.is_empty() => {
+                        println!("No managed hosts match.")
+                    }
+                    OutputFormat::Human => {
+                        for (index, details) in details.iter().enumerate() {
+                            if index > 0 {
+                                println!();
+                            }
+                            print_details(details);
+                        }
+                    }
+                    OutputFormat::Json if single => println!("{:#}", serde_json::json!(details[0])),
+                    OutputFormat::Json => println!("{:#}", serde_json::json!(details)),
+                }
+                Ok(())
+            })
+        }
+        Commands::Test { host, timeout } => {
+            ssv::test_connection(&host, Duration::from_secs(timeout)).map(|report| {
+                if !report.output.is_empty() {
+                    println!("{}", report.output);
+                }
+                if report.passed {
+                    println!("✅ Connection test passed for '{host}': {}", report.reason);
+                } else {
+                    println!("❌ Connection test failed for '{host}': {}", report.reason);
+                    std::process::exit(1);
+                }
+            })
+        }
+        Commands::Audit { max_age_days, min_rsa_bits, require_passphrase, format } => {
+            let policy = AuditPolicy { max_age_days, min_rsa_bits, require_passphrase };
+            let report = configured_format(format)
+                .and_then(|format| context(None)?.audit(&policy).map(|report| (format, report)));
+            report.map(|(format, report)| {
+                match format {
+                    AuditFormat::Human => print_audit(&report),
+                    AuditFormat::Json => println!("{:#}", report.to_json()),
+                    AuditFormat::Sarif => println!("{:#}", report.to_sarif()),
+                }
+                if report.violations() > 0 {
+                    std::process::exit(EXIT_POLICY_VIOLATION);
+                }
+            })
+        }
+        Commands::Doctor { adopt, format } => configured_format(format).and_then(|format| {
+            let adopt = match adopt {
+                None => Adopt::Nothing,
+                Some(hosts) if hosts.is_empty() => Adopt::Changed,
+                Some(hosts) => Adopt::Hosts(hosts),
+            };
+            let report = ssv::doctor(&adopt)?;
+            match format {
+                OutputFormat::Human => print_doctor(&report),
+                OutputFormat::Json => println!("{:#}", serde_json::json!(report)),
+            }
+            if report.drift.len() > report.adopted.len() {
+                std::process::exit(EXIT_DRIFT);
+            }
+            Ok(())
+        }),
+        Commands::Remove { host, force, force_foreign, token_env, yes, allow_guess, selection } => {
+            let selector = selection.into_selector(host.into_iter().collect());
+            let options = RemoveOptions { force, force_foreign, allow_guess, ..Default::default() };
+            run_remove(&selector, options, &token_env, yes)
+        }
+        Commands::Ui => ui::run(),
+        Commands::Rotate { host, backend, passphrase_env, selection } => run_rotate(
+            &selection.into_selector(host.into_iter().collect()),
+            backend,
+            passphrase_env.as_deref(),
+        ),
+        Commands::Lock { host, selection } => {
+            run_lock(&selection.into_selector(host.into_iter().collect()), true)
+        }
+        Commands::Unlock { host, agent: true, lifetime, selection } => {
+            run_agent(&selection.into_selector(host.into_iter().collect()), lifetime.as_deref())
+        }
+        Commands::Unlock { host, selection, .. } => {
+            run_lock(&selection.into_selector(host.into_iter().collect()), false)
         }
-        Commands::List => ssv::list().map(|hosts| {
-            if hosts.is_empty() {
-                println!("(no hosts managed yet)");
+        Commands::Deploy { host, using_password, using_key, key_options } => {
+            let auth = deploy_auth(using_password, using_key.as_deref());
+            ssv::deploy(&host, auth, key_options.as_deref()).map(|_| {
+                println!("🚀 Deployed the public key of '{host}' to its authorized_keys");
+            })
+        }
+        Commands::Undeploy { host, using_password, using_key } => {
+            let auth = deploy_auth(using_password, using_key.as_deref());
+            ssv::undeploy(&host, auth).map(|()| {
+                println!("🧹 Removed the public key of '{host}' from its authorized_keys");
+            })
+        }
+        Commands::Register { host, provider, token_env, api_url, title } => read_token(&token_env)
+            .and_then(|token| {
+                ssv::register(&host, provider, api_url.as_deref(), &token, title.as_deref())
+            })
+            .map(|registration| {
+                println!(
+                    "🔗 Registered '{host}' with {} as key {} \"{}\"",
+                    registration.provider, registration.key_id, registration.title
+                );
+            }),
+        Commands::Plan { file, prune } => ssv::plan(&file, prune).map(|plan| {
+            print_plan(&plan);
+            println!(
+                "Plan: {} to create, {} to update, {} to prune",
+                plan.count(ChangeAction::Create),
+                plan.count(ChangeAction::Update),
+                plan.count(ChangeAction::Prune)
+            );
+        }),
+        Commands::Apply { file, prune } => context(None).and_then(|ssv| ssv.apply(&file, prune)).map(|plan| {
+            print_plan(&plan);
+            println!(
+                "Applied: {} created, {} updated, {} pruned",
+                plan.count(ChangeAction::Create),
+                plan.count(ChangeAction::Update),
+                plan.count(ChangeAction::Prune)
+            );
+        }),
+        Commands::Export { out, hosts, passphrase_env, selection } => {
+            let selected = if hosts.is_empty() && selection.is_empty() {
+                Ok(Some(Vec::new()))
             } else {
-                for host in hosts {
-                    println!("{host}");
+                ssv::select(&selection.into_selector(hosts))
+                    .map(|hosts| (!hosts.is_empty()).then_some(hosts))
+            };
+            selected.and_then(|selected| {
+                let Some(hosts) = selected else {
+                    println!("No managed hosts match; nothing exported.");
+                    return Ok(());
+                };
+                let passphrase = read_passphrase(passphrase_env.as_deref(), true)?;
+                let exported = context(None)?.export(&out, &hosts, &passphrase)?;
+                println!("📦 Exported {} host(s) to {}", exported.len(), out.display());
+                for host in exported {
+                    println!("    {host}");
                 }
+                Ok(())
+            })
+        }
+        Commands::Import { bundle, rename, passphrase_env } => {
+            read_passphrase(passphrase_env.as_deref(), false)
+                .and_then(|passphrase| context(None)?.import(&bundle, &passphrase, &rename))
+                .map(|imported| {
+                    println!("📥 Imported {} host(s) from {}", imported.len(), bundle.display());
+                    for host in imported {
+                        println!("    {host}");
+                    }
+                })
+        }
+        Commands::Authorized { action } => run_authorized(action),
+        Commands::Git { action } => run_git(action),
+        Commands::Template { action } => run_template(action),
+        Commands::Restore { host: None } => ssv::trash().map(|entries| {
+            if entries.is_empty() {
+                println!("The trash is empty.");
+            }
+            for entry in &entries {
+                println!("{}  {}", entry.removed_at, entry.host);
             }
         }),
-        Commands::Remove { host } => ssv::remove(&host).map(|()| {
-            println!("🗑️  Removed SSH assets for '{host}'");
+        Commands::Restore { host: Some(host) } => {
+            context(None).and_then(|ssv| ssv.restore(&host)).map(|entry| {
+                println!("♻️  Restored '{host}' as removed at {}", entry.removed_at);
+            })
+        }
+        Commands::History { host, format } => configured_format(format).and_then(|format| {
+            let entries = ssv::history(host.as_deref())?;
+            match format {
+                OutputFormat::Human if entries.is_empty() => println!("(no history recorded)"),
+                OutputFormat::Human => {
+                    for entry in &entries {
+                        print_history_entry(entry);
+                    }
+                }
+                OutputFormat::Json => println!("{:#}", serde_json::json!(entries)),
+            }
+            Ok(())
         }),
+        Commands::Config { action } => run_config(action),
+        Commands::Completions { shell } => write_completions(shell).map_err(AppError::from),
+        Commands::Man { out_dir } => write_man_pages(out_dir).map_err(AppError::from),
     };
 
     if let Err(e) = result {
@@ -68,3 +843,816 @@ fn main() {
         std::process::exit(1);
     }
 }
+
+/// Resolve `--format`, falling back to `SSV_FORMAT`, the config file and then `human`. A default
+/// from the environment or config file that this command cannot print, such as `sarif` outside
+/// `audit`, is skipped.
+fn configured_format<F: ValueEnum + Default>(explicit: Option<F>) -> Result<F, AppError> {
+    if let Some(format) = explicit {
+        return Ok(format);
+    }
+    let supported = |name: &str| F::from_str(name, true).ok();
+    if let Some(format) = std::env::var("SSV_FORMAT").ok().as_deref().and_then(supported) {
+        return Ok(format);
+    }
+    let settings = ssv::settings()?;
+    Ok(settings.format.as_deref().and_then(supported).unwrap_or_default())
+}
+
+fn run_remove(
+    selector: &HostSelector,
+    mut options: RemoveOptions,
+    token_env: &[(Provider, String)],
+    yes: bool,
+) -> Result<(), AppError> {
+    options.tokens = token_env
+        .iter()
+        .map(|(provider, var)| read_token(var).map(|token| (*provider, token)))
+        .collect::<Result<_, _>>()?;
+    let ssv = context(None)?;
+    let hosts = ssv.select(selector)?;
+    if hosts.is_empty() {
+        println!("No managed hosts match; nothing removed.");
+        return Ok(());
+    }
+    if !yes && io::stdin().is_terminal() && !confirm_removal(&ssv, &hosts, &options)? {
+        println!("Nothing removed.");
+        return Ok(());
+    }
+
+    if let [host] = hosts.as_slice() {
+        return remove_host(&ssv, host, &options);
+    }
+    let mut failed = 0;
+    for host in &hosts {
+        if let Err(err) = remove_host(&ssv, host, &options) {
+            eprintln!("❌ {host}: {err}");
+            failed += 1;
+        }
+    }
+    bulk_summary("Removed", hosts.len(), failed)
+}
+
+fn remove_host(ssv: &Ssv, host: &str, options: &RemoveOptions) -> Result<(), AppError> {
+    let report = ssv.remove(host, options)?;
+    println!("🗑️  Removed SSH assets for '{host}'");
+    if report.trashed {
+        println!("    `ssv restore --host {host}` brings them back from the trash");
+    }
+    if !report.dependents.is_empty() {
+        println!("⚠️  Still jumping through '{host}': {}", report.dependents.join(", "));
+    }
+    for registration in &report.deregistered {
+        println!(
+            "    deleted {} key {} \"{}\"",
+            registration.provider, registration.key_id, registration.title
+        );
+    }
+    if report.still_deployed {
+        println!(
+            "⚠️  The key was still deployed to '{host}'; its authorized_keys line is left behind"
+        );
+    }
+    for registration in &report.registrations_left {
+        println!(
+            "⚠️  Key {} \"{}\" is still registered with {} ({}); pass --token-env {}=VAR to \
+             delete it",
+            registration.key_id,
+            registration.title,
+            registration.provider,
+            registration.api_url,
+            registration.provider
+        );
+    }
+    Ok(())
+}
+
+/// List the files a removal deletes and ask whether to go ahead.
+fn confirm_removal(ssv: &Ssv, hosts: &[String], options: &RemoveOptions) -> Result<bool, AppError> {
+    let allow_guess = options.allow_guess;
+    let plans = hosts
+        .iter()
+        .map(|host| {
+            let foreign = !options.force_foreign
+                && ssv.paths().host_config_path(host).exists()
+                && ssv.ownership(host)? == Ownership::Foreign;
+            ssv.removal_plan(host).map(|plan| (host, plan, foreign))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if let [(_, plan, foreign)] = plans.as_slice()
+        && (*foreign || plan.guessed && !allow_guess)
+    {
+        // The removal itself refuses and explains why.
+        return Ok(true);
+    }
+
+    if ssv.settings()?.trash_retention == Some(0) {
+        println!("The following files will be deleted:");
+    } else {
+        println!("The following files will be moved to the trash:");
+    }
+    for (host, plan, foreign) in &plans {
+        if *foreign {
+            println!("⚠️  '{host}' was not written by ssv and is skipped without --force-foreign");
+            continue;
+        }
+        if plan.guessed && !allow_guess {
+            println!("⚠️  '{host}' has no config and is skipped without --allow-guess");
+            continue;
+        }
+        if plan.guessed {
+            println!("⚠️  '{host}' has no config; its key files were matched by name");
+        }
+        if plan.files.is_empty() {
+            println!("    (no local files for '{host}')");
+        }
+        for path in &plan.files {
+            println!("    {}", path.display());
+        }
+        for path in &plan.stored {
+            println!("    {} (in the vault)", path.display());
+        }
+    }
+
+    match hosts {
+        [host] => print!("Remove '{host}'? [y/N]: "),
+        _ => print!("Remove {} hosts? [y/N]: ", hosts.len()),
+    }
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// The library context for commands that touch private keys: keys come from `backend` when one
+/// was given, and the vault passphrase is asked for on a terminal when the environment lacks it.
+fn context(backend: Option<KeyBackend>) -> Result<Ssv, AppError> {
+    let mut ssv = Ssv::from_env()?;
+    if let Some(backend) = backend {
+        ssv = ssv.with_backend(backend);
+    }
+    if ssv.needs_vault_passphrase()? && io::stdin().is_terminal() {
+        ssv = ssv.with_vault_passphrase(rpassword::prompt_password("Vault passphrase: ")?);
+    }
+    Ok(ssv)
+}
+
+fn run_lock(selector: &HostSelector, lock: bool) -> Result<(), AppError> {
+    let ssv = context(None)?;
+    let hosts = ssv.select(selector)?;
+    let verb = if lock { "Locked" } else { "Unlocked" };
+    if hosts.is_empty() {
+        println!("No managed hosts match; nothing {}.", verb.to_lowercase());
+        return Ok(());
+    }
+    let mut failed = 0;
+    for host in &hosts {
+        let result = if lock { ssv.lock(host) } else { ssv.unlock(host) };
+        match result {
+            Ok(report) => print_lock(&report, lock),
+            Err(err) if hosts.len() == 1 => return Err(err),
+            Err(err) => {
+                eprintln!("❌ {host}: {err}");
+                failed += 1;
+            }
+        }
+    }
+    if hosts.len() == 1 { Ok(()) } else { bulk_summary(verb, hosts.len(), failed) }
+}
+
+fn run_agent(selector: &HostSelector, lifetime: Option<&str>) -> Result<(), AppError> {
+    let ssv = context(None)?;
+    let hosts = ssv.select(selector)?;
+    if hosts.is_empty() {
+        println!("No managed hosts match; nothing unlocked.");
+        return Ok(());
+    }
+    let mut failed = 0;
+    for host in &hosts {
+        match ssv.unlock_to_agent(host, lifetime) {
+            Ok(()) => match lifetime {
+                Some(lifetime) => {
+                    println!("🔓 Added the key of '{host}' to ssh-agent for {lifetime}")
+                }
+                None => println!("🔓 Added the key of '{host}' to ssh-agent"),
+            },
+            Err(err) if hosts.len() == 1 => return Err(err),
+            Err(err) => {
+                eprintln!("❌ {host}: {err}");
+                failed += 1;
+            }
+        }
+    }
+    if hosts.len() == 1 { Ok(()) } else { bulk_summary("Unlocked", hosts.len(), failed) }
+}
+
+fn print_lock(report: &KeyLock, locked: bool) {
+    let (host, key) = (&report.host, report.key.display());
+    match (locked, report.changed) {
+        (true, true) => println!("🔒 Locked the key of '{host}' in the vault ({key} deleted)"),
+        (true, false) => println!("🔒 The key of '{host}' is already locked"),
+        (false, true) => println!("🔓 Unlocked the key of '{host}' to {key}"),
+        (false, false) => println!("🔓 The key of '{host}' is already unlocked ({key})"),
+    }
+}
+
+fn run_rotate(
+    selector: &HostSelector,
+    backend: Option<KeyBackend>,
+    passphrase_env: Option<&str>,
+) -> Result<(), AppError> {
+    let ssv = context(backend)?;
+    let hosts = ssv.select(selector)?;
+    if hosts.is_empty() {
+        println!("No managed hosts match; nothing rotated.");
+        return Ok(());
+    }
+    let rotate = |host: &str| {
+        let options = rotation_options(&ssv, host, passphrase_env)?;
+        ssv.rotate_with(host, &options)
+    };
+    if let [host] = hosts.as_slice() {
+        return rotate(host).map(|report| print_rotation(&report));
+    }
+    let mut failed = 0;
+    for host in &hosts {
+        match rotate(host) {
+            Ok(report) => print_rotation(&report),
+            Err(err) => {
+                eprintln!("❌ {host}: {err}");
+                failed += 1;
+            }
+        }
+    }
+    bulk_summary("Rotated", hosts.len(), failed)
+}
+
+/// The new key's passphrase from `passphrase_env`, or asked for on a terminal when the old key
+/// has one; without either, rotating a protected key fails.
+fn rotation_options(
+    ssv: &Ssv,
+    host: &str,
+    passphrase_env: Option<&str>,
+) -> Result<RotateOptions, AppError> {
+    if let Some(name) = passphrase_env {
+        let passphrase = std::env::var(name).map_err(|_| {
+            AppError::ValidationError(format!("environment variable {name} is not set"))
+        })?;
+        return Ok(RotateOptions { passphrase: Some(passphrase) });
+    }
+    if !ssv.key_encrypted(host)? || !io::stdin().is_terminal() {
+        return Ok(RotateOptions::default());
+    }
+    let passphrase = rpassword::prompt_password(format!("New passphrase for '{host}': "))?;
+    if rpassword::prompt_password("Confirm passphrase: ")? != passphrase {
+        return Err(AppError::ValidationError("passphrases do not match".to_string()));
+    }
+    Ok(RotateOptions { passphrase: Some(passphrase) })
+}
+
+/// Print how a bulk command went and fail if any host failed.
+fn bulk_summary(verb: &str, total: usize, failed: usize) -> Result<(), AppError> {
+    println!("{verb} {} of {total} host(s)", total - failed);
+    if failed > 0 {
+        return Err(AppError::ValidationError(format!("{failed} of {total} host(s) failed")));
+    }
+    Ok(())
+}
+
+fn run_authorized(action: AuthorizedAction) -> Result<(), AppError> {
+    match action {
+        AuthorizedAction::Add {
+            label,
+            key,
+            key_file,
+            from,
+            command,
+            expiry_time,
+            restrict,
+            options: extra,
+        } => {
+            let key = match (key, key_file) {
+                (Some(key), _) => key,
+                (None, Some(path)) => std::fs::read_to_string(path)?,
+                (None, None) => unreachable!("clap requires KEY or --key-file"),
+            };
+            let quoted =
+                |name: &str, value: String| format!("{name}=\"{}\"", value.replace('"', "\\\""));
+            let mut options = Vec::new();
+            if restrict {
+                options.push("restrict".to_string());
+            }
+            options.extend(from.map(|value| quoted("from", value)));
+            options.extend(command.map(|value| quoted("command", value)));
+            options.extend(expiry_time.map(|value| quoted("expiry-time", value)));
+            options.extend(extra);
+
+            let entry = ssv::authorize(&label, &key, &options)?;
+            println!(
+                "🔓 Authorized '{label}' ({})",
+                entry.fingerprint.as_deref().unwrap_or(&entry.algorithm)
+            );
+        }
+        AuthorizedAction::Remove { label } => {
+            ssv::unauthorize(&label)?;
+            println!("🔒 Removed authorized key '{label}'");
+        }
+        AuthorizedAction::List => {
+            for entry in ssv::authorized_keys()? {
+                println!(
+                    "{:<16} {:<12} {} {}",
+                    entry.label,
+                    entry.algorithm,
+                    entry.fingerprint.as_deref().unwrap_or("-"),
+                    entry.comment.as_deref().unwrap_or_default()
+                );
+                if let Some(options) = &entry.options {
+                    println!("    options: {options}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_git(action: GitAction) -> Result<(), AppError> {
+    let bindings = match action {
+        GitAction::Use { host, repo, rewrite_remote } => {
+            let binding = ssv::git_use(&host, &repo, rewrite_remote.as_deref())?;
+            println!("🔑 {} now uses the key of '{host}'", binding.repo.display());
+            vec![binding]
+        }
+        GitAction::Status { repos } => ssv::git_status(&repos)?,
+        GitAction::Signing { host, global, repo, email } => {
+            let repo = (!global).then_some(repo.as_path());
+            let setup = ssv::git_signing(&host, repo, email.as_deref())?;
+            let scope = match &setup.repo {
+                Some(repo) => repo.display().to_string(),
+                None => "global git config".to_string(),
+            };
+            println!("✍️  Signing commits with '{host}' in {scope}");
+            println!("    user.signingkey = {}", setup.signing_key.display());
+            println!("    {} trusted in {}", setup.principal, setup.allowed_signers.display());
+            return Ok(());
+        }
+    };
+
+    for binding in bindings {
+        let key = match (&binding.host, &binding.key) {
+            (Some(host), _) => format!("managed key of '{host}'"),
+            (None, Some(key)) => format!("unmanaged key {}", key.display()),
+            (None, None) => "default ssh keys".to_string(),
+        };
+        println!("{}: {key}", binding.repo.display());
+        if let Some(command) = &binding.ssh_command {
+            println!("    core.sshCommand = {command}");
+        }
+        for remote in &binding.remotes {
+            let alias = remote.host.as_ref().map(|h| format!(" (alias '{h}')")).unwrap_or_default();
+            println!("    {} {}{alias}", remote.name, remote.url);
+        }
+    }
+    Ok(())
+}
+
+fn run_template(action: TemplateAction) -> Result<(), AppError> {
+    match action {
+        TemplateAction::List => {
+            for template in ssv::templates()? {
+                let source = if template.builtin { "built-in" } else { "user" };
+                let description = template.description.unwrap_or_default();
+                println!("{:<12} {source:<8}  {description}", template.name);
+            }
+        }
+        TemplateAction::Show { name } => print!("{}", ssv::template(&name)?.body),
+    }
+    Ok(())
+}
+
+fn run_config(action: ConfigAction) -> Result<(), AppError> {
+    match action {
+        ConfigAction::Get { key } => match ssv::settings()?.get(&key)? {
+            Some(value) => println!("{value}"),
+            None => {
+                eprintln!("'{key}' is not set");
+                std::process::exit(1);
+            }
+        },
+        ConfigAction::Set { key, value } => {
+            ssv::config_set(&key, &value)?;
+            println!("{key} = {value}");
+        }
+        ConfigAction::Unset { key } => {
+            if !ssv::config_unset(&key)? {
+                println!("'{key}' was not set");
+            }
+        }
+        ConfigAction::List => {
+            for (key, value) in ssv::settings()?.entries() {
+                println!("{key} = {value}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Exit with clap's usual error for `generate` without `--host` or `--pattern`.
+fn missing_host_error() -> ! {
+    Cli::command()
+        .mut_subcommand("generate", |cmd| {
+            cmd.mut_arg("host", |arg| arg.required_unless_present("patterns"))
+        })
+        .get_matches();
+    unreachable!("generate without --host or --pattern must fail to parse");
+}
+
+fn parse_key_value(raw: &str) -> Result<(String, String), String> {
+    raw.split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{raw}'"))
+}
+
+fn print_rotation(report: &RotationReport) {
+    let host = &report.host;
+    println!("🔄 Rotated the key of '{host}' ({})", report.key.display());
+    if !report.key.exists() {
+        println!(
+            "🔒 The new private key is in the vault; run `ssv unlock --agent --host {host}` to use it"
+        );
+    }
+    if let (Some(old), Some(new)) = (&report.old_fingerprint, &report.new_fingerprint) {
+        println!("    {old} -> {new}");
+    }
+    if report.redeployed {
+        println!("🚀 Replaced the old key in the authorized_keys of '{host}'");
+    }
+    for registration in &report.stale_registrations {
+        println!(
+            "⚠️  {} key {} \"{}\" is the old key; upload the new one with `ssv register`",
+            registration.provider, registration.key_id, registration.title
+        );
+    }
+}
+
+fn deploy_auth(using_password: bool, using_key: Option<&str>) -> DeployAuth<'_> {
+    match using_key {
+        Some(other) => DeployAuth::ManagedKey(other),
+        None if using_password => DeployAuth::Password,
+        None => DeployAuth::Default,
+    }
+}
+
+/// API token from the named environment variable.
+fn read_token(name: &str) -> Result<String, AppError> {
+    std::env::var(name)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| AppError::ValidationError(format!("environment variable {name} is not set")))
+}
+
+fn parse_filter(raw: &str) -> Result<HostFilter, String> {
+    raw.parse().map_err(|err: AppError| err.to_string())
+}
+
+fn parse_backend(raw: &str) -> Result<KeyBackend, String> {
+    raw.parse().map_err(|err: AppError| err.to_string())
+}
+
+fn parse_age(raw: &str) -> Result<HostFilter, String> {
+    HostFilter::older_than(raw).map_err(|err| err.to_string())
+}
+
+fn parse_token_env(raw: &str) -> Result<(Provider, String), String> {
+    let (provider, var) = raw
+        .split_once('=')
+        .filter(|(_, var)| !var.is_empty())
+        .ok_or_else(|| format!("expected PROVIDER=VAR, e.g. github=GITHUB_TOKEN, not '{raw}'"))?;
+    Ok((parse_provider(provider)?, var.to_string()))
+}
+
+fn parse_provider(raw: &str) -> Result<Provider, String> {
+    raw.parse().map_err(|err: AppError| err.to_string())
+}
+
+/// Bundle passphrase from the named environment variable, or an interactive prompt on a terminal.
+fn read_passphrase(env_var: Option<&str>, confirm: bool) -> Result<String, AppError> {
+    if let Some(name) = env_var {
+        return std::env::var(name).map_err(|_| {
+            AppError::ValidationError(format!("environment variable {name} is not set"))
+        });
+    }
+    if !io::stdin().is_terminal() {
+        return Err(AppError::ValidationError(
+            "no terminal to prompt for a passphrase; use --passphrase-env VAR".to_string(),
+        ));
+    }
+
+    let passphrase = rpassword::prompt_password("Bundle passphrase: ")?;
+    if confirm && rpassword::prompt_password("Confirm passphrase: ")? != passphrase {
+        return Err(AppError::ValidationError("passphrases do not match".to_string()));
+    }
+    Ok(passphrase)
+}
+
+fn print_plan(plan: &Plan) {
+    if plan.is_empty() {
+        println!("No changes; managed hosts match the manifest.");
+        return;
+    }
+
+    for change in &plan.changes {
+        let symbol = match change.action {
+            ChangeAction::Create => '+',
+            ChangeAction::Update => '~',
+            ChangeAction::Prune => '-',
+        };
+        println!("{symbol} {}", change.alias);
+        for line in &change.diff {
+            println!("    {line}");
+        }
+        for note in &change.notes {
+            println!("    # {note}");
+        }
+    }
+}
+
+fn print_long_listing(hosts: &[String]) -> Result<(), AppError> {
+    let mut rows = vec![
+        ["HOST", "STATUS", "CREATED", "CREATOR", "PURPOSE", "TICKET", "EXPIRES"].map(String::from),
+    ];
+    for host in hosts {
+        let meta = ssv::metadata(host)?.unwrap_or_default();
+        let cell = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        rows.push([
+            host.clone(),
+            ssv::ownership(host)?.to_string(),
+            cell(meta.created_at),
+            cell(meta.creator),
+            cell(meta.purpose),
+            cell(meta.ticket),
+            cell(meta.expires),
+        ]);
+    }
+
+    let mut widths = [0usize; 7];
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    for row in rows {
+        let line: Vec<String> =
+            row.iter().zip(widths).map(|(value, width)| format!("{value:<width$}")).collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+    Ok(())
+}
+
+/// Print hosts as a forest rooted at hosts without a managed jump host.
+fn print_tree(links: &[(String, Option<String>)]) {
+    let managed: Vec<&str> = links.iter().map(|(host, _)| host.as_str()).collect();
+    let children = |parent: &str| -> Vec<&str> {
+        links
+            .iter()
+            .filter(|(_, via)| via.as_deref() == Some(parent))
+            .map(|(host, _)| host.as_str())
+            .collect()
+    };
+
+    fn walk<'a>(
+        host: &'a str,
+        prefix: &str,
+        children: &dyn Fn(&str) -> Vec<&'a str>,
+        seen: &mut Vec<&'a str>,
+    ) {
+        seen.push(host);
+        let kids = children(host);
+        for (index, child) in kids.iter().enumerate() {
+            let last = index + 1 == kids.len();
+            println!("{prefix}{}{child}", if last { "└── " } else { "├── " });
+            if !seen.contains(child) {
+                let nested = format!("{prefix}{}", if last { "    " } else { "│   " });
+                walk(child, &nested, children, seen);
+            }
+        }
+    }
+
+    let mut seen = Vec::new();
+    for (host, via) in links {
+        match via.as_deref() {
+            Some(jump) if managed.contains(&jump) => continue,
+            Some(jump) => println!("{host} (via {jump}, unmanaged)"),
+            None => println!("{host}"),
+        }
+        walk(host, "", &children, &mut seen);
+    }
+    for (host, _) in links {
+        if !seen.contains(&host.as_str()) {
+            println!("{host} (jump cycle)");
+        }
+    }
+}
+
+fn print_audit(report: &AuditReport) {
+    if report.hosts.is_empty() {
+        println!("(no hosts managed yet)");
+        return;
+    }
+
+    for host in &report.hosts {
+        let key = match (&host.key_type, host.bits) {
+            (Some(key_type), Some(bits)) => format!("{key_type} {bits} bits"),
+            (Some(key_type), None) => format!("{key_type} (unknown size)"),
+            (None, _) => "unknown key".to_string(),
+        };
+        let age =
+            host.age_days.map_or_else(|| "age unknown".to_string(), |d| format!("{d} days old"));
+        let encryption = match host.encrypted {
+            Some(true) => "encrypted",
+            Some(false) => "unencrypted",
+            None => "encryption unknown",
+        };
+        let status =
+            if host.findings.iter().any(|f| f.severity == Severity::Error) { "FAIL" } else { "ok" };
+        println!("[{status}] {}: {key}, {age}, {encryption}", host.host);
+        for file in &host.permissions {
+            println!("    {} {}", file.mode, file.path.display());
+        }
+        for finding in &host.findings {
+            let level = match finding.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            println!("    {level}[{}]: {}", finding.rule, finding.message);
+        }
+    }
+
+    println!(
+        "{} host(s) audited, {} violation(s), max age {} days",
+        report.hosts.len(),
+        report.violations(),
+        report.max_age_days
+    );
+}
+
+fn print_doctor(report: &DoctorReport) {
+    if report.drift.is_empty() {
+        println!("✅ All {} host configs match what ssv wrote", report.checked);
+        return;
+    }
+    for drift in &report.drift {
+        println!("⚠️  {}: {} {}", drift.host, drift.config_path.display(), drift.kind.describe());
+    }
+    if !report.adopted.is_empty() {
+        println!("✅ Adopted {} as they are now", report.adopted.join(", "));
+    }
+    let left: Vec<&Drift> =
+        report.drift.iter().filter(|drift| !report.adopted.contains(&drift.host)).collect();
+    if left.iter().any(|drift| matches!(drift.kind, DriftKind::Foreign | DriftKind::Untracked)) {
+        println!(
+            "`ssv doctor --adopt <HOST>` takes over a config ssv did not write; \
+             `ssv remove --force-foreign --host <HOST>` deletes it."
+        );
+    }
+    if left.iter().any(|drift| matches!(drift.kind, DriftKind::Modified | DriftKind::Missing)) {
+        println!("Review the changes, then run `ssv doctor --adopt` to accept them.");
+    }
+}
+
+/// One line per change: when, who, what, which host and which key.
+fn print_history_entry(entry: &HistoryEntry) {
+    let fingerprint = entry.fingerprint.as_deref().unwrap_or("-");
+    println!(
+        "{}  {:<10} {:<11} {}  {fingerprint}",
+        entry.timestamp,
+        entry.user,
+        entry.operation.as_str(),
+        entry.host
+    );
+}
+
+fn print_details(details: &HostDetails) {
+    println!("Host: {}", details.host);
+    println!("Config: {}", details.config_path.display());
+    for directive in &details.config {
+        println!("    {} {}", directive.key, directive.value);
+    }
+
+    println!("Keys:");
+    for key in &details.keys {
+        if key.exists {
+            let mode = key.mode.as_deref().unwrap_or("----");
+            let size = key.size.unwrap_or_default();
+            println!("    {:<11} {mode} {size:>6}B {}", key.role, key.path.display());
+        } else {
+            println!("    {:<11} (missing) {}", key.role, key.path.display());
+        }
+    }
+
+    if let Some(fingerprint) = &details.fingerprint {
+        println!("Fingerprint: {fingerprint}");
+    }
+    if let Some(public_key) = &details.public_key {
+        println!("Public key: {public_key}");
+    }
+    if let Some(cert) = &details.certificate {
+        let until = cert.valid_before.as_deref().unwrap_or("forever");
+        let state = if cert.expired { "expired" } else { "valid" };
+        println!("Certificate: {} ({state}) from {} to {until}", cert.key_id, cert.valid_after);
+        if !cert.principals.is_empty() {
+            println!("    principals: {}", cert.principals.join(", "));
+        }
+    }
+
+    if details.known_hosts.is_empty() {
+        println!("Known hosts: (none)");
+    } else {
+        println!("Known hosts:");
+        for entry in &details.known_hosts {
+            println!("    {entry}");
+        }
+    }
+
+    if let Some(meta) = &details.metadata {
+        println!("Metadata:");
+        let fields = [
+            ("created_at", &meta.created_at),
+            ("creator", &meta.creator),
+            ("comment", &meta.comment),
+            ("purpose", &meta.purpose),
+            ("ticket", &meta.ticket),
+            ("expires", &meta.expires),
+            ("via", &meta.via),
+            ("deployed_at", &meta.deployment.as_ref().map(|d| d.deployed_at.clone())),
+            ("key_options", &meta.deployment.as_ref().and_then(|d| d.key_options.clone())),
+            ("patterns", &(!meta.patterns.is_empty()).then(|| meta.patterns.join(" "))),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                println!("    {name}: {value}");
+            }
+        }
+        for registration in &meta.registrations {
+            println!(
+                "    registered: {} key {} \"{}\" ({})",
+                registration.provider,
+                registration.key_id,
+                registration.title,
+                registration.api_url
+            );
+        }
+    }
+}
+
+/// Completion candidates for host arguments, read from the managed inventory at completion time.
+fn managed_hosts() -> Vec<CompletionCandidate> {
+    ssv::list()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|host| {
+            let purpose = ssv::metadata(&host).ok().flatten().and_then(|meta| meta.purpose);
+            CompletionCandidate::new(&host).help(purpose.map(Into::into))
+        })
+        .collect()
+}
+
+/// Completion candidates for `--template`, with each template's description as help text.
+fn template_names() -> Vec<CompletionCandidate> {
+    ssv::templates()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|template| {
+            CompletionCandidate::new(&template.name).help(template.description.map(Into::into))
+        })
+        .collect()
+}
+
+/// Emit a registration script that calls back into `ssv` so host names complete dynamically.
+fn write_completions(shell: CompletionShell) -> io::Result<()> {
+    let name = match shell {
+        CompletionShell::Bash => "bash",
+        CompletionShell::Zsh => "zsh",
+        CompletionShell::Fish => "fish",
+        CompletionShell::Powershell => "powershell",
+        CompletionShell::Elvish => "elvish",
+    };
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(name)
+        .ok_or_else(|| io::Error::other(format!("unsupported shell '{name}'")))?;
+    completer.write_registration("COMPLETE", "ssv", "ssv", "ssv", &mut io::stdout())
+}
+
+fn write_man_pages(out_dir: Option<PathBuf>) -> io::Result<()> {
+    let command = Cli::command();
+    match out_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(&dir)?;
+            clap_mangen::generate_to(command, &dir)?;
+            println!("📖 Wrote man pages to {}", dir.display());
+            Ok(())
+        }
+        None => clap_mangen::Man::new(command).render(&mut io::stdout()),
+    }
+}
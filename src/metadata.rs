@@ -0,0 +1,90 @@
+//! Sidecar metadata recorded alongside each managed host.
+
+use crate::error::AppError;
+use crate::providers::Provider;
+use crate::secure_file;
+use crate::ssh_paths::SshPaths;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+
+/// Descriptive information stored in `~/.ssh/.ssv/meta/<HOST>.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostMetadata {
+    /// RFC 3339 timestamp of when the key pair was generated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    /// Local account that generated the key pair.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator: Option<String>,
+    /// Comment embedded in the public key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Free-form description of what the key is for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    /// Ticket or change request that motivated the key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<String>,
+    /// Date (`YYYY-MM-DD`) after which the key should no longer be used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    /// Managed host this one is reached through (`generate --via`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub via: Option<String>,
+    /// Host patterns written on the `Host` line when the entry name is only a slug.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+    /// Provider accounts the public key was uploaded to with `ssv register`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registrations: Vec<Registration>,
+    /// Set while the public key is installed in the server's `authorized_keys` by `ssv deploy`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<Deployment>,
+}
+
+/// A public key installed on the server with `ssv deploy`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    /// RFC 3339 timestamp of the last deploy.
+    pub deployed_at: String,
+    /// `authorized_keys` options written in front of the key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_options: Option<String>,
+}
+
+/// A public key uploaded to a Git hosting provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Registration {
+    pub provider: Provider,
+    pub api_url: String,
+    /// Provider-side key id, used to delete the key again.
+    pub key_id: u64,
+    pub title: String,
+}
+
+pub(crate) fn load(paths: &SshPaths, host: &str) -> Result<Option<HostMetadata>, AppError> {
+    let path = paths.metadata_path(host);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(AppError::from(err)),
+    };
+
+    serde_json::from_str(&contents).map(Some).map_err(|err| {
+        AppError::config_error(format!("invalid metadata file {}: {err}", path.display()))
+    })
+}
+
+pub(crate) fn save(paths: &SshPaths, host: &str, metadata: &HostMetadata) -> Result<(), AppError> {
+    let path = paths.metadata_path(host);
+    paths.ensure_dir_with_mode(&paths.state_dir())?;
+    if let Some(parent) = path.parent() {
+        paths.ensure_dir_with_mode(parent)?;
+    }
+
+    let mut contents = serde_json::to_string_pretty(metadata)
+        .map_err(|err| AppError::config_error(format!("failed to encode metadata: {err}")))?;
+    contents.push('\n');
+    secure_file::write(&path, contents.as_bytes(), 0o600)
+}
@@ -0,0 +1,276 @@
+//! Which host configs ssv wrote, and whether they changed since.
+//!
+//! Every config ssv writes starts with a header naming the ssv version and the SHA-256 of the
+//! rest of the file, and is recorded with that checksum in `~/.ssh/.ssv/managed.json`. Only the
+//! state file decides: anyone can write a matching header, so a config without an entry is
+//! foreign, and one whose contents no longer match its entry is modified.
+//!
+//! Homes set up before the state file existed are migrated on first use: configs that are exactly
+//! what ssv renders for their metadata sidecar, or what the first releases wrote before sidecars
+//! existed, are tracked as they are.
+
+use crate::commands::list_hosts::ListHosts;
+use crate::error::AppError;
+use crate::ssh_config::{self, HostBlock};
+use crate::ssh_paths::SshPaths;
+use crate::{metadata, secure_file, timestamp};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::ErrorKind;
+
+const HEADER_PREFIX: &str = "# Managed by ssv ";
+
+/// Who is responsible for a host config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ownership {
+    /// Written by ssv and unchanged since.
+    Managed,
+    /// Written by ssv, then changed by something else.
+    Modified,
+    /// Not tracked by ssv, e.g. placed in `conf.d` by hand, whatever its header claims.
+    Foreign,
+}
+
+impl Ownership {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Ownership::Managed => "managed",
+            Ownership::Modified => "modified",
+            Ownership::Foreign => "foreign",
+        }
+    }
+}
+
+impl Display for Ownership {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A config's entry in the state file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Tracked {
+    /// Hex SHA-256 of the config below its header.
+    pub sha256: String,
+    /// ssv version that wrote it.
+    pub version: String,
+    /// RFC 3339 timestamp of the write.
+    pub written_at: String,
+}
+
+/// Contents of `~/.ssh/.ssv/managed.json`, keyed by host.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct State {
+    #[serde(default)]
+    pub configs: BTreeMap<String, Tracked>,
+}
+
+/// Write `body` as the config of `host`, stamped with a header, and track its checksum.
+pub(crate) fn write_config(paths: &SshPaths, host: &str, body: &str) -> Result<(), AppError> {
+    let body = strip_header(body);
+    let sha256 = checksum(body);
+    let version = env!("CARGO_PKG_VERSION");
+    let contents = format!("{HEADER_PREFIX}{version}; sha256={sha256}\n{body}");
+    secure_file::write(&paths.host_config_path(host), contents.as_bytes(), 0o600)?;
+
+    let mut state = load(paths)?;
+    let tracked =
+        Tracked { sha256, version: version.to_string(), written_at: timestamp::now_rfc3339() };
+    state.configs.insert(host.to_string(), tracked);
+    save(paths, &state)
+}
+
+/// Stop tracking the config of `host`, once it is deleted.
+pub(crate) fn forget(paths: &SshPaths, host: &str) -> Result<(), AppError> {
+    let mut state = load(paths)?;
+    if state.configs.remove(host).is_some() {
+        save(paths, &state)?;
+    }
+    Ok(())
+}
+
+/// Track the config of `host` under an entry recorded earlier, e.g. when it is restored.
+pub(crate) fn track(paths: &SshPaths, host: &str, tracked: Tracked) -> Result<(), AppError> {
+    let mut state = load(paths)?;
+    state.configs.insert(host.to_string(), tracked);
+    save(paths, &state)
+}
+
+/// How the config `contents` of `host` relates to what ssv recorded writing.
+pub(crate) fn classify(state: &State, host: &str, contents: &str) -> Ownership {
+    match state.configs.get(host) {
+        None => Ownership::Foreign,
+        Some(tracked) if tracked.sha256 == checksum(strip_header(contents)) => Ownership::Managed,
+        Some(_) => Ownership::Modified,
+    }
+}
+
+/// Ownership of the config of `host`, which must exist.
+pub(crate) fn status(paths: &SshPaths, host: &str) -> Result<Ownership, AppError> {
+    let contents = fs::read_to_string(paths.host_config_path(host))?;
+    Ok(classify(&load(paths)?, host, &contents))
+}
+
+/// The config without ssv's header line.
+pub(crate) fn strip_header(contents: &str) -> &str {
+    match contents.split_once('\n') {
+        Some((first, rest)) if first.starts_with(HEADER_PREFIX) => rest,
+        None if contents.starts_with(HEADER_PREFIX) => "",
+        _ => contents,
+    }
+}
+
+/// The checksum recorded in the header, if the config has one.
+pub(crate) fn header_checksum(contents: &str) -> Option<&str> {
+    contents
+        .lines()
+        .next()?
+        .strip_prefix(HEADER_PREFIX)?
+        .split_once("; sha256=")
+        .map(|(_, sum)| sum)
+}
+
+fn checksum(body: &str) -> String {
+    Sha256::digest(body.as_bytes()).iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(crate) fn load(paths: &SshPaths) -> Result<State, AppError> {
+    let path = paths.managed_state_path();
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return migrated(paths),
+        Err(err) => return Err(err.into()),
+    };
+    serde_json::from_str(&contents).map_err(|err| {
+        AppError::config_error(format!("invalid state file {}: {err}", path.display()))
+    })
+}
+
+/// State for a home without a state file: unstamped configs that ssv rendered, as told by
+/// [`rendered_by_ssv`], are tracked as they are. Nothing is written until the next save.
+fn migrated(paths: &SshPaths) -> Result<State, AppError> {
+    let mut state = State::default();
+    for host in ListHosts.execute(paths)? {
+        let Ok(contents) = fs::read_to_string(paths.host_config_path(&host)) else {
+            continue;
+        };
+        if header_checksum(&contents).is_some()
+            || !(rendered_by_ssv(paths, &host, &contents)?
+                || rendered_by_first_release(paths, &host, &contents))
+        {
+            continue;
+        }
+        let written_at = metadata::load(paths, &host)?
+            .and_then(|meta| meta.created_at)
+            .unwrap_or_else(timestamp::now_rfc3339);
+        let tracked =
+            Tracked { sha256: checksum(&contents), version: "unknown".to_string(), written_at };
+        state.configs.insert(host, tracked);
+    }
+    Ok(state)
+}
+
+/// Whether `contents` is, byte for byte, the block ssv renders for `host` from its own
+/// directives, and ssv keeps a metadata sidecar for it. Hand-written files fail either test.
+fn rendered_by_ssv(paths: &SshPaths, host: &str, contents: &str) -> Result<bool, AppError> {
+    let Some(meta) = metadata::load(paths, host)? else {
+        return Ok(false);
+    };
+    let directives = ssh_config::parse(contents);
+    let value = |key| ssh_config::value(&directives, key);
+    let Some(identity_file) = value("IdentityFile") else {
+        return Ok(false);
+    };
+    const RENDERED: &[&str] =
+        &["host", "hostname", "user", "port", "identityfile", "identitiesonly"];
+    let options: Vec<(String, String)> = directives
+        .iter()
+        .filter(|directive| !RENDERED.contains(&directive.key.to_ascii_lowercase().as_str()))
+        .map(|directive| (directive.key.clone(), directive.value.clone()))
+        .collect();
+    let block = HostBlock {
+        alias: host,
+        patterns: &meta.patterns,
+        hostname: value("HostName"),
+        user: value("User"),
+        port: value("Port").and_then(|port| port.parse().ok()),
+        identity_file,
+        options: &options,
+    };
+    Ok(block.render() == contents)
+}
+
+/// Whether `contents` is, byte for byte, the fixed block the first ssv releases wrote:
+/// `Host`, `HostName` set to the alias, optional `User` and `Port`, the managed `IdentityFile`
+/// and `IdentitiesOnly yes`. Those releases kept no metadata sidecar.
+fn rendered_by_first_release(paths: &SshPaths, host: &str, contents: &str) -> bool {
+    let directives = ssh_config::parse(contents);
+    let value = |key| ssh_config::value(&directives, key);
+    let Some(key_type) = value("IdentityFile")
+        .and_then(|file| file.strip_prefix("~/.ssh/id_"))
+        .and_then(|file| file.strip_suffix(&format!("_{host}")))
+        .filter(|key_type| paths.validate_key_type(key_type).is_ok())
+    else {
+        return false;
+    };
+
+    let mut expected = format!("Host {host}\nHostName {host}\n");
+    if let Some(user) = value("User") {
+        expected.push_str(&format!("User {user}\n"));
+    }
+    if let Some(port) = value("Port").filter(|port| port.parse::<u16>().is_ok()) {
+        expected.push_str(&format!("Port {port}\n"));
+    }
+    expected.push_str(&format!("IdentityFile ~/.ssh/id_{key_type}_{host}\nIdentitiesOnly yes\n"));
+    expected == contents
+}
+
+fn save(paths: &SshPaths, state: &State) -> Result<(), AppError> {
+    paths.ensure_dir_with_mode(&paths.state_dir())?;
+    let mut contents = serde_json::to_string_pretty(state)
+        .map_err(|err| AppError::config_error(format!("failed to encode state file: {err}")))?;
+    contents.push('\n');
+    secure_file::write(&paths.managed_state_path(), contents.as_bytes(), 0o600)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamped(body: &str) -> String {
+        format!("{HEADER_PREFIX}0.2.0; sha256={}\n{body}", checksum(body))
+    }
+
+    #[test]
+    fn classifies_by_state_alone() {
+        let body = "Host db\nHostName db\n";
+        let mut state = State::default();
+        assert_eq!(classify(&state, "db", body), Ownership::Foreign);
+        // A correct header is easy to forge, so it counts for nothing without a state entry.
+        assert_eq!(classify(&state, "db", &stamped(body)), Ownership::Foreign);
+
+        let tracked = Tracked {
+            sha256: checksum(body),
+            version: "0.2.0".into(),
+            written_at: "2026-01-01T00:00:00Z".into(),
+        };
+        state.configs.insert("db".into(), tracked);
+        assert_eq!(classify(&state, "db", &stamped(body)), Ownership::Managed);
+        assert_eq!(classify(&state, "db", body), Ownership::Managed);
+        // The file was replaced by another stamped one.
+        assert_eq!(classify(&state, "db", &stamped("Host web\n")), Ownership::Modified);
+    }
+
+    #[test]
+    fn strips_only_ssv_headers() {
+        let body = "Host db\n";
+        assert_eq!(strip_header(&stamped(body)), body);
+        assert_eq!(strip_header("# my notes\nHost db\n"), "# my notes\nHost db\n");
+        assert_eq!(header_checksum(&stamped(body)), Some(checksum(body).as_str()));
+        assert_eq!(header_checksum(body), None);
+    }
+}
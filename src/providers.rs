@@ -0,0 +1,152 @@
+//! Minimal clients for the SSH key endpoints of Git hosting providers.
+//!
+//! GitHub, GitLab and Gitea all expose `GET/POST /user/keys` and `DELETE /user/keys/{id}` with
+//! the same `{id, title, key}` shape; they differ only in the default API base and the
+//! authentication header.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Keys requested per page when listing; Gitea caps pages at 50 by default.
+const PAGE_SIZE: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    Github,
+    Gitlab,
+    Gitea,
+}
+
+/// A public key as reported by a provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteKey {
+    pub id: u64,
+    #[serde(default)]
+    pub title: String,
+    pub key: String,
+}
+
+impl Provider {
+    pub fn default_api_url(self) -> &'static str {
+        match self {
+            Provider::Github => "https://api.github.com",
+            Provider::Gitlab => "https://gitlab.com/api/v4",
+            Provider::Gitea => "https://gitea.com/api/v1",
+        }
+    }
+
+    pub(crate) fn upload(
+        self,
+        api_url: &str,
+        token: &str,
+        title: &str,
+        key: &str,
+    ) -> Result<RemoteKey, AppError> {
+        let url = format!("{}/user/keys", api_url.trim_end_matches('/'));
+        let response = self
+            .request("POST", &url, token)
+            .send_json(serde_json::json!({ "title": title, "key": key }))
+            .map_err(|err| Self::error(&url, err))?;
+        Self::decode(&url, response)
+    }
+
+    /// Every key of the account, following the pages until a short one.
+    pub(crate) fn list(self, api_url: &str, token: &str) -> Result<Vec<RemoteKey>, AppError> {
+        let size = match self {
+            Provider::Gitea => "limit",
+            Provider::Github | Provider::Gitlab => "per_page",
+        };
+        let mut keys = Vec::new();
+        for page in 1.. {
+            let url = format!(
+                "{}/user/keys?{size}={PAGE_SIZE}&page={page}",
+                api_url.trim_end_matches('/')
+            );
+            let response =
+                self.request("GET", &url, token).call().map_err(|err| Self::error(&url, err))?;
+            let batch: Vec<RemoteKey> = Self::decode(&url, response)?;
+            let last = batch.len() < PAGE_SIZE;
+            keys.extend(batch);
+            if last {
+                break;
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Delete a key; a key that is already gone counts as deleted.
+    pub(crate) fn delete(self, api_url: &str, token: &str, id: u64) -> Result<(), AppError> {
+        let url = format!("{}/user/keys/{id}", api_url.trim_end_matches('/'));
+        match self.request("DELETE", &url, token).call() {
+            Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+            Err(err) => Err(Self::error(&url, err)),
+        }
+    }
+
+    fn request(self, method: &str, url: &str, token: &str) -> ureq::Request {
+        let request = ureq::AgentBuilder::new()
+            .timeout(TIMEOUT)
+            .build()
+            .request(method, url)
+            .set("User-Agent", concat!("ssv/", env!("CARGO_PKG_VERSION")));
+        match self {
+            Provider::Github => request
+                .set("Accept", "application/vnd.github+json")
+                .set("Authorization", &format!("Bearer {token}")),
+            Provider::Gitlab => request.set("PRIVATE-TOKEN", token),
+            Provider::Gitea => request.set("Authorization", &format!("token {token}")),
+        }
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(
+        url: &str,
+        response: ureq::Response,
+    ) -> Result<T, AppError> {
+        response.into_json().map_err(|err| {
+            AppError::request_failed(url, None, format!("unexpected response body: {err}"))
+        })
+    }
+
+    fn error(url: &str, err: ureq::Error) -> AppError {
+        match err {
+            ureq::Error::Status(status, response) => {
+                let body = response.into_string().unwrap_or_default();
+                AppError::request_failed(url, Some(status), body.trim().to_string())
+            }
+            ureq::Error::Transport(transport) => {
+                AppError::request_failed(url, None, transport.to_string())
+            }
+        }
+    }
+}
+
+impl Display for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Provider::Github => "github",
+            Provider::Gitlab => "gitlab",
+            Provider::Gitea => "gitea",
+        })
+    }
+}
+
+impl FromStr for Provider {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "github" => Ok(Provider::Github),
+            "gitlab" => Ok(Provider::Gitlab),
+            "gitea" => Ok(Provider::Gitea),
+            _ => Err(AppError::validation_error(format!(
+                "unknown provider '{value}'; expected github, gitlab or gitea"
+            ))),
+        }
+    }
+}
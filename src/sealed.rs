@@ -0,0 +1,75 @@
+//! Passphrase-based authenticated encryption for export bundles and the key vault.
+//!
+//! Layout: `magic (8) | salt (16) | nonce (24) | XChaCha20-Poly1305 ciphertext`, with the key
+//! derived from the passphrase by Argon2id. The magic is bound as associated data.
+
+use crate::error::AppError;
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+pub(crate) fn seal(
+    magic: &[u8; 8],
+    passphrase: &str,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, AppError> {
+    if passphrase.is_empty() {
+        return Err(AppError::validation_error("passphrase must not be empty"));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?.into());
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad: magic })
+        .map_err(|_| AppError::config_error("encryption failed"))?;
+
+    let mut sealed = Vec::with_capacity(magic.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(magic);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+pub(crate) fn open(magic: &[u8; 8], passphrase: &str, sealed: &[u8]) -> Result<Vec<u8>, AppError> {
+    let body = sealed
+        .strip_prefix(magic.as_slice())
+        .filter(|body| body.len() > SALT_LEN + NONCE_LEN)
+        .ok_or_else(|| AppError::validation_error("file is not an ssv encrypted archive"))?;
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, salt)?.into());
+    cipher
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: magic })
+        .map_err(|_| AppError::validation_error("wrong passphrase or corrupted archive"))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], AppError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| AppError::config_error(format!("key derivation failed: {err}")))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAGIC: &[u8; 8] = b"SSVTEST1";
+
+    #[test]
+    fn round_trips_and_rejects_wrong_passphrase() {
+        let sealed = seal(MAGIC, "correct horse", b"secret").unwrap();
+        assert_eq!(open(MAGIC, "correct horse", &sealed).unwrap(), b"secret");
+        assert!(open(MAGIC, "battery staple", &sealed).is_err());
+        assert!(open(b"SSVOTHER", "correct horse", &sealed).is_err());
+    }
+}
@@ -0,0 +1,42 @@
+//! Atomic, permission-restricted writes for files under `~/.ssh`.
+
+use crate::error::AppError;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+/// Write `contents` to `path` via a sibling temporary file that is synced and renamed into place,
+/// so readers never observe a partially written file. The final file carries `mode` on Unix.
+pub(crate) fn write(path: &Path, contents: &[u8], mode: u32) -> Result<(), AppError> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let tmp_path = parent.join(format!(".{file_name}.tmp-{}", std::process::id()));
+
+    let result = (|| -> Result<(), AppError> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(mode);
+        let mut file = options.open(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+
+        #[cfg(unix)]
+        fs::set_permissions(&tmp_path, fs::Permissions::from_mode(mode))?;
+        #[cfg(not(unix))]
+        let _ = mode;
+
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
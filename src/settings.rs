@@ -0,0 +1,262 @@
+//! User defaults read from `$XDG_CONFIG_HOME/ssv/config.toml` (`~/.config/ssv/config.toml`).
+//!
+//! Settings sit below command-line flags and environment variables and above built-in defaults;
+//! callers only consult them for values the user did not pass explicitly.
+
+use crate::error::AppError;
+use crate::key_store::STORAGES;
+use crate::keygen::KeyBackend;
+use crate::ssh_paths::SshPaths;
+use crate::{secure_file, ssh_config};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+
+/// Output formats a default may name; commands that cannot print one fall back to `human`.
+const FORMATS: &[&str] = &["human", "json", "sarif"];
+
+/// Scalar keys accepted by `ssv config get|set`; tables use `users.<PATTERN>` and
+/// `options.<KEYWORD>`.
+pub const KEYS: &[&str] =
+    &["key_type", "bits", "comment", "format", "backend", "storage", "trash_retention"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Key type for `ssv generate` when `--type` is omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<String>,
+    /// Key size passed to `ssh-keygen -b` when `--bits` is omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bits: Option<u32>,
+    /// Key comment template when `--comment` is omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Default `--format`; `sarif` only applies to `audit`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Key generator for `generate`, `rotate` and `apply` when `--backend` is omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// Where private keys are kept: `files` in `~/.ssh` (the default) or the encrypted `vault`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<String>,
+    /// Days `ssv restore` can bring a removed host back; 0 deletes removed hosts right away.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trash_retention: Option<u32>,
+    /// Default `User` per host pattern (`*` and `?` wildcards); the longest matching pattern wins.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub users: BTreeMap<String, String>,
+    /// Directives added to every generated host block unless overridden with `--option`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, String>,
+}
+
+impl Settings {
+    /// Default user for a hostname, picked from the most specific matching pattern.
+    pub fn user_for(&self, hostname: &str) -> Option<&str> {
+        self.users
+            .iter()
+            .filter(|(pattern, _)| ssh_config::pattern_matches(pattern, hostname))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, user)| user.as_str())
+    }
+
+    /// Configured directives overlaid with explicit ones; explicit keys win case-insensitively.
+    pub fn merged_options(&self, explicit: &[(String, String)]) -> Vec<(String, String)> {
+        let mut merged: Vec<(String, String)> = self
+            .options
+            .iter()
+            .filter(|(key, _)| !explicit.iter().any(|(k, _)| k.eq_ignore_ascii_case(key)))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        merged.extend_from_slice(explicit);
+        merged
+    }
+
+    /// Current value of a `config` key, or `None` when unset.
+    pub fn get(&self, key: &str) -> Result<Option<String>, AppError> {
+        Ok(match key {
+            "key_type" => self.key_type.clone(),
+            "bits" => self.bits.map(|bits| bits.to_string()),
+            "comment" => self.comment.clone(),
+            "format" => self.format.clone(),
+            "backend" => self.backend.clone(),
+            "storage" => self.storage.clone(),
+            "trash_retention" => self.trash_retention.map(|days| days.to_string()),
+            _ => match Self::table_key(key)? {
+                ("users", pattern) => self.users.get(pattern).cloned(),
+                (_, keyword) => self.options.get(keyword).cloned(),
+            },
+        })
+    }
+
+    /// Validate and store a value for a `config` key.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), AppError> {
+        if value.is_empty() || value.contains('\n') {
+            return Err(AppError::validation_error(format!("invalid value for '{key}'")));
+        }
+
+        match key {
+            "key_type" => {
+                if !value.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) {
+                    return Err(AppError::validation_error(format!(
+                        "invalid key type '{value}'; expected lowercase letters or digits"
+                    )));
+                }
+                self.key_type = Some(value.to_string());
+            }
+            "bits" => {
+                let bits = value.parse().map_err(|_| {
+                    AppError::validation_error(format!("bits must be a number, got '{value}'"))
+                })?;
+                self.bits = Some(bits);
+            }
+            "comment" => self.comment = Some(value.to_string()),
+            "format" => {
+                if !FORMATS.contains(&value) {
+                    return Err(AppError::validation_error(format!(
+                        "unknown format '{value}'; expected one of {}",
+                        FORMATS.join(", ")
+                    )));
+                }
+                self.format = Some(value.to_string());
+            }
+            "backend" => {
+                value.parse::<KeyBackend>()?;
+                self.backend = Some(value.to_string());
+            }
+            "storage" => {
+                if !STORAGES.contains(&value) {
+                    return Err(AppError::validation_error(format!(
+                        "unknown storage '{value}'; expected one of {}",
+                        STORAGES.join(", ")
+                    )));
+                }
+                self.storage = Some(value.to_string());
+            }
+            "trash_retention" => {
+                let days = value.strip_suffix('d').unwrap_or(value).parse().map_err(|_| {
+                    AppError::validation_error(format!(
+                        "trash_retention must be a number of days, got '{value}'"
+                    ))
+                })?;
+                self.trash_retention = Some(days);
+            }
+            _ => match Self::table_key(key)? {
+                ("users", pattern) => {
+                    ssh_config::validate_user(value)?;
+                    self.users.insert(pattern.to_string(), value.to_string());
+                }
+                (_, keyword) => {
+                    ssh_config::validate_extra_option(keyword, value)?;
+                    self.options.insert(keyword.to_string(), value.to_string());
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Clear a `config` key, returning whether it had a value.
+    pub fn unset(&mut self, key: &str) -> Result<bool, AppError> {
+        Ok(match key {
+            "key_type" => self.key_type.take().is_some(),
+            "bits" => self.bits.take().is_some(),
+            "comment" => self.comment.take().is_some(),
+            "format" => self.format.take().is_some(),
+            "backend" => self.backend.take().is_some(),
+            "storage" => self.storage.take().is_some(),
+            "trash_retention" => self.trash_retention.take().is_some(),
+            _ => match Self::table_key(key)? {
+                ("users", pattern) => self.users.remove(pattern).is_some(),
+                (_, keyword) => self.options.remove(keyword).is_some(),
+            },
+        })
+    }
+
+    /// Every configured value as `key = value` pairs in `config get` key syntax.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut entries: Vec<(String, String)> = KEYS
+            .iter()
+            .filter_map(|key| self.get(key).ok().flatten().map(|value| (key.to_string(), value)))
+            .collect();
+        entries.extend(self.users.iter().map(|(k, v)| (format!("users.{k}"), v.clone())));
+        entries.extend(self.options.iter().map(|(k, v)| (format!("options.{k}"), v.clone())));
+        entries
+    }
+
+    fn table_key(key: &str) -> Result<(&str, &str), AppError> {
+        match key.split_once('.') {
+            Some((table @ ("users" | "options"), name)) if !name.is_empty() => Ok((table, name)),
+            _ => Err(AppError::validation_error(format!(
+                "unknown config key '{key}'; expected one of {}, users.<PATTERN> or options.<KEYWORD>",
+                KEYS.join(", ")
+            ))),
+        }
+    }
+}
+
+pub(crate) fn load(paths: &SshPaths) -> Result<Settings, AppError> {
+    let path = paths.config_dir().join("config.toml");
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(err) => return Err(AppError::from(err)),
+    };
+
+    toml::from_str(&contents).map_err(|err| {
+        AppError::config_error(format!("invalid config file {}: {err}", path.display()))
+    })
+}
+
+pub(crate) fn save(paths: &SshPaths, settings: &Settings) -> Result<(), AppError> {
+    let dir = paths.config_dir();
+    fs::create_dir_all(dir)?;
+    let contents = toml::to_string_pretty(settings)
+        .map_err(|err| AppError::config_error(format!("failed to encode config: {err}")))?;
+    secure_file::write(&dir.join("config.toml"), contents.as_bytes(), 0o644)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_specific_user_pattern_wins() {
+        let mut settings = Settings::default();
+        settings.set("users.*.example", "ops").unwrap();
+        settings.set("users.*.corp.example", "deploy").unwrap();
+        assert_eq!(settings.user_for("db1.corp.example"), Some("deploy"));
+        assert_eq!(settings.user_for("www.example"), Some("ops"));
+        assert_eq!(settings.user_for("github.com"), None);
+    }
+
+    #[test]
+    fn explicit_options_override_configured_ones() {
+        let mut settings = Settings::default();
+        settings.set("options.ServerAliveInterval", "30").unwrap();
+        settings.set("options.ForwardAgent", "no").unwrap();
+        let merged = settings.merged_options(&[("forwardagent".into(), "yes".into())]);
+        assert_eq!(
+            merged,
+            vec![
+                ("ServerAliveInterval".to_string(), "30".to_string()),
+                ("forwardagent".to_string(), "yes".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_values() {
+        let mut settings = Settings::default();
+        assert!(settings.set("colour", "red").is_err());
+        assert!(settings.set("bits", "many").is_err());
+        assert!(settings.set("format", "xml").is_err());
+        assert!(settings.set("options.Host", "x").is_err());
+        for keyword in ["User", "port", "IDENTITYFILE"] {
+            let err = settings.set(&format!("options.{keyword}"), "x").unwrap_err().to_string();
+            assert!(err.contains("written by ssv itself"), "{err}");
+        }
+    }
+}
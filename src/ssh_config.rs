@@ -0,0 +1,298 @@
+//! Minimal reader and writer for the single `Host` block of a managed config file.
+
+use crate::error::AppError;
+use serde::Serialize;
+
+/// One `Keyword value` line of an SSH client configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Directive {
+    pub key: String,
+    pub value: String,
+}
+
+/// The `Host` block ssv writes into `~/.ssh/conf.d/<HOST>.conf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HostBlock<'a> {
+    pub alias: &'a str,
+    /// Host patterns written on the `Host` line instead of `alias` when non-empty.
+    pub patterns: &'a [String],
+    pub hostname: Option<&'a str>,
+    pub user: Option<&'a str>,
+    pub port: Option<u16>,
+    /// Value written to `IdentityFile`, usually a `~/.ssh/...` path.
+    pub identity_file: &'a str,
+    /// Extra `Keyword value` directives appended after the managed ones.
+    pub options: &'a [(String, String)],
+}
+
+impl HostBlock<'_> {
+    pub(crate) fn render(&self) -> String {
+        let host_line =
+            if self.patterns.is_empty() { self.alias.to_string() } else { self.patterns.join(" ") };
+        let mut contents = format!("Host {host_line}\n");
+        if let Some(hostname) = self.hostname {
+            contents.push_str(&format!("HostName {hostname}\n"));
+        }
+        if let Some(user) = self.user {
+            contents.push_str(&format!("User {}\n", user));
+        }
+        if let Some(port) = self.port {
+            contents.push_str(&format!("Port {}\n", port));
+        }
+        contents.push_str(&format!("IdentityFile {}\n", self.identity_file));
+        contents.push_str("IdentitiesOnly yes\n");
+        for (key, value) in self.options {
+            contents.push_str(&format!("{key} {value}\n"));
+        }
+        contents
+    }
+}
+
+/// Reject directives that would break the single-block layout ssv relies on.
+pub(crate) fn validate_option(key: &str, value: &str) -> Result<(), AppError> {
+    if !key.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AppError::validation_error(format!("invalid option keyword '{key}'")));
+    }
+    if ["host", "match", "include"].iter().any(|reserved| key.eq_ignore_ascii_case(reserved)) {
+        return Err(AppError::validation_error(format!(
+            "option '{key}' cannot be set on a managed host block"
+        )));
+    }
+    if value.contains(['\n', '\r']) {
+        return Err(AppError::validation_error(format!("value for '{key}' must be a single line")));
+    }
+    Ok(())
+}
+
+/// Keywords [`HostBlock::render`] writes from its own fields, which extra options must not repeat.
+pub(crate) const OWN_KEYWORDS: [&str; 3] = ["user", "port", "identityfile"];
+
+/// [`validate_option`] for an extra directive appended to a managed block, which must also leave
+/// the [`OWN_KEYWORDS`] to ssv.
+pub(crate) fn validate_extra_option(key: &str, value: &str) -> Result<(), AppError> {
+    validate_option(key, value)?;
+    if OWN_KEYWORDS.iter().any(|own| key.eq_ignore_ascii_case(own)) {
+        return Err(AppError::validation_error(format!(
+            "option '{key}' is written by ssv itself; use its dedicated setting instead"
+        )));
+    }
+    Ok(())
+}
+
+/// Reject a login name that would split or inject into the `User` line.
+pub(crate) fn validate_user(user: &str) -> Result<(), AppError> {
+    if user.is_empty() || user.contains(|c: char| c.is_whitespace() || c.is_control()) {
+        return Err(AppError::validation_error(format!(
+            "invalid user '{}'; it must not be empty or contain spaces",
+            user.escape_default()
+        )));
+    }
+    Ok(())
+}
+
+/// Parse directives in file order, skipping comments and blank lines.
+///
+/// Both `Keyword value` and `Keyword=value` forms are accepted.
+pub(crate) fn parse(contents: &str) -> Vec<Directive> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let split = line.find(|c: char| c.is_whitespace() || c == '=')?;
+            let (key, rest) = line.split_at(split);
+            let value = rest.trim_start().strip_prefix('=').unwrap_or(rest).trim();
+            Some(Directive { key: key.to_string(), value: value.to_string() })
+        })
+        .collect()
+}
+
+/// First value of a directive, compared case-insensitively as ssh does.
+pub(crate) fn value<'a>(directives: &'a [Directive], key: &str) -> Option<&'a str> {
+    directives.iter().find(|d| d.key.eq_ignore_ascii_case(key)).map(|d| d.value.as_str())
+}
+
+/// ssh_config-style wildcard match: `*` spans any run of characters and `?` exactly one.
+pub(crate) fn pattern_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c.eq_ignore_ascii_case(&name[n]) => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Check a `Host` pattern: ssh wildcards `*` and `?`, an optional leading `!`, and otherwise the
+/// characters allowed in host names. At least one pattern of a list must be non-negated.
+pub(crate) fn validate_patterns(patterns: &[String]) -> Result<(), AppError> {
+    for pattern in patterns {
+        let body = pattern.strip_prefix('!').unwrap_or(pattern);
+        let valid = !body.is_empty()
+            && body.chars().all(|c| {
+                c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '*' | '?' | ':')
+            });
+        if !valid {
+            return Err(AppError::validation_error(format!(
+                "invalid host pattern '{pattern}'; use host characters, '*', '?' and a leading '!'"
+            )));
+        }
+    }
+    if !patterns.iter().any(|pattern| !pattern.starts_with('!')) {
+        return Err(AppError::validation_error("at least one host pattern must not be negated"));
+    }
+    Ok(())
+}
+
+/// Filesystem-safe name for a pattern entry, e.g. `*.internal.example.com` becomes
+/// `wildcard-internal.example.com`.
+pub(crate) fn pattern_slug(patterns: &[String]) -> String {
+    let parts: Vec<String> = patterns
+        .iter()
+        .map(|pattern| {
+            let negated = pattern.starts_with('!');
+            let cleaned: String = pattern
+                .trim_start_matches('!')
+                .chars()
+                .map(|c| if matches!(c, '*' | '?' | ':') { '_' } else { c.to_ascii_lowercase() })
+                .collect();
+            let cleaned = cleaned.trim_matches(|c| matches!(c, '.' | '-' | '_'));
+            if negated { format!("not-{cleaned}") } else { cleaned.to_string() }
+        })
+        .filter(|part| !part.is_empty() && part != "not-")
+        .collect();
+    if parts.is_empty() { "wildcard".to_string() } else { format!("wildcard-{}", parts.join("_")) }
+}
+
+/// Host names of every `ProxyJump` hop, with `user@`, `ssh://` and `:port` stripped.
+pub(crate) fn jump_hosts(directives: &[Directive]) -> Vec<String> {
+    let Some(chain) = value(directives, "ProxyJump").filter(|v| !v.eq_ignore_ascii_case("none"))
+    else {
+        return Vec::new();
+    };
+
+    chain
+        .split(',')
+        .map(|hop| {
+            let hop = hop.trim();
+            let hop = hop.strip_prefix("ssh://").unwrap_or(hop);
+            let hop = hop.rsplit_once('@').map_or(hop, |(_, host)| host);
+            match hop.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+                Some((host, _)) => host.to_string(),
+                None => hop.split(':').next().unwrap_or(hop).to_string(),
+            }
+        })
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_ssh_style_wildcards() {
+        assert!(pattern_matches("*.corp.example", "db1.corp.example"));
+        assert!(pattern_matches("db?.corp.example", "db1.corp.example"));
+        assert!(!pattern_matches("*.corp.example", "corp.example"));
+        assert!(pattern_matches("*", "anything"));
+    }
+
+    #[test]
+    fn renders_pattern_block_without_hostname() {
+        let patterns = vec!["*.internal.example.com".to_string(), "!bastion.internal".to_string()];
+        let block = HostBlock {
+            alias: "wildcard-internal.example.com",
+            patterns: &patterns,
+            hostname: None,
+            user: None,
+            port: None,
+            identity_file: "~/.ssh/id_ed25519_wildcard-internal.example.com",
+            options: &[],
+        };
+        assert!(block.render().starts_with(
+            "Host *.internal.example.com !bastion.internal\nIdentityFile ~/.ssh/id_ed25519_"
+        ));
+    }
+
+    #[test]
+    fn slugs_and_validates_patterns() {
+        let patterns = vec!["*.internal.example.com".to_string(), "!db?.internal".to_string()];
+        assert!(validate_patterns(&patterns).is_ok());
+        assert_eq!(pattern_slug(&patterns), "wildcard-internal.example.com_not-db_.internal");
+        assert!(validate_patterns(&["!only.negated".to_string()]).is_err());
+        assert!(validate_patterns(&["bad/pattern".to_string()]).is_err());
+    }
+
+    #[test]
+    fn extracts_jump_hosts_from_proxyjump_chain() {
+        let directives =
+            parse("Host db\nProxyJump ops@edge:2222,ssh://bastion.corp,[fd00::1]:22\n");
+        assert_eq!(jump_hosts(&directives), ["edge", "bastion.corp", "fd00::1"]);
+        assert!(jump_hosts(&parse("ProxyJump none\n")).is_empty());
+    }
+
+    #[test]
+    fn renders_managed_block_with_extra_options() {
+        let options = vec![("ForwardAgent".to_string(), "no".to_string())];
+        let block = HostBlock {
+            alias: "work",
+            patterns: &[],
+            hostname: Some("github.com"),
+            user: Some("git"),
+            port: None,
+            identity_file: "~/.ssh/id_ed25519_work",
+            options: &options,
+        };
+        assert_eq!(
+            block.render(),
+            "Host work\nHostName github.com\nUser git\nIdentityFile ~/.ssh/id_ed25519_work\n\
+             IdentitiesOnly yes\nForwardAgent no\n"
+        );
+    }
+
+    #[test]
+    fn option_validation_rejects_structural_keywords() {
+        assert!(validate_option("ForwardAgent", "no").is_ok());
+        assert!(validate_option("Host", "evil").is_err());
+        assert!(validate_option("Proxy Command", "nc").is_err());
+        assert!(validate_option("User", "git\nHost *").is_err());
+    }
+
+    #[test]
+    fn user_validation_rejects_whitespace_and_control_characters() {
+        assert!(validate_user("git").is_ok());
+        assert!(validate_user("first.last@corp").is_ok());
+        for invalid in ["", "git user", "git\nProxyCommand touch /tmp/pwned", "git\r", "git\u{7}"] {
+            assert!(validate_user(invalid).is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn parses_both_separator_styles() {
+        let directives = parse("# managed\nHost github.com\n  HostName=github.com\nPort  22\n");
+        assert_eq!(directives.len(), 3);
+        assert_eq!(value(&directives, "hostname"), Some("github.com"));
+        assert_eq!(value(&directives, "Port"), Some("22"));
+        assert_eq!(value(&directives, "User"), None);
+    }
+}
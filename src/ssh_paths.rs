@@ -5,17 +5,94 @@ use std::path::{Path, PathBuf};
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
-/// Helper struct for resolving and preparing SSH asset paths.
-pub(crate) struct SshPaths {
+/// Where ssv keeps its files and which programs it runs.
+///
+/// Everything is derived from a home directory: keys and configs live in `<home>/.ssh`, settings
+/// and templates in `<home>/.config/ssv`. The `with_*` methods override individual locations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshPaths {
     home: PathBuf,
+    config_dir: PathBuf,
+    ssh_keygen: PathBuf,
+    ssh: PathBuf,
+    ssh_add: PathBuf,
+    git: PathBuf,
 }
 
 impl SshPaths {
-    /// Resolve the paths using the `HOME` environment variable.
-    pub(crate) fn from_env() -> Result<Self, AppError> {
+    /// Paths under `home`, running `ssh-keygen`, `ssh`, `ssh-add` and `git` from `PATH`.
+    pub fn new(home: impl Into<PathBuf>) -> Self {
+        let home = home.into();
+        Self {
+            config_dir: home.join(".config").join("ssv"),
+            home,
+            ssh_keygen: PathBuf::from("ssh-keygen"),
+            ssh: PathBuf::from("ssh"),
+            ssh_add: PathBuf::from("ssh-add"),
+            git: PathBuf::from("git"),
+        }
+    }
+
+    /// Resolve the paths from `HOME`, `XDG_CONFIG_HOME` (ignored when relative) and the
+    /// `SSV_SSH_KEYGEN_PATH`, `SSV_SSH_PATH`, `SSV_SSH_ADD_PATH` and `SSV_GIT_PATH` program
+    /// overrides.
+    pub fn from_env() -> Result<Self, AppError> {
         let home = std::env::var_os("HOME")
             .ok_or_else(|| AppError::config_error("HOME environment variable not set"))?;
-        Ok(Self { home: PathBuf::from(home) })
+        let mut paths = Self::new(home);
+        if let Some(dir) =
+            std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).filter(|dir| dir.is_absolute())
+        {
+            paths.config_dir = dir.join("ssv");
+        }
+        let program = |var| std::env::var_os(var).map(PathBuf::from);
+        paths.ssh_keygen = program("SSV_SSH_KEYGEN_PATH").unwrap_or(paths.ssh_keygen);
+        paths.ssh = program("SSV_SSH_PATH").unwrap_or(paths.ssh);
+        paths.ssh_add = program("SSV_SSH_ADD_PATH").unwrap_or(paths.ssh_add);
+        paths.git = program("SSV_GIT_PATH").unwrap_or(paths.git);
+        Ok(paths)
+    }
+
+    /// Keep ssv's settings and templates in `dir` instead of `<home>/.config/ssv`.
+    pub fn with_config_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config_dir = dir.into();
+        self
+    }
+
+    pub fn with_ssh_keygen(mut self, program: impl Into<PathBuf>) -> Self {
+        self.ssh_keygen = program.into();
+        self
+    }
+
+    pub fn with_ssh(mut self, program: impl Into<PathBuf>) -> Self {
+        self.ssh = program.into();
+        self
+    }
+
+    pub fn with_ssh_add(mut self, program: impl Into<PathBuf>) -> Self {
+        self.ssh_add = program.into();
+        self
+    }
+
+    pub fn with_git(mut self, program: impl Into<PathBuf>) -> Self {
+        self.git = program.into();
+        self
+    }
+
+    pub fn ssh_keygen(&self) -> &Path {
+        &self.ssh_keygen
+    }
+
+    pub fn ssh(&self) -> &Path {
+        &self.ssh
+    }
+
+    pub fn ssh_add(&self) -> &Path {
+        &self.ssh_add
+    }
+
+    pub fn git(&self) -> &Path {
+        &self.git
     }
 
     /// Ensure the ~/.ssh and ~/.ssh/conf.d directories exist with secure permissions.
@@ -25,23 +102,71 @@ impl SshPaths {
         Ok(())
     }
 
-    pub(crate) fn ssh_root(&self) -> PathBuf {
+    pub fn ssh_root(&self) -> PathBuf {
         self.home.join(".ssh")
     }
 
-    pub(crate) fn conf_dir(&self) -> PathBuf {
+    pub fn conf_dir(&self) -> PathBuf {
         self.ssh_root().join("conf.d")
     }
 
-    pub(crate) fn home(&self) -> &Path {
+    pub fn known_hosts_path(&self) -> PathBuf {
+        self.ssh_root().join("known_hosts")
+    }
+
+    /// `authorized_keys` file sshd consults for logins to this account.
+    pub fn authorized_keys_path(&self) -> PathBuf {
+        self.ssh_root().join("authorized_keys")
+    }
+
+    /// `allowed_signers` file git consults to verify SSH commit signatures.
+    pub fn allowed_signers_path(&self) -> PathBuf {
+        self.ssh_root().join("allowed_signers")
+    }
+
+    /// Directory holding ssv's own bookkeeping (metadata sidecars, logs).
+    pub fn state_dir(&self) -> PathBuf {
+        self.ssh_root().join(".ssv")
+    }
+
+    /// Encrypted file holding the private keys when the `storage` setting is `vault`.
+    pub fn vault_path(&self) -> PathBuf {
+        self.state_dir().join("vault")
+    }
+
+    /// Append-only JSON Lines log of every change ssv made.
+    pub fn history_path(&self) -> PathBuf {
+        self.state_dir().join("log.jsonl")
+    }
+
+    /// Checksums of the host configs ssv wrote, for telling them from foreign or edited ones.
+    pub fn managed_state_path(&self) -> PathBuf {
+        self.state_dir().join("managed.json")
+    }
+
+    /// Directory holding removed hosts until `ssv restore` or the `trash_retention` setting.
+    pub fn trash_dir(&self) -> PathBuf {
+        self.state_dir().join("trash")
+    }
+
+    pub fn metadata_path(&self, host: &str) -> PathBuf {
+        self.state_dir().join("meta").join(format!("{host}.json"))
+    }
+
+    /// ssv's own configuration directory, holding `config.toml` and user templates.
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+
+    pub fn home(&self) -> &Path {
         &self.home
     }
 
-    pub(crate) fn host_config_path(&self, host: &str) -> PathBuf {
+    pub fn host_config_path(&self, host: &str) -> PathBuf {
         self.conf_dir().join(format!("{host}.conf"))
     }
 
-    pub(crate) fn key_paths(&self, key_type: &str, host: &str) -> (PathBuf, PathBuf) {
+    pub fn key_paths(&self, key_type: &str, host: &str) -> (PathBuf, PathBuf) {
         let filename = format!("id_{}_{}", key_type, host);
         let private = self.ssh_root().join(&filename);
         let public = self.ssh_root().join(format!("{filename}.pub"));
@@ -83,7 +208,7 @@ impl SshPaths {
         Ok(())
     }
 
-    fn ensure_dir_with_mode(&self, path: &Path) -> Result<(), AppError> {
+    pub(crate) fn ensure_dir_with_mode(&self, path: &Path) -> Result<(), AppError> {
         if !path.exists() {
             fs::create_dir_all(path)?;
         }
@@ -112,7 +237,7 @@ mod tests {
     use super::*;
 
     fn paths() -> SshPaths {
-        SshPaths { home: PathBuf::from(".") }
+        SshPaths::new(".")
     }
 
     #[test]
@@ -0,0 +1,182 @@
+//! Named host shapes applied by `ssv generate --template`.
+//!
+//! A template is a list of ssh_config directives with `{alias}`, `{hostname}` and `{user}`
+//! placeholders. User templates live in `<config dir>/templates/<NAME>.conf` and take precedence
+//! over the built-in ones; a leading `#` comment line serves as the description.
+
+use crate::GenerateOptions;
+use crate::error::AppError;
+use crate::ssh_paths::SshPaths;
+use crate::{ssh_config, whoami};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+
+const BUILTIN: &[(&str, &str)] = &[
+    ("github", "# GitHub account over SSH\nHostName github.com\nUser git\n"),
+    ("gitlab", "# GitLab.com account over SSH\nHostName gitlab.com\nUser git\n"),
+    (
+        "ec2",
+        "# AWS EC2 instance with the Amazon Linux default user\nUser ec2-user\n\
+         StrictHostKeyChecking accept-new\nServerAliveInterval 60\n",
+    ),
+    (
+        "bastion",
+        "# Internal box reached through the host alias 'bastion'\nProxyJump bastion\n\
+         ServerAliveInterval 30\n",
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Template {
+    pub name: String,
+    pub description: Option<String>,
+    /// Whether the template ships with ssv rather than coming from the config directory.
+    pub builtin: bool,
+    pub body: String,
+}
+
+impl Template {
+    fn new(name: &str, body: String, builtin: bool) -> Self {
+        let description = body
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix('#'))
+            .map(|line| line.trim().to_string());
+        Self { name: name.to_string(), description, builtin, body }
+    }
+
+    /// Fill in generate options the caller left unset. Explicit values always win; `HostName`,
+    /// `User` and `Port` from the template only apply when the matching option is `None`.
+    pub fn apply(&self, options: &mut GenerateOptions) -> Result<(), AppError> {
+        let hostname = options.hostname.clone().unwrap_or_else(|| options.host.clone());
+        let user = options.user.clone().unwrap_or_else(whoami::local_user);
+        let body = self
+            .body
+            .replace("{alias}", &options.host)
+            .replace("{hostname}", &hostname)
+            .replace("{user}", &user);
+
+        for directive in ssh_config::parse(&body) {
+            let (key, value) = (directive.key, directive.value);
+            match key.to_ascii_lowercase().as_str() {
+                "hostname" => {
+                    options.hostname.get_or_insert(value);
+                }
+                "user" => {
+                    options.user.get_or_insert(value);
+                }
+                "port" => {
+                    let port = value.parse().map_err(|_| {
+                        AppError::validation_error(format!(
+                            "template '{}' has an invalid Port '{value}'",
+                            self.name
+                        ))
+                    })?;
+                    options.port.get_or_insert(port);
+                }
+                "identityfile" | "identitiesonly" => {
+                    return Err(AppError::validation_error(format!(
+                        "template '{}' must not set {key}; ssv manages key files itself",
+                        self.name
+                    )));
+                }
+                _ => {
+                    ssh_config::validate_option(&key, &value)?;
+                    if !options.options.iter().any(|(k, _)| k.eq_ignore_ascii_case(&key)) {
+                        options.options.push((key, value));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Look up a template by name, preferring the user's file over a built-in.
+pub(crate) fn load(paths: &SshPaths, name: &str) -> Result<Template, AppError> {
+    validate_name(name)?;
+    let path = paths.config_dir().join("templates").join(format!("{name}.conf"));
+    match fs::read_to_string(&path) {
+        Ok(body) => return Ok(Template::new(name, body, false)),
+        Err(err) if err.kind() != ErrorKind::NotFound => return Err(AppError::from(err)),
+        Err(_) => {}
+    }
+
+    BUILTIN
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(name, body)| Template::new(name, body.to_string(), true))
+        .ok_or_else(|| AppError::validation_error(format!("unknown template '{name}'")))
+}
+
+/// Built-in and user templates sorted by name, user files shadowing built-ins.
+pub(crate) fn list(paths: &SshPaths) -> Result<Vec<Template>, AppError> {
+    let mut templates: BTreeMap<String, Template> = BUILTIN
+        .iter()
+        .map(|(name, body)| (name.to_string(), Template::new(name, body.to_string(), true)))
+        .collect();
+
+    let entries = match fs::read_dir(paths.config_dir().join("templates")) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Ok(templates.into_values().collect());
+        }
+        Err(err) => return Err(AppError::from(err)),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        let Some(name) =
+            path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".conf"))
+        else {
+            continue;
+        };
+        if validate_name(name).is_ok() {
+            templates
+                .insert(name.to_string(), Template::new(name, fs::read_to_string(&path)?, false));
+        }
+    }
+    Ok(templates.into_values().collect())
+}
+
+fn validate_name(name: &str) -> Result<(), AppError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        return Err(AppError::validation_error(format!(
+            "invalid template name '{name}'; allowed characters are alphanumeric, '-', '_'"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_unset_fields_and_keeps_explicit_ones() {
+        let template = Template::new(
+            "corp",
+            "# Corp box\nHostName {alias}.corp.example\nUser ops\nPort 2222\nProxyJump {user}@jump\n"
+                .to_string(),
+            false,
+        );
+        let mut options = GenerateOptions::new("db1");
+        options.user = Some("deploy".to_string());
+        options.options.push(("proxyjump".to_string(), "other".to_string()));
+        template.apply(&mut options).unwrap();
+
+        assert_eq!(template.description.as_deref(), Some("Corp box"));
+        assert_eq!(options.hostname.as_deref(), Some("db1.corp.example"));
+        assert_eq!(options.user.as_deref(), Some("deploy"));
+        assert_eq!(options.port, Some(2222));
+        assert_eq!(options.options, vec![("proxyjump".to_string(), "other".to_string())]);
+    }
+
+    #[test]
+    fn rejects_identity_directives() {
+        let template = Template::new("bad", "IdentityFile ~/.ssh/other\n".to_string(), false);
+        assert!(template.apply(&mut GenerateOptions::new("db1")).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::error::AppError;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Current UTC time formatted as RFC 3339 (e.g., `2024-05-01T12:30:00Z`).
+pub(crate) fn now_rfc3339() -> String {
+    format_rfc3339(SystemTime::now())
+}
+
+/// Current UTC date formatted as `YYYY-MM-DD`.
+pub(crate) fn today() -> String {
+    format_date(SystemTime::now())
+}
+
+pub(crate) fn format_rfc3339(time: SystemTime) -> String {
+    let secs = unix_seconds(time);
+    let (year, month, day) = civil_from_days((secs / SECONDS_PER_DAY) as i64);
+    let rem = secs % SECONDS_PER_DAY;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+pub(crate) fn format_date(time: SystemTime) -> String {
+    let (year, month, day) = civil_from_days((unix_seconds(time) / SECONDS_PER_DAY) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Parse a `YYYY-MM-DD` date or an RFC 3339 UTC timestamp produced by [`now_rfc3339`].
+pub(crate) fn parse(value: &str) -> Result<SystemTime, AppError> {
+    let invalid = || {
+        AppError::validation_error(format!(
+            "invalid date '{value}'; expected YYYY-MM-DD or YYYY-MM-DDTHH:MM:SSZ"
+        ))
+    };
+
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time.strip_suffix('Z').ok_or_else(invalid)?)),
+        None => (value, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let mut next = |len: usize| -> Result<u32, AppError> {
+        let part = parts.next().filter(|p| p.len() == len).ok_or_else(invalid)?;
+        part.parse().map_err(|_| invalid())
+    };
+    let (year, month, day) = (next(4)?, next(2)?, next(2)?);
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year as i64, month)).contains(&day) {
+        return Err(invalid());
+    }
+
+    let mut secs = days_from_civil(year as i64, month, day) * SECONDS_PER_DAY as i64;
+    if let Some(time) = time {
+        let fields: Vec<&str> = time.split(':').collect();
+        if fields.len() != 3 || fields.iter().any(|f| f.len() != 2) {
+            return Err(invalid());
+        }
+        let values: Vec<u32> =
+            fields.iter().map(|f| f.parse().map_err(|_| invalid())).collect::<Result<_, _>>()?;
+        if values[0] > 23 || values[1] > 59 || values[2] > 59 {
+            return Err(invalid());
+        }
+        secs += (values[0] * 3600 + values[1] * 60 + values[2]) as i64;
+    }
+
+    let secs = u64::try_from(secs).map_err(|_| invalid())?;
+    Ok(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Whole days elapsed between `earlier` and now; zero when `earlier` lies in the future.
+pub(crate) fn days_since(earlier: SystemTime) -> u64 {
+    SystemTime::now().duration_since(earlier).map(|d| d.as_secs() / SECONDS_PER_DAY).unwrap_or(0)
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Conversions between day counts and proleptic Gregorian dates, after Howard Hinnant's
+// `days_from_civil`/`civil_from_days` algorithms.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_parses_round_trip() {
+        let time = parse("2024-02-29T13:05:09Z").expect("valid timestamp");
+        assert_eq!(format_rfc3339(time), "2024-02-29T13:05:09Z");
+        assert_eq!(format_date(time), "2024-02-29");
+    }
+
+    #[test]
+    fn parse_rejects_malformed_dates() {
+        assert!(parse("2023-02-29").is_err());
+        assert!(parse("2024-13-01").is_err());
+        assert!(parse("24-01-01").is_err());
+        assert!(parse("2024-01-01T10:00:00").is_err());
+    }
+}
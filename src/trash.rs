@@ -0,0 +1,212 @@
+//! Removed hosts, kept in `~/.ssh/.ssv/trash` for the `trash_retention` setting so
+//! `ssv restore` can bring them back.
+//!
+//! Each removal gets its own directory holding the removed files, numbered in the order of
+//! `entry.json`, which records where they came from. Private keys held in an external key store
+//! stay in that store under a name prefixed with the entry id.
+
+use crate::error::AppError;
+use crate::key_store::{self, KeyStore};
+use crate::ownership::{self, Tracked};
+use crate::ssh_paths::SshPaths;
+use crate::{secure_file, settings, timestamp};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Days a removed host stays restorable when `trash_retention` is not set.
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// One removed host waiting in the trash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    /// Directory name underneath the trash, `<timestamp>-<host>`.
+    pub id: String,
+    pub host: String,
+    /// RFC 3339 timestamp of the removal.
+    pub removed_at: String,
+    /// Original paths of the removed files.
+    pub files: Vec<PathBuf>,
+    /// `IdentityFile`s whose private keys were held in the key store.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stored: Vec<PathBuf>,
+    /// What the ownership state recorded for the config, so a restored config is still ssv's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tracked: Option<Tracked>,
+}
+
+/// Move the `files` and `stored` keys of a removed `host` into the trash. Returns `None`, leaving
+/// everything in place, when `trash_retention` is 0; the caller deletes them then. An empty
+/// removal is refused rather than kept as an entry with nothing to restore.
+pub(crate) fn put(
+    paths: &SshPaths,
+    store: &dyn KeyStore,
+    host: &str,
+    files: &[PathBuf],
+    stored: &[PathBuf],
+) -> Result<Option<TrashEntry>, AppError> {
+    if files.is_empty() && stored.is_empty() {
+        return Err(AppError::HostNotFound(host.to_string()));
+    }
+    let retention = retention(paths)?;
+    purge(paths, store, retention)?;
+    if retention == 0 {
+        return Ok(None);
+    }
+
+    let removed_at = timestamp::now_rfc3339();
+    let stamp: String = removed_at.chars().filter(char::is_ascii_alphanumeric).collect();
+    let mut id = format!("{stamp}-{host}");
+    let mut suffix = 1;
+    while entry_dir(paths, &id).exists() {
+        suffix += 1;
+        id = format!("{stamp}-{host}-{suffix}");
+    }
+    let dir = entry_dir(paths, &id);
+    paths.ensure_dir_with_mode(&paths.trash_dir())?;
+    paths.ensure_dir_with_mode(&dir)?;
+
+    let tracked = ownership::load(paths)?.configs.remove(host);
+    let entry = TrashEntry {
+        id,
+        host: host.to_string(),
+        removed_at,
+        files: files.to_vec(),
+        stored: stored.to_vec(),
+        tracked,
+    };
+    save(paths, &entry)?;
+    for (index, file) in files.iter().enumerate() {
+        fs::rename(file, dir.join(index.to_string()))?;
+    }
+    for key in stored {
+        if let Some(contents) = key_store::stored(store, key)? {
+            store.put(&stored_name(&entry.id, key)?, &contents)?;
+            key_store::forget(store, key)?;
+        }
+    }
+    Ok(Some(entry))
+}
+
+/// Every entry within `trash_retention`, oldest first, optionally only those for `host`. Expired
+/// entries stay on disk until the next [`put`] or [`restore`] purges them, but are never listed.
+pub(crate) fn list(paths: &SshPaths, host: Option<&str>) -> Result<Vec<TrashEntry>, AppError> {
+    let retention = retention(paths)?;
+    let mut current = Vec::new();
+    for entry in entries(paths, host)? {
+        if !expired(&entry, retention)? {
+            current.push(entry);
+        }
+    }
+    Ok(current)
+}
+
+fn entries(paths: &SshPaths, host: Option<&str>) -> Result<Vec<TrashEntry>, AppError> {
+    let dir = paths.trash_dir();
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut trashed = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path().join("entry.json");
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let entry: TrashEntry = serde_json::from_str(&contents).map_err(|err| {
+            AppError::config_error(format!("invalid trash entry {}: {err}", path.display()))
+        })?;
+        if host.is_none_or(|host| entry.host == host) {
+            trashed.push(entry);
+        }
+    }
+    trashed.sort_by(|a, b| (&a.removed_at, &a.id).cmp(&(&b.removed_at, &b.id)));
+    Ok(trashed)
+}
+
+/// Put the most recently removed `host` back where it was and drop it from the trash. Expired
+/// entries are purged first, so an entry past `trash_retention` is never brought back.
+pub(crate) fn restore(
+    paths: &SshPaths,
+    store: &dyn KeyStore,
+    host: &str,
+) -> Result<TrashEntry, AppError> {
+    purge(paths, store, retention(paths)?)?;
+    let entry = entries(paths, Some(host))?
+        .pop()
+        .ok_or_else(|| AppError::validation_error(format!("the trash holds no '{host}'")))?;
+    if let Some(existing) = entry.files.iter().find(|file| file.exists()) {
+        return Err(AppError::validation_error(format!(
+            "cannot restore '{host}': {} exists; remove it first",
+            existing.display()
+        )));
+    }
+
+    let dir = entry_dir(paths, &entry.id);
+    for (index, file) in entry.files.iter().enumerate() {
+        if let Some(parent) = file.parent() {
+            paths.ensure_dir_with_mode(parent)?;
+        }
+        fs::rename(dir.join(index.to_string()), file)?;
+    }
+    for key in &entry.stored {
+        let name = stored_name(&entry.id, key)?;
+        if let Some(contents) = store.get(&name)? {
+            let staged = dir.join("key");
+            secure_file::write(&staged, &contents, 0o600)?;
+            key_store::install(store, &staged, key)?;
+            store.delete(&name)?;
+        }
+    }
+    if let Some(tracked) = &entry.tracked {
+        ownership::track(paths, host, tracked.clone())?;
+    }
+    fs::remove_dir_all(&dir)?;
+    Ok(entry)
+}
+
+/// Delete entries older than `retention` days, all of them when it is 0.
+fn purge(paths: &SshPaths, store: &dyn KeyStore, retention: u32) -> Result<(), AppError> {
+    for entry in entries(paths, None)? {
+        if !expired(&entry, retention)? {
+            continue;
+        }
+        for key in &entry.stored {
+            store.delete(&stored_name(&entry.id, key)?)?;
+        }
+        fs::remove_dir_all(entry_dir(paths, &entry.id))?;
+    }
+    Ok(())
+}
+
+fn expired(entry: &TrashEntry, retention: u32) -> Result<bool, AppError> {
+    let removed = timestamp::parse(&entry.removed_at)?;
+    Ok(timestamp::days_since(removed) >= u64::from(retention))
+}
+
+fn retention(paths: &SshPaths) -> Result<u32, AppError> {
+    Ok(settings::load(paths)?.trash_retention.unwrap_or(DEFAULT_RETENTION_DAYS))
+}
+
+fn entry_dir(paths: &SshPaths, id: &str) -> PathBuf {
+    paths.trash_dir().join(id)
+}
+
+/// Name of a trashed private key in the key store, e.g. `trash-<id>-id_ed25519_db`.
+fn stored_name(id: &str, key: &Path) -> Result<String, AppError> {
+    let name = key
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| AppError::validation_error(format!("invalid key path {}", key.display())))?;
+    Ok(format!("trash-{id}-{name}"))
+}
+
+fn save(paths: &SshPaths, entry: &TrashEntry) -> Result<(), AppError> {
+    let mut contents = serde_json::to_string_pretty(entry)
+        .map_err(|err| AppError::config_error(format!("failed to encode trash entry: {err}")))?;
+    contents.push('\n');
+    secure_file::write(&entry_dir(paths, &entry.id).join("entry.json"), contents.as_bytes(), 0o600)
+}
@@ -0,0 +1,122 @@
+//! `ssv ui`: an interactive terminal front end over the library API.
+
+mod render;
+mod state;
+
+use base64::Engine;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ssv::error::AppError;
+use state::{App, Effect};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::process::Command;
+use std::time::Duration;
+
+pub(crate) fn run() -> Result<(), AppError> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(AppError::ValidationError("ssv ui needs an interactive terminal".into()));
+    }
+    let mut app = App::new(ssv::list()?);
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<(), AppError> {
+    let mut shown: Option<String> = None;
+    while !app.quit {
+        let selected = app.selected_host().map(str::to_string);
+        if selected != shown {
+            app.details = selected.as_deref().and_then(|host| ssv::show(host).ok());
+            shown = selected;
+        }
+        terminal.draw(|frame| render::draw(frame, app))?;
+
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        app.status = None;
+        let Some(effect) = app.handle_key(key) else { continue };
+
+        app.status = Some("Working…".to_string());
+        terminal.draw(|frame| render::draw(frame, app))?;
+        let outcome = perform(terminal, &effect).map_err(|err| err.to_string());
+        app.report(outcome);
+        app.set_hosts(ssv::list()?);
+        shown = None;
+    }
+    Ok(())
+}
+
+/// Carry out an effect, returning the message for the status line.
+fn perform(terminal: &mut DefaultTerminal, effect: &Effect) -> Result<String, AppError> {
+    match effect {
+        Effect::Generate(options) => {
+            ssv::generate_with(options)?;
+            Ok(format!("Generated SSH assets for '{}'", options.host))
+        }
+        Effect::Edit(host) => {
+            let path = ssv::show(host)?.config_path;
+            let before = fs::read(&path)?;
+            let editor = std::env::var("VISUAL")
+                .or_else(|_| std::env::var("EDITOR"))
+                .unwrap_or_else(|_| "vi".to_string());
+            ratatui::restore();
+            let status = Command::new("sh")
+                .arg("-c")
+                .arg(format!("{editor} \"$1\""))
+                .arg("sh")
+                .arg(&path)
+                .status();
+            *terminal = ratatui::init();
+            terminal.clear()?;
+            let status = status?;
+            if !status.success() {
+                return Err(AppError::CommandFailed { program: editor, status });
+            }
+            if fs::read(&path)? == before {
+                return Ok(format!("No changes to {}", path.display()));
+            }
+            ssv::record_edit(host)?;
+            Ok(format!("Edited {}", path.display()))
+        }
+        Effect::Rotate(host) => {
+            let report = ssv::rotate(host)?;
+            let mut message = format!("Rotated the key of '{host}'");
+            if report.redeployed {
+                message.push_str(" and redeployed it");
+            }
+            if !report.stale_registrations.is_empty() {
+                message.push_str("; provider registrations still hold the old key");
+            }
+            Ok(message)
+        }
+        Effect::Remove(host) => {
+            let report = ssv::remove(host)?;
+            let mut message = format!("Removed SSH assets for '{host}'");
+            if !report.registrations_left.is_empty() {
+                message.push_str("; provider keys are still registered");
+            }
+            Ok(message)
+        }
+        Effect::CopyPublicKey(host) => {
+            let key = ssv::show(host)?.public_key.ok_or_else(|| {
+                AppError::ValidationError(format!("host '{host}' has no public key"))
+            })?;
+            // OSC 52 asks the terminal emulator to set the clipboard, which also works over ssh.
+            let encoded = base64::engine::general_purpose::STANDARD.encode(key.trim());
+            let mut stdout = io::stdout();
+            write!(stdout, "\x1b]52;c;{encoded}\x07")?;
+            stdout.flush()?;
+            Ok(format!("Copied the public key of '{host}' to the clipboard"))
+        }
+        Effect::Test(host) => {
+            let report = ssv::test_connection(host, Duration::from_secs(10))?;
+            let verdict = if report.passed { "passed" } else { "failed" };
+            Ok(format!("Connection test {verdict} for '{host}': {}", report.reason))
+        }
+    }
+}
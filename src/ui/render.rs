@@ -0,0 +1,162 @@
+//! Drawing of [`App`] state with ratatui widgets.
+
+use super::state::{App, GenerateForm, Mode};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Clear, List, ListState, Paragraph, Wrap};
+use ssv::HostDetails;
+
+const HELP: &str = "↑/↓ select  g generate  e edit  r rotate  d remove  c copy key  t test  q quit";
+
+pub(crate) fn draw(frame: &mut Frame, app: &App) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    let [list_area, detail_area] =
+        Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)]).areas(main);
+
+    let list = List::new(app.hosts.iter().map(String::as_str))
+        .block(Block::bordered().title(format!(" Hosts ({}) ", app.hosts.len())))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected(app.selected_host().map(|_| app.selected));
+    frame.render_stateful_widget(list, list_area, &mut state);
+
+    let details = match &app.details {
+        Some(details) => detail_lines(details),
+        None if app.hosts.is_empty() => {
+            vec![Line::from("No managed hosts; press g to generate one.")]
+        }
+        None => Vec::new(),
+    };
+    frame.render_widget(
+        Paragraph::new(details)
+            .block(Block::bordered().title(" Details "))
+            .wrap(Wrap { trim: false }),
+        detail_area,
+    );
+
+    let footer_text = app.status.as_deref().unwrap_or(HELP);
+    frame.render_widget(Paragraph::new(footer_text).dim(), footer);
+
+    match &app.mode {
+        Mode::Browse => {}
+        Mode::Confirm { prompt, .. } => {
+            let area = popup(frame.area(), 70, 4);
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(format!("{prompt} [y/N]"))
+                    .block(Block::bordered().title(" Confirm "))
+                    .wrap(Wrap { trim: true }),
+                area,
+            );
+        }
+        Mode::Form(form) => {
+            let height = form.fields.len() as u16 + 4;
+            let area = popup(frame.area(), 60, height);
+            frame.render_widget(Clear, area);
+            frame.render_widget(form_text(form), area);
+        }
+    }
+}
+
+fn form_text(form: &GenerateForm) -> Paragraph<'_> {
+    let mut lines: Vec<Line> = form
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let line = Line::from(format!("{:<11} {}", field.label, field.value));
+            if index == form.focus { line.add_modifier(Modifier::REVERSED) } else { line }
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(match &form.error {
+        Some(error) => Line::from(error.as_str()).red(),
+        None => Line::from("Tab next field  Enter generate  Esc cancel").dim(),
+    });
+    Paragraph::new(Text::from(lines)).block(Block::bordered().title(" Generate host "))
+}
+
+fn detail_lines(details: &HostDetails) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(details.config_path.display().to_string()).bold()];
+    lines.extend(
+        details
+            .config
+            .iter()
+            .map(|directive| Line::from(format!("  {} {}", directive.key, directive.value))),
+    );
+    lines.push(Line::from(""));
+    for key in details.keys.iter().filter(|key| key.exists) {
+        let mode = key.mode.as_deref().map(|mode| format!(" ({mode})")).unwrap_or_default();
+        lines.push(Line::from(format!("{}: {}{mode}", key.role, key.path.display())));
+    }
+    if let Some(fingerprint) = &details.fingerprint {
+        lines.push(Line::from(format!("Fingerprint: {fingerprint}")));
+    }
+    if let Some(meta) = &details.metadata {
+        let fields = [
+            ("Created", &meta.created_at),
+            ("Purpose", &meta.purpose),
+            ("Expires", &meta.expires),
+            ("Via", &meta.via),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                lines.push(Line::from(format!("{name}: {value}")));
+            }
+        }
+        if let Some(deployment) = &meta.deployment {
+            lines.push(Line::from(format!("Deployed: {}", deployment.deployed_at)));
+        }
+        for registration in &meta.registrations {
+            lines.push(Line::from(format!(
+                "Registered: {} key {}",
+                registration.provider, registration.key_id
+            )));
+        }
+    }
+    lines.push(Line::from(format!("Known hosts: {}", details.known_hosts.len())));
+    lines
+}
+
+/// A centered rectangle `percent_x` wide and `height` rows tall.
+fn popup(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(area);
+    let [area] =
+        Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center).areas(area);
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn screen(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn renders_hosts_and_confirmation() {
+        let mut app = App::new(vec!["db".to_string(), "web".to_string()]);
+        let text = screen(&app);
+        assert!(text.contains("Hosts (2)"));
+        assert!(text.contains("> db"));
+        assert!(text.contains("g generate"));
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE));
+        assert!(screen(&app).contains("Delete the key pair and config of 'db'? [y/N]"));
+    }
+}
@@ -0,0 +1,316 @@
+//! Terminal-independent state of `ssv ui`: key handling, selection, confirmations and forms.
+//!
+//! Keys go in and [`Effect`]s come out; the runner performs the effect and reports the outcome
+//! back, so everything here can be exercised without a terminal.
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ssv::{GenerateOptions, HostDetails};
+
+/// Work the runner performs on behalf of the UI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Effect {
+    Generate(Box<GenerateOptions>),
+    Edit(String),
+    Rotate(String),
+    Remove(String),
+    CopyPublicKey(String),
+    Test(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Mode {
+    Browse,
+    /// A destructive effect waiting for `y`.
+    Confirm {
+        prompt: String,
+        effect: Effect,
+    },
+    Form(GenerateForm),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Field {
+    pub label: &'static str,
+    pub value: String,
+}
+
+/// Input form for a new host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GenerateForm {
+    pub fields: Vec<Field>,
+    pub focus: usize,
+    pub error: Option<String>,
+}
+
+const HOST: usize = 0;
+const HOSTNAME: usize = 1;
+const USER: usize = 2;
+const PORT: usize = 3;
+const KEY_TYPE: usize = 4;
+
+impl GenerateForm {
+    fn new() -> Self {
+        let field = |label, value: &str| Field { label, value: value.to_string() };
+        Self {
+            fields: vec![
+                field("Host alias", ""),
+                field("HostName", ""),
+                field("User", ""),
+                field("Port", ""),
+                field("Key type", "ed25519"),
+            ],
+            focus: 0,
+            error: None,
+        }
+    }
+
+    fn value(&self, index: usize) -> Option<String> {
+        let value = self.fields[index].value.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+
+    /// Options for [`ssv::generate_with`]; deeper validation happens there.
+    fn submit(&self) -> Result<GenerateOptions, String> {
+        let host = self.value(HOST).ok_or("host alias is required")?;
+        let port = match self.value(PORT) {
+            Some(port) => Some(port.parse::<u16>().map_err(|_| format!("invalid port '{port}'"))?),
+            None => None,
+        };
+        let mut options = GenerateOptions::new(host);
+        options.hostname = self.value(HOSTNAME);
+        options.user = self.value(USER);
+        options.port = port;
+        if let Some(key_type) = self.value(KEY_TYPE) {
+            options.key_type = key_type;
+        }
+        Ok(options)
+    }
+}
+
+pub(crate) struct App {
+    pub hosts: Vec<String>,
+    pub selected: usize,
+    /// Details of the selected host, loaded by the runner.
+    pub details: Option<HostDetails>,
+    pub mode: Mode,
+    pub status: Option<String>,
+    pub quit: bool,
+}
+
+impl App {
+    pub(crate) fn new(hosts: Vec<String>) -> Self {
+        Self { hosts, selected: 0, details: None, mode: Mode::Browse, status: None, quit: false }
+    }
+
+    pub(crate) fn selected_host(&self) -> Option<&str> {
+        self.hosts.get(self.selected).map(String::as_str)
+    }
+
+    /// Replace the inventory after a change, keeping the selection on the same host if it exists.
+    pub(crate) fn set_hosts(&mut self, hosts: Vec<String>) {
+        let current = self.selected_host().map(str::to_string);
+        self.hosts = hosts;
+        self.selected = current
+            .and_then(|host| self.hosts.iter().position(|h| *h == host))
+            .unwrap_or(self.selected.min(self.hosts.len().saturating_sub(1)));
+    }
+
+    /// Show the outcome of an effect in the status line.
+    pub(crate) fn report(&mut self, outcome: Result<String, String>) {
+        self.status = Some(match outcome {
+            Ok(message) => message,
+            Err(error) => format!("Error: {error}"),
+        });
+    }
+
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> Option<Effect> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return None;
+        }
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => self.browse(key),
+            Mode::Confirm { prompt, effect } => match key.code {
+                KeyCode::Char('y' | 'Y') => Some(effect),
+                KeyCode::Char('n' | 'N') | KeyCode::Esc => {
+                    self.status = Some("Cancelled".to_string());
+                    None
+                }
+                _ => {
+                    self.mode = Mode::Confirm { prompt, effect };
+                    None
+                }
+            },
+            Mode::Form(form) => self.form(form, key),
+        }
+    }
+
+    fn browse(&mut self, key: KeyEvent) -> Option<Effect> {
+        let host = self.selected_host().map(str::to_string);
+        match (key.code, host) {
+            (KeyCode::Char('q') | KeyCode::Esc, _) => self.quit = true,
+            (KeyCode::Down | KeyCode::Char('j'), _) => {
+                self.selected = (self.selected + 1).min(self.hosts.len().saturating_sub(1));
+            }
+            (KeyCode::Up | KeyCode::Char('k'), _) => {
+                self.selected = self.selected.saturating_sub(1)
+            }
+            (KeyCode::Home, _) => self.selected = 0,
+            (KeyCode::End, _) => self.selected = self.hosts.len().saturating_sub(1),
+            (KeyCode::Char('g'), _) => self.mode = Mode::Form(GenerateForm::new()),
+            (KeyCode::Char('e'), Some(host)) => return Some(Effect::Edit(host)),
+            (KeyCode::Char('c'), Some(host)) => return Some(Effect::CopyPublicKey(host)),
+            (KeyCode::Char('t'), Some(host)) => return Some(Effect::Test(host)),
+            (KeyCode::Char('r'), Some(host)) => {
+                self.mode = Mode::Confirm {
+                    prompt: format!("Replace the key pair of '{host}'?"),
+                    effect: Effect::Rotate(host),
+                };
+            }
+            (KeyCode::Char('d') | KeyCode::Delete, Some(host)) => {
+                self.mode = Mode::Confirm {
+                    prompt: format!("Delete the key pair and config of '{host}'?"),
+                    effect: Effect::Remove(host),
+                };
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn form(&mut self, mut form: GenerateForm, key: KeyEvent) -> Option<Effect> {
+        let last = form.fields.len() - 1;
+        match key.code {
+            KeyCode::Esc => {
+                self.status = Some("Cancelled".to_string());
+                return None;
+            }
+            KeyCode::Enter => match form.submit() {
+                Ok(options) => return Some(Effect::Generate(Box::new(options))),
+                Err(error) => form.error = Some(error),
+            },
+            KeyCode::Tab | KeyCode::Down => form.focus = (form.focus + 1).min(last),
+            KeyCode::BackTab | KeyCode::Up => form.focus = form.focus.saturating_sub(1),
+            KeyCode::Backspace => {
+                form.fields[form.focus].value.pop();
+            }
+            KeyCode::Char(c) => form.fields[form.focus].value.push(c),
+            _ => {}
+        }
+        self.mode = Mode::Form(form);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(app: &mut App, code: KeyCode) -> Option<Effect> {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            assert_eq!(press(app, KeyCode::Char(c)), None);
+        }
+    }
+
+    fn hosts(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn navigation_stays_within_the_inventory() {
+        let mut app = App::new(hosts(&["a", "b", "c"]));
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.selected_host(), Some("a"));
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Char('j'));
+        }
+        assert_eq!(app.selected_host(), Some("c"));
+        press(&mut app, KeyCode::Home);
+        assert_eq!(app.selected, 0);
+
+        app.selected = 2;
+        app.set_hosts(hosts(&["c", "d"]));
+        assert_eq!(app.selected_host(), Some("c"));
+        app.set_hosts(hosts(&["d"]));
+        assert_eq!(app.selected_host(), Some("d"));
+    }
+
+    #[test]
+    fn destructive_actions_need_confirmation() {
+        let mut app = App::new(hosts(&["db"]));
+        assert_eq!(press(&mut app, KeyCode::Char('d')), None);
+        assert!(matches!(&app.mode, Mode::Confirm { prompt, .. } if prompt.contains("'db'")));
+        assert_eq!(press(&mut app, KeyCode::Char('x')), None);
+        assert_eq!(press(&mut app, KeyCode::Char('n')), None);
+        assert_eq!(app.mode, Mode::Browse);
+        assert_eq!(app.status.as_deref(), Some("Cancelled"));
+
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(press(&mut app, KeyCode::Char('y')), Some(Effect::Rotate("db".into())));
+        press(&mut app, KeyCode::Delete);
+        assert_eq!(press(&mut app, KeyCode::Char('y')), Some(Effect::Remove("db".into())));
+        assert_eq!(app.mode, Mode::Browse);
+    }
+
+    #[test]
+    fn safe_actions_run_immediately() {
+        let mut app = App::new(hosts(&["db"]));
+        assert_eq!(press(&mut app, KeyCode::Char('t')), Some(Effect::Test("db".into())));
+        assert_eq!(press(&mut app, KeyCode::Char('c')), Some(Effect::CopyPublicKey("db".into())));
+        assert_eq!(press(&mut app, KeyCode::Char('e')), Some(Effect::Edit("db".into())));
+
+        let mut empty = App::new(Vec::new());
+        assert_eq!(press(&mut empty, KeyCode::Char('d')), None);
+        assert_eq!(empty.mode, Mode::Browse);
+    }
+
+    #[test]
+    fn generate_form_builds_options() {
+        let mut app = App::new(Vec::new());
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(press(&mut app, KeyCode::Enter), None);
+        assert!(matches!(&app.mode, Mode::Form(form) if form.error.is_some()));
+
+        type_text(&mut app, "web");
+        press(&mut app, KeyCode::Tab);
+        type_text(&mut app, "web.example.com");
+        press(&mut app, KeyCode::Tab);
+        type_text(&mut app, "deploy");
+        press(&mut app, KeyCode::Tab);
+        type_text(&mut app, "22x");
+        assert_eq!(press(&mut app, KeyCode::Enter), None);
+        assert!(
+            matches!(&app.mode, Mode::Form(form) if form.error.as_deref() == Some("invalid port '22x'"))
+        );
+
+        press(&mut app, KeyCode::Backspace);
+        let Some(Effect::Generate(options)) = press(&mut app, KeyCode::Enter) else {
+            panic!("form did not submit");
+        };
+        assert_eq!(options.host, "web");
+        assert_eq!(options.hostname.as_deref(), Some("web.example.com"));
+        assert_eq!(options.user.as_deref(), Some("deploy"));
+        assert_eq!(options.port, Some(22));
+        assert_eq!(options.key_type, "ed25519");
+        assert_eq!(app.mode, Mode::Browse);
+    }
+
+    #[test]
+    fn quitting() {
+        let mut app = App::new(Vec::new());
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Char('q'));
+        assert!(!app.quit, "q is text inside the form");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('q'));
+        assert!(app.quit);
+
+        let mut app = App::new(Vec::new());
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(app.quit);
+    }
+}
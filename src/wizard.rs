@@ -0,0 +1,231 @@
+//! Guided `ssv generate` for interactive terminals.
+//!
+//! Each answer is validated as soon as it is entered and the question is asked again until it
+//! passes. Input and output are generic so the flow can be driven from tests.
+
+use ssv::error::AppError;
+use std::io::{self, BufRead, Write};
+
+/// Everything the wizard asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Answers {
+    pub host: String,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub key_type: String,
+    pub passphrase: Option<String>,
+    pub options: Vec<(String, String)>,
+}
+
+pub(crate) struct Wizard<R, W, S> {
+    input: R,
+    output: W,
+    /// Reads a line without echoing it.
+    read_secret: S,
+}
+
+impl<R, W, S> Wizard<R, W, S>
+where
+    R: BufRead,
+    W: Write,
+    S: FnMut(&str) -> io::Result<String>,
+{
+    pub(crate) fn new(input: R, output: W, read_secret: S) -> Self {
+        Self { input, output, read_secret }
+    }
+
+    /// Ask for a new host. `existing` are the managed hosts, `default_key_type` the configured one.
+    pub(crate) fn run(
+        &mut self,
+        existing: &[String],
+        default_key_type: &str,
+    ) -> Result<Answers, AppError> {
+        let host = self.ask("Host alias", None, |value| {
+            let value = required(value)?;
+            ssv::validate_host(&value).map_err(|err| err.to_string())?;
+            if existing.contains(&value) {
+                return Err(format!("'{value}' is already managed"));
+            }
+            Ok(value)
+        })?;
+        let hostname = self.ask("HostName", Some(&host), |value| {
+            let value = required(value)?;
+            ssv::validate_host(&value).map_err(|err| err.to_string())?;
+            Ok(value)
+        })?;
+        let user = self.ask("User (blank for none)", None, |value| {
+            if value.is_empty() {
+                return Ok(None);
+            }
+            ssv::validate_user(value).map_err(|err| err.to_string())?;
+            Ok(Some(value.to_string()))
+        })?;
+        let port = self.ask("Port", Some("22"), |value| {
+            let port = value.parse::<u16>().map_err(|_| format!("invalid port '{value}'"))?;
+            if port == 0 {
+                return Err("port must not be 0".to_string());
+            }
+            Ok((port != 22).then_some(port))
+        })?;
+        let key_type = self.ask("Key type", Some(default_key_type), |value| {
+            ssv::validate_key_type(value).map_err(|err| err.to_string())?;
+            Ok(value.to_string())
+        })?;
+        let passphrase = self.passphrase()?;
+
+        writeln!(self.output, "Extra ssh_config options as KEY=VALUE, blank line to finish")?;
+        let mut options = Vec::new();
+        while let Some(option) = self.ask("Option", None, |value| {
+            if value.is_empty() {
+                return Ok(None);
+            }
+            let (key, value) = crate::parse_key_value(value)?;
+            ssv::validate_option(&key, &value).map_err(|err| err.to_string())?;
+            Ok(Some((key, value)))
+        })? {
+            options.push(option);
+        }
+
+        Ok(Answers {
+            hostname: (hostname != host).then_some(hostname),
+            host,
+            user,
+            port,
+            key_type,
+            passphrase,
+            options,
+        })
+    }
+
+    /// Print the config about to be written and ask whether to go ahead.
+    pub(crate) fn confirm(&mut self, config: &str) -> Result<bool, AppError> {
+        writeln!(self.output, "\nThe following config will be written:\n")?;
+        for line in config.lines() {
+            writeln!(self.output, "    {line}")?;
+        }
+        writeln!(self.output)?;
+        self.ask("Generate this host? [Y/n]", None, |value| match value {
+            "" | "y" | "Y" | "yes" => Ok(true),
+            "n" | "N" | "no" => Ok(false),
+            _ => Err("answer y or n".to_string()),
+        })
+    }
+
+    fn passphrase(&mut self) -> Result<Option<String>, AppError> {
+        loop {
+            let passphrase = (self.read_secret)("Passphrase (blank for none): ")?;
+            if passphrase.is_empty() {
+                return Ok(None);
+            }
+            if (self.read_secret)("Repeat passphrase: ")? == passphrase {
+                return Ok(Some(passphrase));
+            }
+            writeln!(self.output, "  passphrases do not match")?;
+        }
+    }
+
+    fn ask<T>(
+        &mut self,
+        question: &str,
+        default: Option<&str>,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<T, AppError> {
+        loop {
+            match default {
+                Some(default) => write!(self.output, "{question} [{default}]: ")?,
+                None => write!(self.output, "{question}: ")?,
+            }
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "input ended").into());
+            }
+            let answer = match line.trim() {
+                "" => default.unwrap_or_default(),
+                answer => answer,
+            };
+            match parse(answer) {
+                Ok(value) => return Ok(value),
+                Err(error) => writeln!(self.output, "  {error}")?,
+            }
+        }
+    }
+}
+
+fn required(value: &str) -> Result<String, String> {
+    if value.is_empty() { Err("a value is required".to_string()) } else { Ok(value.to_string()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str, secrets: &[&str]) -> (Result<Answers, AppError>, String) {
+        let mut secrets = secrets.iter().map(|secret| secret.to_string());
+        let mut output = Vec::new();
+        let result = Wizard::new(input.as_bytes(), &mut output, |_: &str| {
+            Ok(secrets.next().expect("unexpected passphrase prompt"))
+        })
+        .run(&["taken".to_string()], "ed25519");
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn defaults_produce_minimal_answers() {
+        let (answers, _) = run("db\n\n\n\n\n\n", &[""]);
+        assert_eq!(
+            answers.unwrap(),
+            Answers {
+                host: "db".into(),
+                hostname: None,
+                user: None,
+                port: None,
+                key_type: "ed25519".into(),
+                passphrase: None,
+                options: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_answers_are_asked_again() {
+        let input = "bad host\ntaken\ndb\ndb.example.com\ndeploy\nhttps\n2222\nRSA\nrsa\n\
+                     nonsense\nServerAliveInterval=30\n\n";
+        let (answers, output) = run(input, &["s3cret", "typo", "s3cret", "s3cret"]);
+        let answers = answers.unwrap();
+
+        assert_eq!(answers.host, "db");
+        assert_eq!(answers.hostname.as_deref(), Some("db.example.com"));
+        assert_eq!(answers.user.as_deref(), Some("deploy"));
+        assert_eq!(answers.port, Some(2222));
+        assert_eq!(answers.key_type, "rsa");
+        assert_eq!(answers.passphrase.as_deref(), Some("s3cret"));
+        assert_eq!(answers.options, vec![("ServerAliveInterval".into(), "30".into())]);
+
+        assert!(output.contains("invalid host identifier 'bad host'"));
+        assert!(output.contains("'taken' is already managed"));
+        assert!(output.contains("invalid port 'https'"));
+        assert!(output.contains("invalid key type 'RSA'"));
+        assert!(output.contains("passphrases do not match"));
+        assert!(output.contains("expected KEY=VALUE, got 'nonsense'"));
+    }
+
+    #[test]
+    fn end_of_input_aborts() {
+        let (answers, _) = run("db\n", &[]);
+        assert!(answers.is_err());
+    }
+
+    #[test]
+    fn confirmation_shows_config() {
+        let mut output = Vec::new();
+        let mut wizard =
+            Wizard::new("maybe\nn\n".as_bytes(), &mut output, |_: &str| Ok(String::new()));
+        assert!(!wizard.confirm("Host db\nHostName db.example.com\n").unwrap());
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("    HostName db.example.com\n"));
+        assert!(output.contains("answer y or n"));
+    }
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+
+const RSA_1024: &str = "ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAAAgQDI5McwoJmrmFmbLYm2YItR5igk+zgUm5miH6kKnvaP3PgMJ/KyuFdho2ytjxy+ERovW0axQ7i2/wx0uhGNtLII6KYmZgYmnD/VpnDqj5nsTEcUoun2/Qzj51rOpu1t3CLeIW2kOGbS1XuoigmNDeizlnuZ/Z1DgSbHWN8h4sB4WQ== fixture\n";
+
+#[test]
+#[serial]
+fn audit_passes_for_fresh_ed25519_keys() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "fresh.test"]).assert().success();
+
+    let output = ctx.cli().args(["audit", "--format", "json"]).assert().success();
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.get_output().stdout).expect("audit emits JSON");
+    let host = &report["hosts"][0];
+    assert_eq!(host["host"], "fresh.test");
+    assert_eq!(host["key_type"], "ed25519");
+    assert_eq!(host["bits"], 256);
+    assert_eq!(host["age_days"], 0);
+}
+
+#[test]
+#[serial]
+fn audit_leaves_a_fresh_home_untouched() {
+    let ctx = TestContext::new();
+    let output = ctx.cli().args(["audit", "--format", "json"]).assert().success();
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.get_output().stdout).expect("audit emits JSON");
+    assert_eq!(report["hosts"], serde_json::json!([]));
+    assert!(!ctx.home().join(".ssh").exists());
+}
+
+#[test]
+#[serial]
+fn audit_flags_weak_and_stale_keys_with_nonzero_exit() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "legacy.test", "--type", "rsa"]).assert().success();
+    fs::write(ctx.public_key_path("rsa", "legacy.test"), RSA_1024).unwrap();
+
+    let meta_path = ctx.home().join(".ssh/.ssv/meta/legacy.test.json");
+    fs::write(&meta_path, r#"{ "created_at": "2020-01-01T00:00:00Z" }"#).unwrap();
+
+    ctx.cli()
+        .args(["audit", "--format", "sarif"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("\"ruleId\": \"weak-key\""))
+        .stdout(predicate::str::contains("\"ruleId\": \"key-age\""));
+
+    ctx.cli()
+        .args(["audit", "--max-age-days", "100000", "--min-rsa-bits", "1024"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[ok] legacy.test: rsa 1024 bits"));
+}
+
+#[test]
+#[serial]
+fn malformed_metadata_is_a_finding_on_its_host() {
+    let ctx = TestContext::new();
+    for host in ["broken.test", "dated.test", "fine.test"] {
+        ctx.cli().args(["generate", "--host", host]).assert().success();
+    }
+    let meta_dir = ctx.home().join(".ssh/.ssv/meta");
+    fs::write(meta_dir.join("broken.test.json"), "{ not json").unwrap();
+    fs::write(meta_dir.join("dated.test.json"), r#"{ "created_at": "yesterday" }"#).unwrap();
+
+    let output = ctx.cli().args(["audit", "--format", "json"]).assert().code(2);
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.get_output().stdout).expect("audit emits JSON");
+    let hosts = report["hosts"].as_array().unwrap();
+    assert_eq!(hosts.len(), 3);
+    for (host, detail) in [("broken.test", ""), ("dated.test", "created_at: invalid date")] {
+        let audit = hosts.iter().find(|audit| audit["host"] == host).unwrap();
+        let finding = &audit["findings"][0];
+        assert_eq!(finding["rule"], "invalid-metadata");
+        assert!(finding["message"].as_str().unwrap().contains(detail), "{finding}");
+        assert!(finding["path"].as_str().unwrap().ends_with(&format!("{host}.json")));
+        assert_eq!(audit["age_days"], 0);
+    }
+    let fine = hosts.iter().find(|audit| audit["host"] == "fine.test").unwrap();
+    assert_eq!(fine["findings"], serde_json::json!([]));
+}
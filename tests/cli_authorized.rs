@@ -0,0 +1,90 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+const KEY: &str =
+    "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAlUFMG3D4/ol4Gm9aH5E4iBjHU7jyNJlXygA4pfxqbB ci@build";
+
+#[test]
+#[serial]
+fn authorized_entries_keep_unmanaged_lines() {
+    let ctx = TestContext::new();
+    let path = ctx.home().join(".ssh/authorized_keys");
+    let original = "# personal keys\nssh-rsa AAAAOTHER me@desktop\n\n";
+    fs::create_dir_all(ctx.home().join(".ssh")).unwrap();
+    fs::write(&path, original).unwrap();
+
+    ctx.cli()
+        .args(["authorized", "add", "--label", "ci", KEY])
+        .args(["--from", "10.0.0.0/8", "--command", "rsync --server", "--restrict"])
+        .args(["--expiry-time", "20301231"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Authorized 'ci' (SHA256:"));
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        format!(
+            "{original}restrict,from=\"10.0.0.0/8\",command=\"rsync --server\",\
+             expiry-time=\"20301231\" {KEY} ssv:ci\n"
+        )
+    );
+    #[cfg(unix)]
+    assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+    ctx.cli()
+        .args(["authorized", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ci               ssh-ed25519  SHA256:"))
+        .stdout(predicate::str::contains("ci@build"))
+        .stdout(predicate::str::contains("options: restrict,from=\"10.0.0.0/8\""))
+        .stdout(predicate::str::contains("me@desktop").not());
+
+    ctx.cli().args(["authorized", "remove", "--label", "ci"]).assert().success();
+    assert_eq!(fs::read_to_string(&path).unwrap(), original);
+    ctx.cli()
+        .args(["authorized", "remove", "--label", "ci"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no authorized key labelled 'ci'"));
+}
+
+#[test]
+#[serial]
+fn authorized_add_rejects_bad_input() {
+    let ctx = TestContext::new();
+    let key_file = ctx.work_dir().join("ci.pub");
+    fs::write(&key_file, format!("{KEY}\n")).unwrap();
+
+    ctx.cli()
+        .args(["authorized", "add", "--label", "ci", "--key-file", key_file.to_str().unwrap()])
+        .assert()
+        .success();
+    ctx.cli()
+        .args(["authorized", "add", "--label", "other", KEY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already authorized on line 1"));
+    ctx.cli()
+        .args(["authorized", "add", "--label", "ci", "ssh-ed25519 AAAATESTKEY"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a valid OpenSSH public key"));
+    ctx.cli()
+        .args(["authorized", "add", "--label", "x", KEY, "--option", "no-such-option"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown authorized_keys option"));
+    ctx.cli()
+        .args(["authorized", "add", "--label", "x", KEY, "--expiry-time", "tomorrow"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must be YYYYMMDD"));
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+const PASSPHRASE_VAR: &str = "SSV_TEST_BUNDLE_PASSPHRASE";
+
+fn export_bundle(ctx: &TestContext, extra: &[&str]) -> std::path::PathBuf {
+    let bundle = ctx.work_dir().join("hosts.ssvb");
+    ctx.cli()
+        .args(["export", "--out", bundle.to_str().unwrap(), "--passphrase-env", PASSPHRASE_VAR])
+        .args(extra)
+        .env(PASSPHRASE_VAR, "correct horse")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported"));
+    bundle
+}
+
+#[test]
+#[serial]
+fn export_then_import_restores_hosts_on_a_fresh_home() {
+    let source = TestContext::new();
+    source
+        .cli()
+        .args(["generate", "--host", "bundle.test", "--user", "git", "--purpose", "backup"])
+        .assert()
+        .success();
+    source.cli().args(["generate", "--host", "other.test"]).assert().success();
+    fs::write(source.home().join(".ssh/known_hosts"), "bundle.test ssh-ed25519 AAAAkey\n").unwrap();
+    let bundle = export_bundle(&source, &["--hosts", "bundle.test"]);
+    assert_eq!(fs::metadata(&bundle).unwrap().permissions().mode() & 0o777, 0o600);
+    assert!(!fs::read_to_string(&bundle).unwrap_or_default().contains("User git"));
+
+    let target = TestContext::new();
+    target
+        .cli()
+        .args(["import", bundle.to_str().unwrap(), "--passphrase-env", PASSPHRASE_VAR])
+        .env(PASSPHRASE_VAR, "correct horse")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bundle.test"));
+
+    target.assert_config_contains("bundle.test", "User git");
+    assert!(!target.host_config_path("other.test").exists());
+    let private = target.private_key_path("ed25519", "bundle.test");
+    assert_eq!(
+        fs::read(&private).unwrap(),
+        fs::read(source.private_key_path("ed25519", "bundle.test")).unwrap()
+    );
+    assert_eq!(fs::metadata(&private).unwrap().permissions().mode() & 0o777, 0o600);
+    assert_eq!(
+        fs::metadata(target.home().join(".ssh")).unwrap().permissions().mode() & 0o777,
+        0o700
+    );
+    let known_hosts = fs::read_to_string(target.home().join(".ssh/known_hosts")).unwrap();
+    assert!(known_hosts.contains("bundle.test ssh-ed25519 AAAAkey"));
+    target
+        .cli()
+        .args(["list", "--long"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("backup"));
+}
+
+#[test]
+#[serial]
+fn import_refuses_conflicts_unless_renamed() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "clash.test"]).assert().success();
+    let bundle = export_bundle(&ctx, &[]);
+
+    ctx.cli()
+        .args(["import", bundle.to_str().unwrap(), "--passphrase-env", PASSPHRASE_VAR])
+        .env(PASSPHRASE_VAR, "correct horse")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("hosts already exist locally: clash.test"))
+        .stderr(predicate::str::contains("--rename"));
+
+    ctx.cli()
+        .args(["import", bundle.to_str().unwrap(), "--passphrase-env", PASSPHRASE_VAR])
+        .args(["--rename", "clash.test=copy.test"])
+        .env(PASSPHRASE_VAR, "correct horse")
+        .assert()
+        .success();
+
+    ctx.assert_config_contains("copy.test", "Host copy.test");
+    ctx.assert_config_contains("copy.test", "id_ed25519_copy.test");
+    assert!(ctx.private_key_path("ed25519", "copy.test").exists());
+    assert!(ctx.public_key_path("ed25519", "copy.test").exists());
+}
+
+#[test]
+#[serial]
+fn import_rejects_wrong_passphrase() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "secret.test"]).assert().success();
+    let bundle = export_bundle(&ctx, &[]);
+
+    let target = TestContext::new();
+    target
+        .cli()
+        .args(["import", bundle.to_str().unwrap(), "--passphrase-env", PASSPHRASE_VAR])
+        .env(PASSPHRASE_VAR, "battery staple")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("wrong passphrase"));
+    assert!(!target.host_config_path("secret.test").exists());
+}
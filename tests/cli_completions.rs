@@ -0,0 +1,62 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn completions_command_emits_dynamic_registration() {
+    let ctx = TestContext::new();
+
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        ctx.cli()
+            .args(["completions", shell])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("COMPLETE"));
+    }
+}
+
+#[test]
+#[serial]
+fn host_arguments_complete_from_managed_inventory() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "alpha.test"]).assert().success();
+    ctx.cli().args(["generate", "--host", "beta.test"]).assert().success();
+
+    ctx.cli()
+        .env("COMPLETE", "fish")
+        .args(["--", "ssv", "remove", "--host", "al"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("alpha.test").and(predicate::str::contains("beta").not()));
+}
+
+#[test]
+#[serial]
+fn completing_hosts_leaves_a_fresh_home_untouched() {
+    let ctx = TestContext::new();
+    ctx.cli()
+        .env("COMPLETE", "fish")
+        .args(["--", "ssv", "remove", "--host", ""])
+        .assert()
+        .success();
+    ctx.cli().arg("list").assert().success();
+    assert!(!ctx.home().join(".ssh").exists());
+}
+
+#[test]
+#[serial]
+fn man_command_writes_a_page_per_subcommand() {
+    let ctx = TestContext::new();
+    let out_dir = ctx.work_dir().join("man");
+
+    ctx.cli().args(["man", "--out-dir"]).arg(&out_dir).assert().success();
+
+    for page in ["ssv.1", "ssv-generate.1", "ssv-remove.1", "ssv-show.1"] {
+        assert!(out_dir.join(page).exists(), "missing man page {page}");
+    }
+
+    ctx.cli().arg("man").assert().success().stdout(predicate::str::contains(".TH ssv"));
+}
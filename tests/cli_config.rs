@@ -0,0 +1,132 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+
+#[test]
+#[serial]
+fn config_defaults_apply_to_generate_below_flags_and_env() {
+    let ctx = TestContext::new();
+    for (key, value) in [
+        ("key_type", "rsa"),
+        ("users.*.corp.example", "deploy"),
+        ("options.ServerAliveInterval", "30"),
+    ] {
+        ctx.cli().args(["config", "set", key, value]).assert().success();
+    }
+    let config = fs::read_to_string(ctx.home().join(".config/ssv/config.toml")).unwrap();
+    assert!(config.contains("key_type = \"rsa\""));
+
+    ctx.cli().args(["generate", "--host", "db1.corp.example"]).assert().success();
+    assert!(ctx.private_key_path("rsa", "db1.corp.example").exists());
+    ctx.assert_config_contains("db1.corp.example", "User deploy");
+    ctx.assert_config_contains("db1.corp.example", "ServerAliveInterval 30");
+
+    ctx.cli()
+        .args(["generate", "--host", "db2.corp.example", "--user", "root"])
+        .env("SSV_KEY_TYPE", "ecdsa")
+        .assert()
+        .success();
+    assert!(ctx.private_key_path("ecdsa", "db2.corp.example").exists());
+    ctx.assert_config_contains("db2.corp.example", "User root");
+
+    ctx.cli()
+        .args(["generate", "--host", "db3.corp.example", "--type", "ed25519"])
+        .env("SSV_KEY_TYPE", "ecdsa")
+        .assert()
+        .success();
+    assert!(ctx.private_key_path("ed25519", "db3.corp.example").exists());
+}
+
+#[test]
+#[serial]
+fn config_get_list_and_unset_round_trip() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["config", "set", "format", "json"]).assert().success();
+    ctx.cli().args(["config", "set", "bits", "4096"]).assert().success();
+
+    ctx.cli().args(["config", "get", "format"]).assert().success().stdout("json\n");
+    ctx.cli().args(["config", "list"]).assert().success().stdout("bits = 4096\nformat = json\n");
+
+    ctx.cli().args(["config", "unset", "bits"]).assert().success();
+    ctx.cli().args(["config", "get", "bits"]).assert().failure();
+    ctx.cli()
+        .args(["config", "set", "format", "xml"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown format 'xml'"));
+    ctx.cli()
+        .args(["config", "set", "colour", "red"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown config key 'colour'"));
+}
+
+#[test]
+#[serial]
+fn config_format_is_used_when_flag_is_omitted() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "fmt.test"]).assert().success();
+    ctx.cli().args(["config", "set", "format", "json"]).assert().success();
+
+    ctx.cli()
+        .args(["show", "--host", "fmt.test"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("{"));
+    ctx.cli()
+        .args(["show", "--host", "fmt.test", "--format", "human"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Host: fmt.test"));
+}
+
+#[test]
+#[serial]
+fn sarif_default_only_applies_to_audit() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "fmt.test"]).assert().success();
+
+    ctx.cli()
+        .arg("doctor")
+        .env("SSV_FORMAT", "sarif")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All 1 host configs match"));
+    ctx.cli()
+        .arg("audit")
+        .env("SSV_FORMAT", "sarif")
+        .assert()
+        .stdout(predicate::str::contains("\"version\": \"2.1.0\""));
+
+    ctx.cli().args(["config", "set", "format", "sarif"]).assert().success();
+    ctx.cli()
+        .args(["show", "--host", "fmt.test"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Host: fmt.test"));
+    ctx.cli()
+        .args(["show", "--host", "fmt.test"])
+        .env("SSV_FORMAT", "json")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("{"));
+}
+
+#[test]
+#[serial]
+fn config_honours_xdg_config_home() {
+    let ctx = TestContext::new();
+    let xdg = ctx.home().join("xdg");
+
+    ctx.cli()
+        .args(["config", "set", "comment", "{user}@{machine}"])
+        .env("XDG_CONFIG_HOME", &xdg)
+        .assert()
+        .success();
+
+    assert!(xdg.join("ssv/config.toml").exists());
+    assert!(!ctx.home().join(".config/ssv/config.toml").exists());
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+
+#[test]
+#[serial]
+fn test_command_accepts_github_greeting() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "github.com", "--user", "git"]).assert().success();
+
+    ctx.cli()
+        .env("SSH_STUB_OUTPUT", "Hi octocat! You've successfully authenticated, but GitHub does not provide shell access.")
+        .env("SSH_STUB_EXIT", "1")
+        .args(["test", "--host", "github.com", "--timeout", "3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Connection test passed for 'github.com'"));
+
+    let log = fs::read_to_string(ctx.ssh_log_path()).expect("ssh stub should be invoked");
+    assert!(log.contains("-T -o BatchMode=yes -o ConnectTimeout=3 github.com"), "args: {log}");
+}
+
+#[test]
+#[serial]
+fn test_command_fails_on_rejected_key() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "server.test"]).assert().success();
+
+    ctx.cli()
+        .env("SSH_STUB_OUTPUT", "server.test: Permission denied (publickey).")
+        .env("SSH_STUB_EXIT", "255")
+        .args(["test", "--host", "server.test"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Connection test failed"))
+        .stdout(predicate::str::contains("authentication was rejected"));
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+use std::path::PathBuf;
+
+fn remote_home(ctx: &TestContext) -> PathBuf {
+    let remote = ctx.work_dir().join("remote");
+    fs::create_dir_all(&remote).unwrap();
+    remote
+}
+
+#[test]
+#[serial]
+fn deploy_is_idempotent_and_undeploy_removes_only_the_managed_key() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "srv", "--comment", "me@laptop"]).assert().success();
+    let remote = remote_home(&ctx);
+    fs::create_dir_all(remote.join(".ssh")).unwrap();
+    let authorized_keys = remote.join(".ssh/authorized_keys");
+    fs::write(&authorized_keys, "ssh-rsa AAAAOTHER colleague\n").unwrap();
+
+    for _ in 0..2 {
+        ctx.cli()
+            .args(["deploy", "--host", "srv", "--using-password"])
+            .env("SSH_STUB_REMOTE_HOME", &remote)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Deployed the public key of 'srv'"));
+    }
+    assert_eq!(
+        fs::read_to_string(&authorized_keys).unwrap(),
+        "ssh-rsa AAAAOTHER colleague\nssh-ed25519 AAAATESTKEY me@laptop\n"
+    );
+    let log = fs::read_to_string(ctx.ssh_log_path()).unwrap();
+    assert!(log.starts_with(
+        "-o PubkeyAuthentication=no -o PreferredAuthentications=password,keyboard-interactive srv "
+    ));
+    ctx.cli()
+        .args(["show", "--host", "srv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deployed_at: "));
+
+    ctx.cli()
+        .args(["undeploy", "--host", "srv"])
+        .env("SSH_STUB_REMOTE_HOME", &remote)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&authorized_keys).unwrap(), "ssh-rsa AAAAOTHER colleague\n");
+    ctx.cli()
+        .args(["show", "--host", "srv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deployed_at").not());
+    ctx.cli()
+        .args(["history", "--host", "srv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deploy      srv"))
+        .stdout(predicate::str::contains("undeploy    srv"));
+}
+
+#[test]
+#[serial]
+fn deploy_with_key_options_replaces_previous_line() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "srv", "--comment", "me@laptop"]).assert().success();
+    let remote = remote_home(&ctx);
+
+    ctx.cli()
+        .args(["deploy", "--host", "srv"])
+        .env("SSH_STUB_REMOTE_HOME", &remote)
+        .assert()
+        .success();
+    ctx.cli()
+        .args(["deploy", "--host", "srv", "--key-options", "from=\"10.0.0.0/8\",no-pty"])
+        .env("SSH_STUB_REMOTE_HOME", &remote)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(remote.join(".ssh/authorized_keys")).unwrap(),
+        "from=\"10.0.0.0/8\",no-pty ssh-ed25519 AAAATESTKEY me@laptop\n"
+    );
+    assert!(
+        fs::read_to_string(ctx.ssh_log_path()).unwrap().starts_with("-o IdentitiesOnly=no srv ")
+    );
+
+    ctx.cli()
+        .args(["deploy", "--host", "srv", "--key-options", "no-pty restrict"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid authorized_keys options"));
+    ctx.cli()
+        .args(["deploy", "--host", "srv", "--key-options", "bogus"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown authorized_keys option 'bogus'"));
+
+    // Quoted values may hold spaces and single quotes.
+    ctx.cli()
+        .args(["deploy", "--host", "srv", "--key-options", "command=\"echo 'hi there'\",restrict"])
+        .env("SSH_STUB_REMOTE_HOME", &remote)
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(remote.join(".ssh/authorized_keys")).unwrap(),
+        "command=\"echo 'hi there'\",restrict ssh-ed25519 AAAATESTKEY me@laptop\n"
+    );
+}
+
+#[test]
+#[serial]
+fn deploy_can_log_in_with_another_managed_key() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "old"]).assert().success();
+    ctx.cli().args(["generate", "--host", "srv"]).assert().success();
+
+    ctx.cli().args(["deploy", "--host", "srv", "--using-key", "old"]).assert().success();
+    let log = fs::read_to_string(ctx.ssh_log_path()).unwrap();
+    assert!(log.starts_with(&format!(
+        "-i {} -o IdentitiesOnly=yes srv ",
+        ctx.private_key_path("ed25519", "old").display()
+    )));
+
+    ctx.cli()
+        .args(["remove", "--host", "srv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("still deployed to 'srv'"));
+}
+
+#[test]
+#[serial]
+fn failed_deploy_is_not_recorded() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "srv"]).assert().success();
+
+    ctx.cli().args(["deploy", "--host", "srv"]).env("SSH_STUB_EXIT", "255").assert().failure();
+    ctx.cli()
+        .args(["show", "--host", "srv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deployed_at").not());
+    ctx.cli()
+        .args(["history", "--host", "srv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deploy").not());
+}
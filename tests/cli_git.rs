@@ -0,0 +1,199 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn init_repo(ctx: &TestContext, remote: &str) -> PathBuf {
+    let repo = ctx.work_dir().join("app");
+    for args in [
+        vec!["init", "-q", repo.to_str().unwrap()],
+        vec!["-C", repo.to_str().unwrap(), "remote", "add", "origin", remote],
+    ] {
+        let status = Command::new("git").args(&args).env("HOME", ctx.home()).status().unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+    repo
+}
+
+fn git_output(ctx: &TestContext, repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .env("HOME", ctx.home())
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[test]
+#[serial]
+fn git_use_sets_ssh_command_to_managed_key() {
+    let ctx = TestContext::new();
+    ctx.cli()
+        .args(["generate", "--host", "work-github", "--template", "github"])
+        .assert()
+        .success();
+    let repo = init_repo(&ctx, "git@github.com:acme/app.git");
+
+    ctx.cli()
+        .args(["git", "use", "--host", "work-github", "--repo", repo.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("managed key of 'work-github'"));
+
+    let key = ctx.private_key_path("ed25519", "work-github");
+    assert_eq!(
+        git_output(&ctx, &repo, &["config", "--local", "core.sshCommand"]),
+        format!("ssh -i {} -o IdentitiesOnly=yes -F /dev/null", key.display())
+    );
+    assert_eq!(
+        git_output(&ctx, &repo, &["remote", "get-url", "origin"]),
+        "git@github.com:acme/app.git"
+    );
+
+    ctx.cli_in(&repo)
+        .args(["git", "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("managed key of 'work-github'"))
+        .stdout(predicate::str::contains("origin git@github.com:acme/app.git\n"));
+}
+
+#[test]
+#[serial]
+fn git_use_can_rewrite_remote_onto_alias() {
+    let ctx = TestContext::new();
+    ctx.cli()
+        .args(["generate", "--host", "work-github", "--template", "github"])
+        .assert()
+        .success();
+    let repo = init_repo(&ctx, "https://github.com/acme/app.git");
+
+    ctx.cli()
+        .args(["git", "use", "--host", "work-github", "--repo", repo.to_str().unwrap()])
+        .arg("--rewrite-remote")
+        .assert()
+        .success();
+
+    assert_eq!(
+        git_output(&ctx, &repo, &["remote", "get-url", "origin"]),
+        "work-github:acme/app.git"
+    );
+    assert!(!git_output(&ctx, &repo, &["config", "--local", "core.sshCommand"]).contains("-F"));
+    ctx.cli()
+        .args(["git", "status", repo.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("origin work-github:acme/app.git (alias 'work-github')"));
+}
+
+#[test]
+#[serial]
+fn git_use_rewrites_a_remote_port_only_onto_an_alias_using_it() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "lab"]).assert().success();
+    ctx.cli().args(["generate", "--host", "lab-ssh", "--port", "2222"]).assert().success();
+    let remote = "ssh://git@gitlab.example:2222/acme/app.git";
+    let repo = init_repo(&ctx, remote);
+
+    ctx.cli()
+        .args(["git", "use", "--host", "lab", "--repo", repo.to_str().unwrap()])
+        .arg("--rewrite-remote")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("uses port 2222, but 'lab' connects to port 22"));
+    assert_eq!(git_output(&ctx, &repo, &["remote", "get-url", "origin"]), remote);
+
+    ctx.cli()
+        .args(["git", "use", "--host", "lab-ssh", "--repo", repo.to_str().unwrap()])
+        .arg("--rewrite-remote")
+        .assert()
+        .success();
+    assert_eq!(
+        git_output(&ctx, &repo, &["remote", "get-url", "origin"]),
+        "git@lab-ssh:acme/app.git"
+    );
+}
+
+#[test]
+#[serial]
+fn git_status_reports_repositories_without_managed_keys() {
+    let ctx = TestContext::new();
+    let repo = init_repo(&ctx, "git@example.com:app.git");
+
+    ctx.cli()
+        .args(["git", "status", repo.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("default ssh keys"));
+    ctx.cli()
+        .args(["git", "status", ctx.home().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a git repository"));
+}
+
+#[test]
+#[serial]
+fn git_signing_configures_repo_and_allowed_signers() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "signer"]).assert().success();
+    let repo = init_repo(&ctx, "git@example.com:app.git");
+    let allowed_signers = ctx.home().join(".ssh/allowed_signers");
+    fs::write(&allowed_signers, "me@example.com ssh-ed25519 AAAAHAND\n").unwrap();
+
+    ctx.cli()
+        .args(["git", "signing", "--host", "signer", "--repo", repo.to_str().unwrap()])
+        .args(["--email", "dev@example.com"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("dev@example.com trusted in"));
+
+    let public_key = ctx.public_key_path("ed25519", "signer");
+    assert_eq!(git_output(&ctx, &repo, &["config", "--local", "gpg.format"]), "ssh");
+    assert_eq!(
+        git_output(&ctx, &repo, &["config", "--local", "user.signingkey"]),
+        public_key.display().to_string()
+    );
+    assert_eq!(
+        git_output(&ctx, &repo, &["config", "--local", "gpg.ssh.allowedSignersFile"]),
+        allowed_signers.display().to_string()
+    );
+    assert_eq!(
+        fs::read_to_string(&allowed_signers).unwrap(),
+        "me@example.com ssh-ed25519 AAAAHAND\n\
+         dev@example.com namespaces=\"git\" ssh-ed25519 AAAATESTKEY ssv:signer\n"
+    );
+
+    ctx.cli().args(["remove", "--host", "signer"]).assert().success();
+    assert_eq!(
+        fs::read_to_string(&allowed_signers).unwrap(),
+        "me@example.com ssh-ed25519 AAAAHAND\n"
+    );
+}
+
+#[test]
+#[serial]
+fn git_signing_global_uses_git_user_email() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "signer"]).assert().success();
+
+    ctx.cli()
+        .args(["git", "signing", "--host", "signer", "--global"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("user.email is not set"));
+
+    fs::write(ctx.home().join(".gitconfig"), "[user]\n\temail = global@example.com\n").unwrap();
+    ctx.cli().args(["git", "signing", "--host", "signer", "--global"]).assert().success();
+
+    let gitconfig = fs::read_to_string(ctx.home().join(".gitconfig")).unwrap();
+    assert!(gitconfig.contains("format = ssh"));
+    let allowed_signers = fs::read_to_string(ctx.home().join(".ssh/allowed_signers")).unwrap();
+    assert!(allowed_signers.starts_with("global@example.com namespaces=\"git\" ssh-ed25519"));
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+
+fn generate(ctx: &TestContext, args: &[&str]) {
+    ctx.cli().arg("generate").args(args).assert().success();
+}
+
+#[test]
+#[serial]
+fn generate_via_writes_proxyjump_and_records_dependency() {
+    let ctx = TestContext::new();
+    generate(&ctx, &["--host", "bastion.corp"]);
+    generate(&ctx, &["--host", "db1", "--via", "bastion.corp"]);
+
+    ctx.assert_config_contains("db1", "ProxyJump bastion.corp");
+    ctx.cli()
+        .args(["show", "--host", "db1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("via: bastion.corp"));
+}
+
+#[test]
+#[serial]
+fn generate_via_requires_managed_jump_host() {
+    let ctx = TestContext::new();
+
+    ctx.cli()
+        .args(["generate", "--host", "db1", "--via", "nowhere"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("jump host 'nowhere' is not managed"));
+    assert!(!ctx.host_config_path("db1").exists());
+}
+
+#[test]
+#[serial]
+fn remove_refuses_jump_host_with_dependents_unless_forced() {
+    let ctx = TestContext::new();
+    generate(&ctx, &["--host", "bastion.corp"]);
+    generate(&ctx, &["--host", "db1", "--via", "bastion.corp"]);
+    generate(&ctx, &["--host", "db2", "--option", "ProxyJump=ops@bastion.corp:2222"]);
+
+    ctx.cli()
+        .args(["remove", "--host", "bastion.corp"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("jump host for db1, db2"));
+    assert!(ctx.host_config_path("bastion.corp").exists());
+
+    ctx.cli()
+        .args(["remove", "--host", "bastion.corp", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Still jumping through 'bastion.corp': db1, db2"));
+    assert!(!ctx.host_config_path("bastion.corp").exists());
+}
+
+#[test]
+#[serial]
+fn list_tree_nests_hosts_under_their_jump_host() {
+    let ctx = TestContext::new();
+    generate(&ctx, &["--host", "bastion.corp"]);
+    generate(&ctx, &["--host", "db1", "--via", "bastion.corp"]);
+    generate(&ctx, &["--host", "deep", "--via", "db1"]);
+    generate(&ctx, &["--host", "app", "--via", "bastion.corp"]);
+    generate(&ctx, &["--host", "web"]);
+    generate(&ctx, &["--host", "edge", "--option", "ProxyJump=gateway.example"]);
+
+    ctx.cli().args(["list", "--tree"]).assert().success().stdout(
+        "bastion.corp\n├── app\n└── db1\n    └── deep\nedge (via gateway.example, unmanaged)\nweb\n",
+    );
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+
+const MANIFEST: &str = r#"
+[[host]]
+alias = "work-github"
+hostname = "github.com"
+user = "git"
+
+[[host]]
+alias = "db.internal"
+port = 2222
+type = "rsa"
+options = { ServerAliveInterval = "30" }
+"#;
+
+#[test]
+#[serial]
+fn plan_then_apply_creates_declared_hosts() {
+    let ctx = TestContext::new();
+    let manifest = ctx.work_dir().join("ssv.toml");
+    fs::write(&manifest, MANIFEST).unwrap();
+
+    ctx.cli()
+        .arg("plan")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+ work-github"))
+        .stdout(predicate::str::contains("+HostName github.com"))
+        .stdout(predicate::str::contains("Plan: 2 to create, 0 to update, 0 to prune"));
+    assert!(!ctx.host_config_path("work-github").exists(), "plan must not change anything");
+
+    ctx.cli()
+        .args(["apply", "-f"])
+        .arg(&manifest)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Applied: 2 created"));
+
+    ctx.assert_config_contains("work-github", "HostName github.com");
+    ctx.assert_config_contains("db.internal", "ServerAliveInterval 30");
+    assert!(ctx.private_key_path("rsa", "db.internal").exists());
+
+    ctx.cli().arg("plan").assert().success().stdout(predicate::str::contains("No changes"));
+}
+
+#[test]
+#[serial]
+fn apply_updates_configs_without_touching_keys_and_prunes() {
+    let ctx = TestContext::new();
+    fs::write(ctx.work_dir().join("ssv.toml"), MANIFEST).unwrap();
+    ctx.cli().arg("apply").assert().success();
+    ctx.cli().args(["generate", "--host", "stray.test"]).assert().success();
+
+    let key = ctx.private_key_path("ed25519", "work-github");
+    fs::write(&key, "ORIGINAL-KEY").unwrap();
+    fs::write(
+        ctx.work_dir().join("ssv.toml"),
+        MANIFEST.replace("user = \"git\"", "user = \"bot\""),
+    )
+    .unwrap();
+
+    ctx.cli()
+        .args(["plan", "--prune"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("~ work-github"))
+        .stdout(predicate::str::contains("-User git"))
+        .stdout(predicate::str::contains("+User bot"))
+        .stdout(predicate::str::contains("- stray.test"));
+
+    ctx.cli()
+        .args(["apply", "--prune"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Applied: 0 created, 1 updated, 1 pruned"));
+
+    ctx.assert_config_contains("work-github", "User bot");
+    assert_eq!(fs::read_to_string(&key).unwrap(), "ORIGINAL-KEY");
+    assert!(!ctx.host_config_path("stray.test").exists());
+}
+
+#[test]
+#[serial]
+fn generate_accepts_hostname_and_extra_options() {
+    let ctx = TestContext::new();
+
+    ctx.cli()
+        .args(["generate", "--host", "alias.test", "--hostname", "real.example.com"])
+        .args(["--option", "ForwardAgent=no"])
+        .assert()
+        .success();
+    ctx.assert_config_contains("alias.test", "HostName real.example.com");
+    ctx.assert_config_contains("alias.test", "ForwardAgent no");
+
+    ctx.cli()
+        .args(["generate", "--host", "bad.test", "--option", "Match=all"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be set on a managed host block"));
+}
+
+#[test]
+#[serial]
+fn apply_keeps_proxy_jump_and_directives_the_manifest_does_not_mention() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "bastion"]).assert().success();
+    ctx.cli()
+        .args(["generate", "--host", "db", "--via", "bastion", "--option", "ForwardAgent=no"])
+        .assert()
+        .success();
+    let manifest = ctx.work_dir().join("ssv.toml");
+    fs::write(&manifest, "[[host]]\nalias = \"bastion\"\n\n[[host]]\nalias = \"db\"\n").unwrap();
+
+    ctx.cli().arg("plan").assert().success().stdout(predicate::str::contains("No changes"));
+
+    fs::write(
+        &manifest,
+        "[[host]]\nalias = \"bastion\"\n\n[[host]]\nalias = \"db\"\nuser = \"ops\"\n",
+    )
+    .unwrap();
+    ctx.cli()
+        .arg("apply")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+User ops"))
+        .stdout(predicate::str::contains("-ProxyJump").not());
+    ctx.assert_config_contains("db", "ProxyJump bastion");
+    ctx.assert_config_contains("db", "ForwardAgent no");
+    ctx.assert_config_contains("db", "User ops");
+}
+
+#[test]
+#[serial]
+fn manifest_via_sets_proxy_jump_and_metadata() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "bastion"]).assert().success();
+    ctx.cli().args(["generate", "--host", "db"]).assert().success();
+    fs::write(
+        ctx.work_dir().join("ssv.toml"),
+        "[[host]]\nalias = \"bastion\"\n\n[[host]]\nalias = \"db\"\nvia = \"bastion\"\n",
+    )
+    .unwrap();
+
+    ctx.cli()
+        .arg("apply")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+ProxyJump bastion"));
+    ctx.assert_config_contains("db", "ProxyJump bastion");
+    let meta = fs::read_to_string(ctx.home().join(".ssh/.ssv/meta/db.json")).unwrap();
+    assert!(meta.contains("\"via\": \"bastion\""), "{meta}");
+}
+
+#[test]
+#[serial]
+fn users_and_ssv_written_options_cannot_inject_directives() {
+    let ctx = TestContext::new();
+    ctx.cli()
+        .args(["generate", "--host", "db", "--user", "git\nProxyCommand touch /tmp/pwned"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid user"));
+    ctx.cli()
+        .args(["generate", "--host", "db", "--option", "Port=2222"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("written by ssv itself"));
+    assert!(!ctx.host_config_path("db").exists());
+
+    let manifest = ctx.work_dir().join("ssv.toml");
+    fs::write(&manifest, "[[host]]\nalias = \"db\"\nuser = \"git\\nProxyCommand nc\"\n").unwrap();
+    ctx.cli().arg("plan").assert().failure().stderr(predicate::str::contains("invalid user"));
+    fs::write(&manifest, "[[host]]\nalias = \"db\"\noptions = { User = \"root\" }\n").unwrap();
+    ctx.cli()
+        .arg("apply")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("option 'User' is written by ssv itself"));
+    assert!(!ctx.host_config_path("db").exists());
+
+    ctx.cli()
+        .args(["config", "set", "users.*.example.com", "git ops"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid user"));
+}
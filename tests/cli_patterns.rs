@@ -0,0 +1,73 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+
+#[test]
+#[serial]
+fn pattern_entry_is_managed_under_its_slug() {
+    let ctx = TestContext::new();
+    let slug = "wildcard-internal.example.com";
+
+    ctx.cli()
+        .args(["generate", "--pattern", "*.internal.example.com", "--user", "ops"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Generated SSH assets for '{slug}'")));
+
+    let config = fs::read_to_string(ctx.host_config_path(slug)).unwrap();
+    assert!(config.starts_with("# Managed by ssv "));
+    assert!(config.contains("\nHost *.internal.example.com\nUser ops\n"));
+    assert!(!config.contains("HostName"));
+    assert!(ctx.private_key_path("ed25519", slug).exists());
+
+    ctx.cli().arg("list").assert().success().stdout(format!("{slug}\n"));
+    ctx.cli()
+        .args(["show", "--host", slug])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("patterns: *.internal.example.com"));
+    ctx.cli()
+        .args(["test", "--host", slug])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is a pattern entry"));
+
+    ctx.cli().args(["remove", "--host", slug]).assert().success();
+    assert!(!ctx.host_config_path(slug).exists());
+    assert!(!ctx.private_key_path("ed25519", slug).exists());
+}
+
+#[test]
+#[serial]
+fn pattern_list_can_be_named_explicitly() {
+    let ctx = TestContext::new();
+
+    ctx.cli()
+        .args(["generate", "--host", "lab", "--pattern", "lab-*", "--pattern", "!lab-gw"])
+        .assert()
+        .success();
+
+    ctx.assert_config_contains("lab", "Host lab-* !lab-gw\n");
+    assert!(ctx.private_key_path("ed25519", "lab").exists());
+}
+
+#[test]
+#[serial]
+fn invalid_patterns_are_rejected() {
+    let ctx = TestContext::new();
+
+    ctx.cli()
+        .args(["generate", "--host", "bad", "--pattern", "a b"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid host pattern 'a b'"));
+    ctx.cli()
+        .args(["generate", "--host", "bad", "--pattern", "!only"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must not be negated"));
+    assert!(!ctx.host_config_path("bad").exists());
+}
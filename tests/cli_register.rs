@@ -0,0 +1,267 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A request seen by the stub provider API.
+#[derive(Debug, Clone)]
+struct Seen {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Seen {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Minimal `/user/keys` API: GET returns the `page`th of `pages` (an empty list past the end),
+/// POST creates key 42, DELETE succeeds.
+struct StubApi {
+    url: String,
+    seen: Arc<Mutex<Vec<Seen>>>,
+}
+
+impl StubApi {
+    fn start(existing: &str) -> Self {
+        Self::paged(vec![existing.to_string()])
+    }
+
+    fn paged(pages: Vec<String>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let path = parts.next().unwrap_or_default().to_string();
+
+                let mut headers = Vec::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    if let Some((key, value)) = header.split_once(':') {
+                        headers.push((key.trim().to_string(), value.trim().to_string()));
+                    }
+                }
+                let length = headers
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+                    .map_or(0, |(_, value)| value.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let body = String::from_utf8(body).unwrap();
+
+                let (status, response) = match method.as_str() {
+                    "GET" => {
+                        let page: usize = path
+                            .split(['?', '&'])
+                            .find_map(|param| param.strip_prefix("page="))
+                            .map_or(1, |page| page.parse().unwrap());
+                        ("200 OK", pages.get(page - 1).cloned().unwrap_or("[]".into()))
+                    }
+                    "POST" => {
+                        let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+                        let created = serde_json::json!({
+                            "id": 42,
+                            "title": request["title"],
+                            "key": request["key"],
+                        });
+                        ("201 Created", created.to_string())
+                    }
+                    _ => ("204 No Content", String::new()),
+                };
+                log.lock().unwrap().push(Seen { method, path, headers, body });
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                    response.len()
+                )
+                .unwrap();
+            }
+        });
+        StubApi { url, seen }
+    }
+
+    fn requests(&self) -> Vec<Seen> {
+        self.seen.lock().unwrap().clone()
+    }
+}
+
+fn register(ctx: &TestContext, api: &StubApi, provider: &str) -> assert_cmd::Command {
+    let mut cmd = ctx.cli();
+    cmd.args(["register", "--host", "work", "--provider", provider])
+        .args(["--token-env", "SSV_TEST_TOKEN", "--api-url", &api.url])
+        .env("SSV_TEST_TOKEN", "secret");
+    cmd
+}
+
+#[test]
+#[serial]
+fn register_uploads_key_and_remove_deletes_it() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "work"]).assert().success();
+    let api = StubApi::start("[]");
+
+    register(&ctx, &api, "github")
+        .args(["--title", "laptop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Registered 'work' with github as key 42 \"laptop\""));
+
+    let requests = api.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        (requests[0].method.as_str(), requests[0].path.as_str()),
+        ("GET", "/user/keys?per_page=50&page=1")
+    );
+    assert_eq!((requests[1].method.as_str(), requests[1].path.as_str()), ("POST", "/user/keys"));
+    assert_eq!(requests[1].header("Authorization"), Some("Bearer secret"));
+    let body: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+    assert_eq!(body, serde_json::json!({ "title": "laptop", "key": "ssh-ed25519 AAAATESTKEY" }));
+
+    ctx.cli().args(["show", "--host", "work"]).assert().success().stdout(predicate::str::contains(
+        format!("registered: github key 42 \"laptop\" ({})", api.url),
+    ));
+
+    ctx.cli()
+        .args(["remove", "--host", "work", "--token-env", "github=SSV_TEST_TOKEN"])
+        .env("SSV_TEST_TOKEN", "secret")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deleted github key 42"));
+    let delete = api.requests().pop().unwrap();
+    assert_eq!((delete.method.as_str(), delete.path.as_str()), ("DELETE", "/user/keys/42"));
+    assert!(!ctx.host_config_path("work").exists());
+}
+
+#[test]
+#[serial]
+fn register_uses_provider_specific_auth_and_adopts_existing_keys() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "work"]).assert().success();
+    let api = StubApi::start(r#"[{"id":7,"title":"old","key":"ssh-ed25519 AAAATESTKEY old"}]"#);
+
+    register(&ctx, &api, "gitlab")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("as key 7 \"old\""));
+    let requests = api.requests();
+    assert_eq!(requests.len(), 1, "an existing key must not be uploaded again");
+    assert_eq!(requests[0].header("PRIVATE-TOKEN"), Some("secret"));
+
+    register(&ctx, &api, "gitea").assert().success();
+    assert_eq!(api.requests()[1].header("Authorization"), Some("token secret"));
+    assert_eq!(api.requests()[1].path, "/user/keys?limit=50&page=1");
+
+    register(&ctx, &api, "gitlab")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already registered with gitlab as key 7"));
+}
+
+#[test]
+#[serial]
+fn remove_without_token_reports_remaining_registration() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "work"]).assert().success();
+    let api = StubApi::start("[]");
+    register(&ctx, &api, "github").assert().success();
+
+    ctx.cli()
+        .args(["remove", "--host", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Key 42"))
+        .stdout(predicate::str::contains("still registered with github"));
+    assert!(api.requests().iter().all(|request| request.method != "DELETE"));
+}
+
+#[test]
+#[serial]
+fn register_finds_existing_keys_on_later_pages() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "work"]).assert().success();
+    let others: Vec<_> = (1..=50)
+        .map(|id| serde_json::json!({ "id": id, "title": "other", "key": format!("ssh-ed25519 AAAA{id}") }))
+        .collect();
+    let api = StubApi::paged(vec![
+        serde_json::Value::from(others).to_string(),
+        r#"[{"id":77,"title":"old","key":"ssh-ed25519 AAAATESTKEY"}]"#.to_string(),
+    ]);
+
+    register(&ctx, &api, "github").assert().success().stdout(predicate::str::contains("as key 77"));
+    let paths: Vec<_> = api.requests().into_iter().map(|request| request.path).collect();
+    assert_eq!(paths, ["/user/keys?per_page=50&page=1", "/user/keys?per_page=50&page=2"]);
+}
+
+#[test]
+#[serial]
+fn remove_deletes_registrations_only_for_providers_with_a_token() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "work"]).assert().success();
+    let api = StubApi::start("[]");
+    register(&ctx, &api, "github").assert().success();
+    register(&ctx, &api, "gitea").assert().success();
+
+    ctx.cli()
+        .args(["remove", "--host", "work", "--yes", "--token-env", "gitea=SSV_GITEA_TOKEN"])
+        .env("SSV_GITEA_TOKEN", "tea")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deleted gitea key 42"))
+        .stdout(predicate::str::contains("still registered with github"))
+        .stdout(predicate::str::contains("--token-env github=VAR"));
+    let deletes: Vec<_> =
+        api.requests().into_iter().filter(|request| request.method == "DELETE").collect();
+    assert_eq!(deletes.len(), 1);
+    assert_eq!(deletes[0].header("Authorization"), Some("token tea"));
+
+    ctx.cli()
+        .args(["remove", "--host", "work", "--token-env", "SSV_TEST_TOKEN"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected PROVIDER=VAR"));
+}
+
+#[test]
+#[serial]
+fn register_requires_token_variable() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "work"]).assert().success();
+
+    ctx.cli()
+        .args(["register", "--host", "work", "--provider", "github"])
+        .args(["--token-env", "SSV_TEST_MISSING_TOKEN"])
+        .env_remove("SSV_TEST_MISSING_TOKEN")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SSV_TEST_MISSING_TOKEN is not set"));
+    ctx.cli()
+        .args(["register", "--host", "work", "--provider", "bitbucket", "--token-env", "X"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown provider 'bitbucket'"));
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+
+fn inventory(ctx: &TestContext) {
+    ctx.cli().args(["generate", "--host", "git.example", "--user", "git"]).assert().success();
+    ctx.cli()
+        .args(["generate", "--host", "web.example", "--type", "rsa", "--user", "deploy"])
+        .assert()
+        .success();
+    ctx.cli().args(["generate", "--host", "db.staging.example"]).assert().success();
+    ctx.cli()
+        .args(["generate", "--host", "api.staging.example", "--port", "2222"])
+        .assert()
+        .success();
+}
+
+fn backdate(ctx: &TestContext, host: &str) {
+    let path = ctx.home().join(format!(".ssh/.ssv/meta/{host}.json"));
+    let mut meta: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    meta["created_at"] = "2020-01-01T00:00:00Z".into();
+    fs::write(&path, meta.to_string()).unwrap();
+}
+
+#[test]
+#[serial]
+fn list_filters_by_glob_and_attribute() {
+    let ctx = TestContext::new();
+    inventory(&ctx);
+
+    ctx.cli().args(["list", "--filter", "user=git"]).assert().success().stdout("git.example\n");
+    ctx.cli()
+        .args(["ls", "--filter", "type=rsa", "--filter", "user=deploy"])
+        .assert()
+        .success()
+        .stdout("web.example\n");
+    ctx.cli()
+        .args(["list", "--match", "*.staging.example"])
+        .assert()
+        .success()
+        .stdout("api.staging.example\ndb.staging.example\n");
+    ctx.cli()
+        .args(["list", "--match", "*.staging.example", "--filter", "port=2222"])
+        .assert()
+        .success()
+        .stdout("api.staging.example\n");
+    ctx.cli()
+        .args(["list", "--filter", "user=nobody"])
+        .assert()
+        .success()
+        .stdout("(no hosts match)\n");
+    ctx.cli()
+        .args(["list", "--filter", "colour=red"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown filter 'colour'"));
+}
+
+#[test]
+#[serial]
+fn remove_match_deletes_every_matching_host() {
+    let ctx = TestContext::new();
+    inventory(&ctx);
+
+    ctx.cli()
+        .args(["remove", "--match", "*.staging.example"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed SSH assets for 'api.staging.example'"))
+        .stdout(predicate::str::contains("Removed SSH assets for 'db.staging.example'"))
+        .stdout(predicate::str::contains("Removed 2 of 2 host(s)"));
+
+    assert!(!ctx.host_config_path("db.staging.example").exists());
+    assert!(!ctx.private_key_path("ed25519", "api.staging.example").exists());
+    assert!(ctx.host_config_path("git.example").exists());
+    assert!(ctx.host_config_path("web.example").exists());
+
+    ctx.cli()
+        .args(["remove", "--match", "*.staging.example"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No managed hosts match; nothing removed."));
+}
+
+#[test]
+#[serial]
+fn rotate_older_than_only_touches_old_keys() {
+    let ctx = TestContext::new();
+    inventory(&ctx);
+    backdate(&ctx, "git.example");
+    backdate(&ctx, "web.example");
+
+    ctx.cli()
+        .args(["rotate", "--older-than", "180d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Rotated the key of 'git.example'"))
+        .stdout(predicate::str::contains("Rotated the key of 'web.example'"))
+        .stdout(predicate::str::contains("staging").not())
+        .stdout(predicate::str::contains("Rotated 2 of 2 host(s)"));
+
+    ctx.cli()
+        .args(["rotate", "--older-than", "180d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No managed hosts match; nothing rotated."));
+    ctx.cli()
+        .args(["rotate", "--older-than", "soon"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid age 'soon'"));
+}
+
+#[test]
+#[serial]
+fn show_and_export_accept_selections() {
+    let ctx = TestContext::new();
+    inventory(&ctx);
+
+    let output = ctx
+        .cli()
+        .args(["show", "--match", "*.staging.example", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let details: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let hosts: Vec<&str> =
+        details.as_array().unwrap().iter().map(|d| d["host"].as_str().unwrap()).collect();
+    assert_eq!(hosts, ["api.staging.example", "db.staging.example"]);
+
+    let bundle = ctx.work_dir().join("staging.ssvb");
+    ctx.cli()
+        .env("SSV_TEST_PASSPHRASE", "correct horse")
+        .args(["export", "--out", bundle.to_str().unwrap(), "--filter", "hostname=*.staging.*"])
+        .args(["--passphrase-env", "SSV_TEST_PASSPHRASE"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 2 host(s)"));
+    assert!(bundle.exists());
+
+    ctx.cli()
+        .args(["export", "--out", bundle.to_str().unwrap(), "--match", "nothing.*"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nothing exported"));
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+
+const ED25519: &str =
+    "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAlUFMG3D4/ol4Gm9aH5E4iBjHU7jyNJlXygA4pfxqbB fix\n";
+
+#[test]
+#[serial]
+fn show_command_reports_config_keys_and_metadata() {
+    let ctx = TestContext::new();
+    ctx.cli()
+        .args(["generate", "--host", "detail.test", "--user", "git", "--purpose", "docs"])
+        .assert()
+        .success();
+    fs::write(ctx.public_key_path("ed25519", "detail.test"), ED25519).unwrap();
+    fs::write(ctx.home().join(".ssh/known_hosts"), ED25519.replace("ssh-", "detail.test ssh-"))
+        .unwrap();
+
+    ctx.cli()
+        .args(["show", "--host", "detail.test"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("User git"))
+        .stdout(predicate::str::contains("private     0600"))
+        .stdout(predicate::str::contains(
+            "Fingerprint: SHA256:fXVpHc3g9fMAmGAWKzgVfcBkd1R0ZtIIy/KRNQmxVrA",
+        ))
+        .stdout(predicate::str::contains("purpose: docs"));
+
+    let output =
+        ctx.cli().args(["show", "--host", "detail.test", "--format", "json"]).assert().success();
+    let details: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(details["config"][1]["key"], "HostName");
+    assert_eq!(details["keys"][0]["role"], "private");
+    assert_eq!(details["known_hosts"].as_array().map(Vec::len), Some(1));
+    assert_eq!(details["metadata"]["purpose"], "docs");
+}
+
+#[test]
+#[serial]
+fn show_command_fails_for_unknown_host() {
+    let ctx = TestContext::new();
+
+    ctx.cli()
+        .args(["show", "--host", "missing.test"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Host 'missing.test' was not found"));
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use std::fs;
+
+#[test]
+#[serial]
+fn generate_applies_builtin_template() {
+    let ctx = TestContext::new();
+
+    ctx.cli()
+        .args(["generate", "--host", "work-github", "--template", "github"])
+        .assert()
+        .success();
+
+    ctx.assert_config_contains("work-github", "Host work-github");
+    ctx.assert_config_contains("work-github", "HostName github.com");
+    ctx.assert_config_contains("work-github", "User git");
+}
+
+#[test]
+#[serial]
+fn user_templates_fill_placeholders_and_yield_to_flags() {
+    let ctx = TestContext::new();
+    let dir = ctx.home().join(".config/ssv/templates");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("corp.conf"),
+        "# Corp box behind the jump host\nHostName {alias}.corp.example\nUser ops\n\
+         ProxyJump {user}@jump.corp.example\n",
+    )
+    .unwrap();
+
+    ctx.cli()
+        .args(["template", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("corp"))
+        .stdout(predicate::str::contains("Corp box behind the jump host"))
+        .stdout(predicate::str::contains("github"));
+
+    ctx.cli()
+        .args(["generate", "--host", "db1", "--template", "corp", "--user", "deploy"])
+        .assert()
+        .success();
+
+    ctx.assert_config_contains("db1", "HostName db1.corp.example");
+    ctx.assert_config_contains("db1", "User deploy");
+    ctx.assert_config_contains("db1", "ProxyJump deploy@jump.corp.example");
+}
+
+#[test]
+#[serial]
+fn generate_rejects_unknown_template() {
+    let ctx = TestContext::new();
+
+    ctx.cli()
+        .args(["generate", "--host", "db1", "--template", "missing"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown template 'missing'"));
+    assert!(!ctx.host_config_path("db1").exists());
+}
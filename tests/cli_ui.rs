@@ -0,0 +1,17 @@
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn ui_requires_a_terminal() {
+    let ctx = TestContext::new();
+
+    ctx.cli()
+        .arg("ui")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("needs an interactive terminal"));
+}
@@ -2,7 +2,11 @@ mod common;
 
 use common::TestContext;
 use serial_test::serial;
-use ssv::{generate, list, remove};
+use ssv::error::AppError;
+use ssv::{
+    GenerateOptions, RemoveOptions, generate, generate_with, list, metadata, removal_plan, remove,
+    remove_with,
+};
 use std::fs;
 
 #[test]
@@ -51,6 +55,28 @@ fn remove_deletes_assets_via_library_api() {
     assert!(!ctx.private_key_path("ed25519", "remove.test").exists());
 }
 
+#[test]
+#[serial]
+fn removal_plan_lists_existing_files() {
+    let ctx = TestContext::new();
+
+    let plan = ctx.with_dir(ctx.work_dir(), || {
+        generate("plan.test", "ed25519", None, None).unwrap();
+        removal_plan("plan.test").unwrap()
+    });
+
+    assert!(!plan.guessed);
+    assert_eq!(
+        plan.files,
+        vec![
+            ctx.host_config_path("plan.test"),
+            ctx.home().join(".ssh/.ssv/meta/plan.test.json"),
+            ctx.private_key_path("ed25519", "plan.test"),
+            ctx.public_key_path("ed25519", "plan.test"),
+        ]
+    );
+}
+
 #[test]
 #[serial]
 fn remove_does_not_delete_other_hosts_when_guessing() {
@@ -64,7 +90,8 @@ fn remove_does_not_delete_other_hosts_when_guessing() {
     assert!(other_key.exists(), "expected foo.bar.com key to exist before removal");
 
     ctx.with_dir(ctx.work_dir(), || {
-        remove("bar.com").expect("remove should tolerate missing config");
+        let err = remove("bar.com").expect_err("bar.com has nothing to remove");
+        assert!(matches!(err, AppError::HostNotFound(ref host) if host == "bar.com"), "{err}");
     });
 
     assert!(other_key.exists(), "remove for bar.com should not delete foo.bar.com key");
@@ -89,9 +116,31 @@ fn remove_ignores_identity_files_outside_ssh_root() {
     .expect("failed to write config");
 
     ctx.with_dir(ctx.work_dir(), || {
-        remove("danger").expect("remove should succeed");
+        // Hand-written, so only a forced removal deletes it.
+        let options = RemoveOptions { force_foreign: true, ..Default::default() };
+        remove_with("danger", &options).expect("remove should succeed");
     });
 
     assert!(outside.exists(), "outside identity file should not be removed");
     assert!(!config_path.exists(), "config file should be removed");
 }
+
+#[test]
+#[serial]
+fn metadata_sidecar_follows_host_lifecycle() {
+    let ctx = TestContext::new();
+
+    ctx.with_dir(ctx.work_dir(), || {
+        let mut options = GenerateOptions::new("sidecar.test");
+        options.purpose = Some("ci".to_string());
+        generate_with(&options).unwrap();
+
+        let meta = metadata("sidecar.test").unwrap().expect("metadata should be recorded");
+        assert_eq!(meta.purpose.as_deref(), Some("ci"));
+        assert!(meta.created_at.is_some());
+        assert!(meta.creator.is_some());
+
+        remove("sidecar.test").unwrap();
+        assert!(metadata("sidecar.test").unwrap().is_none());
+    });
+}
@@ -1,6 +1,7 @@
 //! Shared testing utilities for the `ssv` CLI and library.
 
 use assert_cmd::Command;
+use ssv::{KeyBackend, SshPaths, Ssv};
 use std::env;
 use std::ffi::OsString;
 use std::fs;
@@ -10,40 +11,58 @@ use tempfile::TempDir;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
-/// Testing harness providing an isolated HOME/workspace pair and stubbed ssh-keygen.
+/// Testing harness providing an isolated HOME/workspace pair, the test key backend and a stubbed
+/// ssh.
 #[allow(dead_code)]
 pub struct TestContext {
     root: TempDir,
     work_dir: PathBuf,
     original_home: Option<OsString>,
-    original_keygen: Option<OsString>,
-    keygen_stub: PathBuf,
+    original_backend: Option<OsString>,
+    original_ssh: Option<OsString>,
+    ssh_stub: PathBuf,
+    /// Whether `HOME` and the program overrides were replaced and need restoring on drop.
+    overrides_env: bool,
 }
 
 #[allow(dead_code)]
 impl TestContext {
     /// Create a new isolated environment and point `HOME` to it so the CLI uses local storage.
     pub fn new() -> Self {
+        let mut ctx = Self::isolated();
+        ctx.original_home = env::var_os("HOME");
+        ctx.original_backend = env::var_os("SSV_BACKEND");
+        ctx.original_ssh = env::var_os("SSV_SSH_PATH");
+        ctx.overrides_env = true;
+        unsafe {
+            env::set_var("HOME", ctx.home());
+            env::set_var("SSV_BACKEND", KeyBackend::Test.as_str());
+            env::set_var("SSV_SSH_PATH", &ctx.ssh_stub);
+        }
+        ctx
+    }
+
+    /// Create the same layout without touching the process environment; reach it through
+    /// [`TestContext::ssv`] or [`TestContext::cli`].
+    pub fn isolated() -> Self {
         let root = TempDir::new().expect("Failed to create temp directory for tests");
         let work_dir = root.path().join("work");
         fs::create_dir_all(&work_dir).expect("Failed to create test work directory");
 
         let bin_dir = root.path().join("bin");
         fs::create_dir_all(&bin_dir).expect("Failed to create stub bin directory");
-        let keygen_stub = bin_dir.join("ssh-keygen");
-        Self::write_keygen_stub(&keygen_stub);
-
-        let original_home = env::var_os("HOME");
-        unsafe {
-            env::set_var("HOME", root.path());
+        let ssh_stub = bin_dir.join("ssh");
+        Self::write_ssh_stub(&ssh_stub);
+
+        Self {
+            root,
+            work_dir,
+            original_home: None,
+            original_backend: None,
+            original_ssh: None,
+            ssh_stub,
+            overrides_env: false,
         }
-
-        let original_keygen = env::var_os("SSV_SSH_KEYGEN_PATH");
-        unsafe {
-            env::set_var("SSV_SSH_KEYGEN_PATH", &keygen_stub);
-        }
-
-        Self { root, work_dir, original_home, original_keygen, keygen_stub }
     }
 
     /// Absolute path to the emulated `$HOME` directory.
@@ -66,10 +85,19 @@ impl TestContext {
         let mut cmd = Command::cargo_bin("ssv").expect("Failed to locate ssv binary");
         cmd.current_dir(dir.as_ref())
             .env("HOME", self.home())
-            .env("SSV_SSH_KEYGEN_PATH", &self.keygen_stub);
+            .env("SSV_BACKEND", KeyBackend::Test.as_str())
+            .env("SSV_SSH_PATH", &self.ssh_stub)
+            .env_remove("SSV_VAULT_PASSPHRASE")
+            .env_remove("XDG_CONFIG_HOME");
         cmd
     }
 
+    /// Library handle on the emulated home, using the test key backend and the stubbed ssh.
+    pub fn ssv(&self) -> Ssv {
+        Ssv::with_paths(SshPaths::new(self.home()).with_ssh(&self.ssh_stub))
+            .with_backend(KeyBackend::Test)
+    }
+
     /// Path to the configuration file generated for a host.
     pub fn host_config_path(&self, host: &str) -> PathBuf {
         self.home().join(".ssh").join("conf.d").join(format!("{host}.conf"))
@@ -94,43 +122,27 @@ impl TestContext {
         );
     }
 
-    fn write_keygen_stub(path: &Path) {
+    /// Path of the file where the ssh stub appends its argument list, one invocation per line.
+    pub fn ssh_log_path(&self) -> PathBuf {
+        self.home().join("ssh-invocations.log")
+    }
+
+    /// Write an ssh stand-in that logs its arguments and replays `SSH_STUB_OUTPUT` on stderr
+    /// before exiting with `SSH_STUB_EXIT` (default 0). When `SSH_STUB_REMOTE_HOME` names a
+    /// directory, the last argument is run there with `sh -c`, standing in for the remote shell.
+    fn write_ssh_stub(path: &Path) {
         let script = r#"#!/usr/bin/env sh
-set -eu
-outfile=""
-keytype="stub"
-while [ "$#" -gt 0 ]; do
-  arg="$1"
-  shift
-  case "$arg" in
-    -f)
-      if [ "$#" -eq 0 ]; then
-        echo "missing -f argument" >&2
-        exit 1
-      fi
-      outfile="$1"
-      shift
-      ;;
-    -t)
-      if [ "$#" -eq 0 ]; then
-        echo "missing -t argument" >&2
-        exit 1
-      fi
-      keytype="$1"
-      shift
-      ;;
-    *)
-      ;;
-  esac
-done
-if [ -z "$outfile" ]; then
-  echo "missing -f argument" >&2
-  exit 1
+printf '%s\n' "$*" >> "$HOME/ssh-invocations.log"
+if [ -n "${SSH_STUB_REMOTE_HOME:-}" ]; then
+  for remote_command; do :; done
+  (cd "$SSH_STUB_REMOTE_HOME" && sh -c "$remote_command") || exit $?
 fi
-printf 'PRIVATE-%s\n' "$keytype" > "$outfile"
-printf 'ssh-%s AAAATESTKEY %s@ssv\n' "$keytype" "$keytype" > "${outfile}.pub"
+if [ -n "${SSH_STUB_OUTPUT:-}" ]; then
+  printf '%s\n' "$SSH_STUB_OUTPUT" >&2
+fi
+exit "${SSH_STUB_EXIT:-0}"
 "#;
-        fs::write(path, script).expect("Failed to create ssh-keygen stub");
+        fs::write(path, script).expect("Failed to create ssh stub");
         #[cfg(unix)]
         {
             let mut perms = fs::metadata(path).expect("stub metadata").permissions();
@@ -155,6 +167,9 @@ printf 'ssh-%s AAAATESTKEY %s@ssv\n' "$keytype" "$keytype" > "${outfile}.pub"
 
 impl Drop for TestContext {
     fn drop(&mut self) {
+        if !self.overrides_env {
+            return;
+        }
         match &self.original_home {
             Some(value) => unsafe {
                 env::set_var("HOME", value);
@@ -164,12 +179,21 @@ impl Drop for TestContext {
             },
         }
 
-        match &self.original_keygen {
+        match &self.original_backend {
+            Some(value) => unsafe {
+                env::set_var("SSV_BACKEND", value);
+            },
+            None => unsafe {
+                env::remove_var("SSV_BACKEND");
+            },
+        }
+
+        match &self.original_ssh {
             Some(value) => unsafe {
-                env::set_var("SSV_SSH_KEYGEN_PATH", value);
+                env::set_var("SSV_SSH_PATH", value);
             },
             None => unsafe {
-                env::remove_var("SSV_SSH_KEYGEN_PATH");
+                env::remove_var("SSV_SSH_PATH");
             },
         }
     }
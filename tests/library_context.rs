@@ -0,0 +1,78 @@
+//! `Ssv` works on an explicit home, so these tests leave the process environment alone and run
+//! in parallel.
+
+mod common;
+
+use common::TestContext;
+use ssv::{GenerateOptions, RemoveOptions, SshPaths, Ssv};
+
+#[test]
+fn generate_and_list_use_the_explicit_home() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+
+    let mut options = GenerateOptions::new("db.test");
+    options.hostname = Some("10.0.0.5".into());
+    options.user = Some("deploy".into());
+    options.port = Some(2222);
+    let generated = ssv.generate(&options).unwrap();
+
+    assert_eq!(generated.host, "db.test");
+    assert_eq!(generated.config_path, ctx.host_config_path("db.test"));
+    assert_eq!(generated.private_key, ctx.private_key_path("ed25519", "db.test"));
+    assert_eq!(generated.public_key_path, ctx.public_key_path("ed25519", "db.test"));
+    assert!(generated.public_key.starts_with("ssh-ed25519 AAAATESTKEY"));
+
+    let entries = ssv.list().unwrap();
+    assert_eq!(entries.len(), 1);
+    let entry = &entries[0];
+    assert_eq!(entry.host, "db.test");
+    assert_eq!(entry.patterns, ["db.test"]);
+    assert_eq!(entry.hostname.as_deref(), Some("10.0.0.5"));
+    assert_eq!(entry.user.as_deref(), Some("deploy"));
+    assert_eq!(entry.port, Some(2222));
+    assert_eq!(entry.via, None);
+    assert_eq!(entry.identity_file.as_ref(), Some(&generated.private_key));
+
+    assert_eq!(ssv.hosts().unwrap(), ["db.test"]);
+    assert!(ssv.metadata("db.test").unwrap().unwrap().created_at.is_some());
+    assert_eq!(ssv.show("db.test").unwrap().config_path, generated.config_path);
+}
+
+#[test]
+fn remove_returns_a_report_instead_of_printing() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    ssv.generate(&GenerateOptions::new("gone.test")).unwrap();
+
+    let plan = ssv.removal_plan("gone.test").unwrap();
+    assert!(plan.files.contains(&ctx.private_key_path("ed25519", "gone.test")));
+
+    let report = ssv.remove("gone.test", &RemoveOptions::default()).unwrap();
+    assert!(report.dependents.is_empty());
+    assert!(!report.still_deployed);
+    assert!(plan.files.iter().all(|path| !path.exists()));
+    assert!(ssv.hosts().unwrap().is_empty());
+}
+
+#[test]
+fn contexts_are_independent() {
+    let first = TestContext::isolated();
+    let second = TestContext::isolated();
+    first.ssv().generate(&GenerateOptions::new("one.test")).unwrap();
+
+    assert_eq!(first.ssv().hosts().unwrap(), ["one.test"]);
+    assert!(second.ssv().hosts().unwrap().is_empty());
+}
+
+#[test]
+fn settings_follow_the_configured_directory() {
+    let ctx = TestContext::isolated();
+    let config_dir = ctx.work_dir().join("ssv-config");
+    let ssv = Ssv::with_paths(SshPaths::new(ctx.home()).with_config_dir(&config_dir));
+
+    ssv.config_set("key_type", "rsa").unwrap();
+    assert!(config_dir.join("config.toml").exists());
+    assert_eq!(ssv.settings().unwrap().key_type.as_deref(), Some("rsa"));
+    assert_eq!(Ssv::new(ctx.home()).settings().unwrap().key_type, None);
+}
@@ -0,0 +1,155 @@
+//! The change log in `~/.ssh/.ssv/log.jsonl` and `ssv history`.
+
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use ssv::{Adopt, GenerateOptions, KeyBackend, Operation, RemoveOptions};
+use std::fs;
+
+#[test]
+fn lifecycle_operations_are_logged_in_order() {
+    let ctx = TestContext::isolated();
+    // Real keys, so that every entry carries a fingerprint.
+    let ssv = ctx.ssv().with_backend(KeyBackend::Native);
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+    ssv.generate(&GenerateOptions::new("web")).unwrap();
+    let generated = ssv.show("db").unwrap().fingerprint;
+    ssv.rotate("db").unwrap();
+    ssv.record_edit("db").unwrap();
+    ssv.remove("db", &RemoveOptions::default()).unwrap();
+
+    let entries = ssv.history(Some("db")).unwrap();
+    let operations: Vec<_> = entries.iter().map(|entry| entry.operation).collect();
+    assert_eq!(
+        operations,
+        [Operation::Generate, Operation::Rotate, Operation::Edit, Operation::Remove]
+    );
+    assert!(generated.is_some());
+    assert_eq!(entries[0].fingerprint, generated);
+    assert_ne!(entries[1].fingerprint, generated);
+    assert_eq!(entries[2].fingerprint, entries[1].fingerprint);
+    assert_eq!(entries[3].fingerprint, entries[1].fingerprint);
+    assert!(entries[0].files.contains(&ctx.private_key_path("ed25519", "db")));
+    assert!(entries[0].files.contains(&ctx.host_config_path("db")));
+    assert_eq!(entries[2].files, [ctx.host_config_path("db")]);
+    assert!(entries[3].files.contains(&ctx.public_key_path("ed25519", "db")));
+    assert!(entries.iter().all(|entry| !entry.user.is_empty() && entry.timestamp.ends_with('Z')));
+
+    assert_eq!(ssv.history(None).unwrap().len(), 5);
+    assert!(ssv.history(Some("mail")).unwrap().is_empty());
+}
+
+#[test]
+fn removing_an_unknown_host_is_not_logged() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+    assert!(ssv.remove("ghost", &RemoveOptions::default()).is_err());
+
+    assert!(ssv.history(Some("ghost")).unwrap().is_empty());
+    assert_eq!(ssv.history(None).unwrap().len(), 1);
+}
+
+#[test]
+fn manifest_updates_adoptions_and_authorized_keys_are_logged() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv().with_backend(KeyBackend::Native);
+    let manifest = ctx.work_dir().join("ssv.toml");
+    fs::write(&manifest, "[[host]]\nalias = \"db\"\n").unwrap();
+    ssv.apply(&manifest, false).unwrap();
+    fs::write(&manifest, "[[host]]\nalias = \"db\"\nuser = \"admin\"\n").unwrap();
+    ssv.apply(&manifest, false).unwrap();
+    let config = ctx.host_config_path("db");
+    fs::write(&config, fs::read_to_string(&config).unwrap().replace("admin", "root")).unwrap();
+    ssv.doctor(&Adopt::Changed).unwrap();
+
+    let key = fs::read_to_string(ctx.public_key_path("ed25519", "db")).unwrap();
+    ssv.authorize("laptop", &key, &[]).unwrap();
+    ssv.unauthorize("laptop").unwrap();
+
+    let entries = ssv.history(None).unwrap();
+    let operations: Vec<_> =
+        entries.iter().map(|entry| (entry.operation, entry.host.as_str())).collect();
+    assert_eq!(
+        operations,
+        [
+            (Operation::Generate, "db"),
+            (Operation::Apply, "db"),
+            (Operation::Adopt, "db"),
+            (Operation::Authorize, "laptop"),
+            (Operation::Unauthorize, "laptop")
+        ]
+    );
+    assert_eq!(entries[1].files, entries[2].files);
+    assert_eq!(entries[2].files, [config]);
+    let fingerprint = ssv.show("db").unwrap().fingerprint;
+    assert!(fingerprint.is_some());
+    assert_eq!(entries[3].fingerprint, fingerprint);
+    assert_eq!(entries[4].fingerprint, fingerprint);
+    assert_eq!(entries[4].files, [ctx.home().join(".ssh/authorized_keys")]);
+}
+
+#[test]
+fn log_is_private_json_lines() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    assert!(ssv.history(None).unwrap().is_empty());
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+
+    let log = ctx.home().join(".ssh/.ssv/log.jsonl");
+    let contents = fs::read_to_string(&log).unwrap();
+    let record: serde_json::Value = serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+    assert_eq!(record["operation"], "generate");
+    assert_eq!(record["host"], "db");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(fs::metadata(&log).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    fs::write(&log, format!("{contents}not json\n")).unwrap();
+    let err = ssv.history(None).unwrap_err().to_string();
+    assert!(err.contains("log.jsonl:2"), "{err}");
+}
+
+#[test]
+fn imported_hosts_are_logged_per_host() {
+    let source = TestContext::isolated();
+    source.ssv().with_backend(KeyBackend::Native).generate(&GenerateOptions::new("db")).unwrap();
+    let bundle = source.work_dir().join("hosts.ssvb");
+    source.ssv().export(&bundle, &[], "pw").unwrap();
+
+    let target = TestContext::isolated();
+    target.ssv().import(&bundle, "pw", &[]).unwrap();
+    let entries = target.ssv().history(None).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].operation, Operation::Import);
+    assert!(entries[0].fingerprint.is_some());
+    assert_eq!(entries[0].fingerprint, source.ssv().show("db").unwrap().fingerprint);
+    assert!(entries[0].files.contains(&target.host_config_path("db")));
+}
+
+#[test]
+#[serial]
+fn history_command_filters_by_host() {
+    let ctx = TestContext::new();
+    ctx.cli().arg("history").assert().success().stdout("(no history recorded)\n");
+    ctx.cli().args(["generate", "--host", "db"]).assert().success();
+    ctx.cli().args(["generate", "--host", "web"]).assert().success();
+    ctx.cli().args(["remove", "--host", "web", "--yes"]).assert().success();
+
+    ctx.cli()
+        .args(["history", "--host", "web"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("generate    web"))
+        .stdout(predicate::str::contains("remove      web"))
+        .stdout(predicate::str::contains(" db ").not());
+
+    let output = ctx.cli().args(["history", "--format", "json"]).output().unwrap();
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(entries.as_array().unwrap().len(), 3);
+    assert_eq!(entries[2]["operation"], "remove");
+}
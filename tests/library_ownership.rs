@@ -0,0 +1,289 @@
+//! Ownership headers, the managed-file state and `ssv doctor`.
+
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use ssv::{Adopt, DriftKind, GenerateOptions, Ownership, RemoveOptions};
+use std::fs;
+
+fn write_foreign(ctx: &TestContext, host: &str) {
+    let path = ctx.host_config_path(host);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, format!("Host {host}\nHostName {host}.example\n")).unwrap();
+}
+
+#[test]
+fn generated_configs_are_stamped_and_tracked() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+
+    let config = fs::read_to_string(ctx.host_config_path("db")).unwrap();
+    let header = config.lines().next().unwrap();
+    assert!(header.starts_with("# Managed by ssv "), "{header}");
+    assert!(header.contains("; sha256="), "{header}");
+    let state = fs::read_to_string(ctx.home().join(".ssh/.ssv/managed.json")).unwrap();
+    assert!(state.contains(header.rsplit('=').next().unwrap()));
+
+    assert_eq!(ssv.ownership("db").unwrap(), Ownership::Managed);
+    assert_eq!(ssv.list().unwrap()[0].ownership, Ownership::Managed);
+    assert!(ssv.doctor(&Adopt::Nothing).unwrap().drift.is_empty());
+
+    ssv.remove("db", &RemoveOptions::default()).unwrap();
+    assert!(!state_mentions(&ctx, "db"));
+}
+
+#[test]
+fn foreign_configs_are_only_removed_with_force_foreign() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    write_foreign(&ctx, "legacy");
+    assert_eq!(ssv.ownership("legacy").unwrap(), Ownership::Foreign);
+
+    let err = ssv.remove("legacy", &RemoveOptions::default()).unwrap_err().to_string();
+    assert!(err.contains("was not written by ssv"), "{err}");
+    assert!(ctx.host_config_path("legacy").exists());
+
+    let err = ssv
+        .remove("legacy", &RemoveOptions { force: true, ..Default::default() })
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("pass --force-foreign"), "{err}");
+    ssv.remove("legacy", &RemoveOptions { force_foreign: true, ..Default::default() }).unwrap();
+    assert!(!ctx.host_config_path("legacy").exists());
+}
+
+#[test]
+fn hand_edits_show_as_modified_until_recorded() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+    let path = ctx.host_config_path("db");
+    let config = fs::read_to_string(&path).unwrap();
+    fs::write(&path, format!("{config}ServerAliveInterval 30\n")).unwrap();
+    assert_eq!(ssv.ownership("db").unwrap(), Ownership::Modified);
+
+    // Edits from `ssv ui` are accepted, keeping the new directive.
+    ssv.record_edit("db").unwrap();
+    assert_eq!(ssv.ownership("db").unwrap(), Ownership::Managed);
+    ctx.assert_config_contains("db", "ServerAliveInterval 30");
+
+    // Restoring the old file does not match what ssv last wrote, yet it is still ssv's to remove.
+    fs::write(&path, &config).unwrap();
+    assert_eq!(ssv.ownership("db").unwrap(), Ownership::Modified);
+    ssv.remove("db", &RemoveOptions::default()).unwrap();
+}
+
+#[test]
+fn doctor_reports_and_adopts_drift() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    for host in ["edited", "gone", "kept"] {
+        ssv.generate(&GenerateOptions::new(host)).unwrap();
+    }
+    write_foreign(&ctx, "legacy");
+    let edited = ctx.host_config_path("edited");
+    fs::write(&edited, fs::read_to_string(&edited).unwrap().replace("HostName", "Hostname"))
+        .unwrap();
+    fs::remove_file(ctx.host_config_path("gone")).unwrap();
+
+    let report = ssv.doctor(&Adopt::Nothing).unwrap();
+    let drift: Vec<_> =
+        report.drift.iter().map(|drift| (drift.host.as_str(), drift.kind)).collect();
+    assert_eq!(
+        drift,
+        [
+            ("edited", DriftKind::Modified),
+            ("gone", DriftKind::Missing),
+            ("legacy", DriftKind::Foreign)
+        ]
+    );
+    assert_eq!(report.checked, 3);
+    assert!(report.adopted.is_empty());
+
+    // Without hosts, only ssv's own configs are adopted.
+    let adopted = ssv.doctor(&Adopt::Changed).unwrap();
+    assert_eq!(adopted.adopted, ["edited", "gone"]);
+    ctx.assert_config_contains("edited", "Hostname edited");
+    assert!(!state_mentions(&ctx, "gone"));
+    assert_eq!(ssv.ownership("legacy").unwrap(), Ownership::Foreign);
+
+    let err = ssv.doctor(&Adopt::Hosts(vec!["kept".into()])).unwrap_err().to_string();
+    assert!(err.contains("'kept' has not drifted"), "{err}");
+    let adopted = ssv.doctor(&Adopt::Hosts(vec!["legacy".into()])).unwrap();
+    assert_eq!(adopted.adopted, ["legacy"]);
+    assert_eq!(ssv.ownership("legacy").unwrap(), Ownership::Managed);
+    assert!(ssv.doctor(&Adopt::Nothing).unwrap().drift.is_empty());
+
+    // A stamped config whose state entry was lost, e.g. after copying ~/.ssh.
+    fs::remove_file(ctx.home().join(".ssh/.ssv/managed.json")).unwrap();
+    let report = ssv.doctor(&Adopt::Changed).unwrap();
+    assert!(report.drift.iter().all(|drift| drift.kind == DriftKind::Untracked));
+    assert_eq!(report.drift.len(), 3);
+    assert!(report.adopted.is_empty());
+}
+
+#[test]
+fn forged_headers_do_not_make_a_config_managed() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+    let stamped = fs::read_to_string(ctx.host_config_path("db")).unwrap();
+    let forged = stamped.replace("Host db", "Host evil").replace("HostName db", "HostName evil");
+    let header = ssv_header(&forged);
+    let path = ctx.host_config_path("evil");
+    fs::write(&path, format!("{header}{}", forged.split_once('\n').unwrap().1)).unwrap();
+
+    assert_eq!(ssv.ownership("evil").unwrap(), Ownership::Foreign);
+    let err = ssv.remove("evil", &RemoveOptions::default()).unwrap_err().to_string();
+    assert!(err.contains("was not written by ssv"), "{err}");
+}
+
+#[test]
+fn configs_from_before_ownership_tracking_are_migrated() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+    let mut pattern = GenerateOptions::for_patterns(vec!["*.internal".into()]);
+    pattern.options = vec![("ForwardAgent".into(), "no".into())];
+    let wildcard = ssv.generate(&pattern).unwrap().host;
+    write_foreign(&ctx, "legacy");
+    // What an older ssv left behind: no headers and no state file.
+    for host in ["db", wildcard.as_str()] {
+        let path = ctx.host_config_path(host);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::write(&path, contents.split_once('\n').unwrap().1).unwrap();
+    }
+    fs::remove_file(ctx.home().join(".ssh/.ssv/managed.json")).unwrap();
+
+    assert_eq!(ssv.ownership("db").unwrap(), Ownership::Managed);
+    assert_eq!(ssv.ownership(&wildcard).unwrap(), Ownership::Managed);
+    assert_eq!(ssv.ownership("legacy").unwrap(), Ownership::Foreign);
+    let report = ssv.doctor(&Adopt::Nothing).unwrap();
+    assert_eq!(report.drift.len(), 1);
+    assert_eq!(report.drift[0].host, "legacy");
+
+    // The migration is saved along with the next change.
+    ssv.generate(&GenerateOptions::new("web")).unwrap();
+    assert!(state_mentions(&ctx, "db"));
+    assert!(!state_mentions(&ctx, "legacy"));
+}
+
+#[test]
+fn apply_leaves_foreign_configs_out_of_pruning() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    ssv.generate(&GenerateOptions::new("old")).unwrap();
+    write_foreign(&ctx, "legacy");
+    let manifest = ctx.work_dir().join("ssv.toml");
+    fs::write(&manifest, "prune = true\n\n[[host]]\nalias = \"db\"\n").unwrap();
+
+    ssv.apply(&manifest, false).unwrap();
+    assert!(ctx.host_config_path("legacy").exists());
+    assert!(!ctx.host_config_path("old").exists());
+    assert_eq!(ssv.ownership("db").unwrap(), Ownership::Managed);
+}
+
+#[test]
+#[serial]
+fn cli_marks_foreign_configs_and_doctor_fails_on_drift() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["generate", "--host", "db"]).assert().success();
+    write_foreign(&ctx, "legacy");
+
+    ctx.cli().arg("list").assert().success().stdout("db\nlegacy (foreign)\n");
+    ctx.cli()
+        .args(["list", "--long"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("STATUS"))
+        .stdout(predicate::str::contains("foreign"));
+    ctx.cli()
+        .args(["remove", "--host", "legacy", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --force-foreign"));
+    ctx.cli()
+        .arg("doctor")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("legacy"))
+        .stdout(predicate::str::contains("was not written by ssv"));
+    ctx.cli()
+        .args(["doctor", "--adopt"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("`ssv doctor --adopt <HOST>` takes over"));
+    ctx.cli()
+        .args(["doctor", "--adopt", "legacy"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Adopted legacy as they are now"));
+    ctx.cli()
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All 2 host configs match"));
+}
+
+/// An ssv header with the right checksum for the body of `config`.
+fn ssv_header(config: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let body = config.split_once('\n').unwrap().1;
+    let sum: String =
+        Sha256::digest(body.as_bytes()).iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("# Managed by ssv 0.1.0; sha256={sum}\n")
+}
+
+fn state_mentions(ctx: &TestContext, host: &str) -> bool {
+    fs::read_to_string(ctx.home().join(".ssh/.ssv/managed.json"))
+        .unwrap_or_default()
+        .contains(&format!("\"{host}\""))
+}
+
+#[test]
+#[serial]
+fn configs_from_the_first_releases_stay_managed_after_upgrading() {
+    let ctx = TestContext::new();
+    // Exactly what the first releases wrote: no header, no sidecar, no state file.
+    let conf_dir = ctx.home().join(".ssh/conf.d");
+    fs::create_dir_all(&conf_dir).unwrap();
+    fs::write(
+        conf_dir.join("old.example.conf"),
+        "Host old.example\nHostName old.example\nIdentityFile ~/.ssh/id_ed25519_old.example\n\
+         IdentitiesOnly yes\n",
+    )
+    .unwrap();
+    fs::write(
+        conf_dir.join("ported.example.conf"),
+        "Host ported.example\nHostName ported.example\nUser deploy\nPort 2222\n\
+         IdentityFile ~/.ssh/id_rsa_ported.example\nIdentitiesOnly yes\n",
+    )
+    .unwrap();
+    fs::write(ctx.private_key_path("ed25519", "old.example"), "PRIVATE-ed25519\n").unwrap();
+    fs::write(ctx.public_key_path("ed25519", "old.example"), "ssh-ed25519 AAAATESTKEY old\n")
+        .unwrap();
+
+    ctx.cli()
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("old.example"))
+        .stdout(predicate::str::contains("foreign").not());
+    ctx.cli().arg("doctor").assert().success();
+    ctx.cli().args(["rm", "--host", "old.example", "--yes"]).assert().success();
+    assert!(!conf_dir.join("old.example.conf").exists());
+    assert!(!ctx.private_key_path("ed25519", "old.example").exists());
+
+    // A first-release config edited by hand is not taken over.
+    assert!(!state_mentions(&ctx, "ported.example"));
+    let ported = conf_dir.join("ported.example.conf");
+    fs::write(
+        &ported,
+        fs::read_to_string(&ported).unwrap().replace("2222", "2200\nForwardAgent yes"),
+    )
+    .unwrap();
+    ctx.cli().arg("doctor").assert().code(2).stdout(predicate::str::contains("ported.example"));
+}
@@ -0,0 +1,185 @@
+//! Private key storage: the encrypted vault, stores injected into `Ssv`, and `ssv lock`/`unlock`.
+
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use ssv::{
+    FileStore, GenerateOptions, KeyBackend, KeyStore, Operation, RemoveOptions, SshPaths, Ssv,
+    VaultStore,
+};
+use std::fs;
+use std::path::PathBuf;
+
+fn vault(ctx: &TestContext) -> Ssv {
+    let ssv = ctx.ssv();
+    ssv.config_set("storage", "vault").unwrap();
+    ssv.with_vault_passphrase("open sesame")
+}
+
+#[test]
+fn vault_keeps_generated_keys_locked_until_unlocked() {
+    let ctx = TestContext::isolated();
+    let ssv = vault(&ctx);
+
+    let generated = ssv.generate(&GenerateOptions::new("db")).unwrap();
+    assert!(!generated.private_key.exists());
+    assert!(ctx.public_key_path("ed25519", "db").exists());
+    ctx.assert_config_contains("db", "IdentityFile ~/.ssh/id_ed25519_db");
+    let sealed = fs::read(ctx.home().join(".ssh/.ssv/vault")).unwrap();
+    assert!(!String::from_utf8_lossy(&sealed).contains("PRIVATE-ed25519"));
+
+    let unlocked = ssv.unlock("db").unwrap();
+    assert!(unlocked.changed);
+    assert_eq!(fs::read_to_string(&generated.private_key).unwrap(), "PRIVATE-ed25519\n");
+    assert!(!ssv.unlock("db").unwrap().changed);
+
+    let locked = ssv.lock("db").unwrap();
+    assert!(locked.changed);
+    assert!(!generated.private_key.exists());
+    assert!(!ssv.lock("db").unwrap().changed);
+}
+
+/// An ssh-add stand-in that saves its arguments and standard input next to itself.
+fn ssh_add_stub(ctx: &TestContext) -> PathBuf {
+    let path = ctx.work_dir().join("ssh-add");
+    fs::write(
+        &path,
+        "#!/usr/bin/env sh\nprintf '%s\\n' \"$*\" > \"$0.args\"\ncat > \"$0.stdin\"\n",
+    )
+    .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    path
+}
+
+#[test]
+fn agent_unlock_never_writes_the_key_to_disk() {
+    let ctx = TestContext::isolated();
+    let ssh_add = ssh_add_stub(&ctx);
+    let ssv = Ssv::with_paths(SshPaths::new(ctx.home()).with_ssh_add(&ssh_add))
+        .with_backend(KeyBackend::Test);
+    ssv.config_set("storage", "vault").unwrap();
+    let ssv = ssv.with_vault_passphrase("open sesame");
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+
+    ssv.unlock_to_agent("db", Some("1h")).unwrap();
+    assert_eq!(fs::read_to_string(ssh_add.with_extension("args")).unwrap(), "-t 1h -\n");
+    assert_eq!(fs::read_to_string(ssh_add.with_extension("stdin")).unwrap(), "PRIVATE-ed25519\n");
+    assert!(!ctx.private_key_path("ed25519", "db").exists());
+    let last = ssv.history(Some("db")).unwrap().pop().unwrap();
+    assert_eq!(last.operation, Operation::Unlock);
+    assert!(last.files.is_empty());
+
+    ssv.unlock_to_agent("db", None).unwrap();
+    assert_eq!(fs::read_to_string(ssh_add.with_extension("args")).unwrap(), "-\n");
+    assert!(ssv.remove("db", &RemoveOptions::default()).is_ok());
+}
+
+#[test]
+fn vault_needs_the_right_passphrase() {
+    let ctx = TestContext::isolated();
+    vault(&ctx).generate(&GenerateOptions::new("db")).unwrap();
+
+    assert!(ctx.ssv().needs_vault_passphrase().unwrap());
+    let err = ctx.ssv().unlock("db").unwrap_err().to_string();
+    assert!(err.contains("SSV_VAULT_PASSPHRASE"), "{err}");
+    assert!(ctx.ssv().with_vault_passphrase("guess").unlock("db").is_err());
+    assert!(!ctx.private_key_path("ed25519", "db").exists());
+}
+
+#[test]
+fn lock_migrates_plain_keys_into_the_vault() {
+    let ctx = TestContext::isolated();
+    let plain = ctx.ssv();
+    plain.generate(&GenerateOptions::new("db")).unwrap();
+    let err = plain.lock("db").unwrap_err().to_string();
+    assert!(err.contains("stored as plain files"), "{err}");
+
+    let ssv = vault(&ctx);
+    assert!(ssv.lock("db").unwrap().changed);
+    assert!(!ctx.private_key_path("ed25519", "db").exists());
+    assert!(ssv.unlock("db").unwrap().changed);
+}
+
+#[test]
+fn rotate_and_remove_reach_into_the_vault() {
+    let ctx = TestContext::isolated();
+    let ssv = vault(&ctx);
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+    ssv.unlock("db").unwrap();
+
+    // The new key goes straight into the vault and the stale unlocked copy is deleted.
+    let report = ssv.rotate("db").unwrap();
+    assert!(!report.key.exists());
+    assert!(ssv.unlock("db").unwrap().changed);
+    ssv.lock("db").unwrap();
+
+    let plan = ssv.removal_plan("db").unwrap();
+    assert_eq!(plan.stored, [ctx.private_key_path("ed25519", "db")]);
+    ssv.remove("db", &RemoveOptions::default()).unwrap();
+    let store = VaultStore::new(ctx.home().join(".ssh/.ssv/vault"), "open sesame");
+    assert_eq!(store.get("id_ed25519_db").unwrap(), None);
+}
+
+#[test]
+fn injected_store_holds_keys_elsewhere() {
+    let ctx = TestContext::isolated();
+    let keys = ctx.work_dir().join("keys");
+    fs::create_dir(&keys).unwrap();
+    let ssv = ctx.ssv().with_key_store(FileStore::new(&keys));
+
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+    assert_eq!(fs::read_to_string(keys.join("id_ed25519_db")).unwrap(), "PRIVATE-ed25519\n");
+    assert!(!ctx.private_key_path("ed25519", "db").exists());
+    assert!(!ssv.needs_vault_passphrase().unwrap());
+
+    let err = ctx.ssv().config_set("storage", "keychain").unwrap_err().to_string();
+    assert!(err.contains("unknown storage 'keychain'"), "{err}");
+}
+
+#[test]
+#[serial]
+fn cli_locks_and_unlocks_with_the_passphrase_from_the_environment() {
+    let ctx = TestContext::new();
+    ctx.cli().args(["config", "set", "storage", "vault"]).assert().success();
+    ctx.cli()
+        .args(["generate", "--host", "db"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SSV_VAULT_PASSPHRASE"));
+
+    let run = |args: &[&str]| {
+        let mut command = ctx.cli();
+        command.env("SSV_VAULT_PASSPHRASE", "open sesame").args(args);
+        command
+    };
+    run(&["generate", "--host", "db"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ssv unlock --agent --host db"));
+    run(&["unlock"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Unlocked the key of 'db'"));
+    assert!(ctx.private_key_path("ed25519", "db").exists());
+    run(&["lock", "--host", "db"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Locked the key of 'db'"));
+    assert!(!ctx.private_key_path("ed25519", "db").exists());
+
+    let ssh_add = ssh_add_stub(&ctx);
+    run(&["unlock", "--agent", "--lifetime", "8h"])
+        .env("SSV_SSH_ADD_PATH", &ssh_add)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added the key of 'db' to ssh-agent for 8h"));
+    assert_eq!(fs::read_to_string(ssh_add.with_extension("args")).unwrap(), "-t 8h -\n");
+    assert!(!ctx.private_key_path("ed25519", "db").exists());
+    run(&["unlock", "--lifetime", "8h"]).assert().failure();
+}
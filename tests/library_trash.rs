@@ -0,0 +1,151 @@
+//! Removed hosts kept in `~/.ssh/.ssv/trash`, `ssv restore` and the `trash_retention` setting.
+
+mod common;
+
+use common::TestContext;
+use predicates::prelude::*;
+use serial_test::serial;
+use ssv::{FileStore, GenerateOptions, KeyBackend, Operation, Ownership, RemoveOptions};
+use std::fs;
+
+#[test]
+fn removed_hosts_can_be_restored_from_the_trash() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv().with_backend(KeyBackend::Native);
+    let generated = ssv.generate(&GenerateOptions::new("db")).unwrap();
+    let config = fs::read_to_string(ctx.host_config_path("db")).unwrap();
+    let private_key = fs::read(&generated.private_key).unwrap();
+
+    let report = ssv.remove("db", &RemoveOptions::default()).unwrap();
+    assert!(report.trashed);
+    assert!(!ctx.host_config_path("db").exists());
+    assert!(!generated.private_key.exists());
+    let trash = ssv.trash().unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].host, "db");
+    assert!(trash[0].files.contains(&generated.private_key));
+
+    let restored = ssv.restore("db").unwrap();
+    assert_eq!(restored.id, trash[0].id);
+    assert_eq!(fs::read_to_string(ctx.host_config_path("db")).unwrap(), config);
+    assert_eq!(fs::read(&generated.private_key).unwrap(), private_key);
+    assert_eq!(ssv.ownership("db").unwrap(), Ownership::Managed);
+    assert!(ssv.trash().unwrap().is_empty());
+
+    let history = ssv.history(Some("db")).unwrap();
+    let operations: Vec<_> = history.iter().map(|entry| entry.operation).collect();
+    assert_eq!(operations, [Operation::Generate, Operation::Remove, Operation::Restore]);
+    assert!(history[2].fingerprint.is_some());
+    assert_eq!(history[2].fingerprint, history[0].fingerprint);
+
+    let err = ssv.restore("db").unwrap_err().to_string();
+    assert!(err.contains("managed already"), "{err}");
+    ssv.remove("db", &RemoveOptions::default()).unwrap();
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+    let err = ssv.restore("db").unwrap_err().to_string();
+    assert!(err.contains("managed already"), "{err}");
+}
+
+#[test]
+fn stored_keys_come_back_into_the_key_store() {
+    let ctx = TestContext::isolated();
+    let keys = ctx.work_dir().join("keys");
+    fs::create_dir(&keys).unwrap();
+    let ssv = ctx.ssv().with_key_store(FileStore::new(&keys));
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+
+    ssv.remove("db", &RemoveOptions::default()).unwrap();
+    assert!(!keys.join("id_ed25519_db").exists());
+    ssv.restore("db").unwrap();
+    assert_eq!(fs::read_to_string(keys.join("id_ed25519_db")).unwrap(), "PRIVATE-ed25519\n");
+    assert_eq!(fs::read_dir(&keys).unwrap().count(), 1);
+}
+
+#[test]
+fn retention_limits_how_long_removed_hosts_are_kept() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    ssv.generate(&GenerateOptions::new("old")).unwrap();
+    ssv.remove("old", &RemoveOptions::default()).unwrap();
+    let trash = ctx.home().join(".ssh/.ssv/trash");
+    let entry = trash.join(&ssv.trash().unwrap()[0].id).join("entry.json");
+    let contents = fs::read_to_string(&entry).unwrap();
+    let removed_at = ssv.trash().unwrap()[0].removed_at.clone();
+    fs::write(&entry, contents.replace(&removed_at, "2020-01-01T00:00:00Z")).unwrap();
+
+    // Expired entries go whenever something new is put into the trash.
+    ssv.generate(&GenerateOptions::new("db")).unwrap();
+    ssv.remove("db", &RemoveOptions::default()).unwrap();
+    let hosts: Vec<_> = ssv.trash().unwrap().into_iter().map(|entry| entry.host).collect();
+    assert_eq!(hosts, ["db"]);
+
+    ssv.config_set("trash_retention", "0").unwrap();
+    ssv.generate(&GenerateOptions::new("web")).unwrap();
+    assert!(!ssv.remove("web", &RemoveOptions::default()).unwrap().trashed);
+    assert!(ssv.trash().unwrap().is_empty());
+    assert!(!ctx.private_key_path("ed25519", "web").exists());
+
+    let err = ssv.config_set("trash_retention", "soon").unwrap_err().to_string();
+    assert!(err.contains("number of days"), "{err}");
+}
+
+#[test]
+fn expired_entries_are_neither_listed_nor_restored() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    ssv.generate(&GenerateOptions::new("old")).unwrap();
+    ssv.remove("old", &RemoveOptions::default()).unwrap();
+    let trash = ctx.home().join(".ssh/.ssv/trash");
+    let id = ssv.trash().unwrap()[0].id.clone();
+    let entry = trash.join(&id).join("entry.json");
+    let contents = fs::read_to_string(&entry).unwrap();
+    let removed_at = ssv.trash().unwrap()[0].removed_at.clone();
+    fs::write(&entry, contents.replace(&removed_at, "2020-01-01T00:00:00Z")).unwrap();
+
+    assert!(ssv.trash().unwrap().is_empty());
+    let err = ssv.restore("old").unwrap_err().to_string();
+    assert!(err.contains("the trash holds no 'old'"), "{err}");
+    assert!(!trash.join(&id).exists());
+    assert!(!ctx.host_config_path("old").exists());
+}
+
+#[test]
+fn removing_an_unknown_host_leaves_no_trash_entry() {
+    let ctx = TestContext::isolated();
+    let ssv = ctx.ssv();
+    let err = ssv.remove("ghost", &RemoveOptions::default()).unwrap_err().to_string();
+    assert!(err.contains("'ghost' was not found"), "{err}");
+    assert!(ssv.trash().unwrap().is_empty());
+    assert!(!ctx.home().join(".ssh/.ssv/trash").exists());
+}
+
+#[test]
+#[serial]
+fn cli_lists_and_restores_the_trash() {
+    let ctx = TestContext::new();
+    ctx.cli().arg("restore").assert().success().stdout("The trash is empty.\n");
+    ctx.cli().args(["generate", "--host", "db"]).assert().success();
+    ctx.cli()
+        .args(["remove", "--host", "db", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("`ssv restore --host db` brings them back"));
+
+    ctx.cli().arg("restore").assert().success().stdout(predicate::str::contains("  db\n"));
+    ctx.cli()
+        .args(["restore", "--host", "db"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 'db'"));
+    ctx.cli().arg("list").assert().success().stdout("db\n");
+    ctx.cli()
+        .args(["restore", "--host", "db"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("managed already"));
+    ctx.cli()
+        .args(["restore", "--host", "web"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("the trash holds no 'web'"));
+}